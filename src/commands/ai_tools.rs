@@ -0,0 +1,304 @@
+use std::time::Duration;
+
+use chrono_tz::Tz;
+use serde::Deserialize;
+use serde_json::json;
+
+use super::{
+    github::fetch_github,
+    mc_server::{DEFAULT_PORT, DEFAULT_PROTOCOL_VERSION},
+    misc::get_time_and_tz,
+    reminders::create_reminder,
+};
+use crate::{
+    config::Config,
+    utils::{
+        bot::user_in_whitelist,
+        llm::{ToolCall, ToolSpec},
+        server::ping::ping,
+        youtube::{self, YouTubeClient},
+    },
+};
+
+/// Longest reminder the assistant may set.
+const MAX_REMINDER: Duration = Duration::from_secs(365 * 24 * 60 * 60);
+/// Tool results are cut to this many characters before they are sent to the model.
+const MAX_RESULT_LEN: usize = 4000;
+
+/// The tools the assistant may call on behalf of a user.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Tool {
+    MinecraftStatus,
+    GitHubLookup,
+    YouTubeVideo,
+    SetReminder,
+    CurrentTime,
+}
+
+const TOOLS: [Tool; 5] = [
+    Tool::MinecraftStatus,
+    Tool::GitHubLookup,
+    Tool::YouTubeVideo,
+    Tool::SetReminder,
+    Tool::CurrentTime,
+];
+
+impl Tool {
+    fn name(self) -> &'static str {
+        match self {
+            Tool::MinecraftStatus => "minecraft_server_status",
+            Tool::GitHubLookup => "github_lookup",
+            Tool::YouTubeVideo => "youtube_video",
+            Tool::SetReminder => "set_reminder",
+            Tool::CurrentTime => "current_time",
+        }
+    }
+
+    fn spec(self) -> ToolSpec {
+        let (description, parameters) = match self {
+            Tool::MinecraftStatus => (
+                "Pings a Minecraft server and returns whether it is online, its version, player count and MOTD.",
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "host": { "type": "string", "description": "Server hostname or IP" },
+                        "port": { "type": "integer", "description": "Server port, 25565 by default" }
+                    },
+                    "required": ["host"]
+                }),
+            ),
+            Tool::GitHubLookup => (
+                "Looks up a GitHub user (`name`) or repository (`owner/repo`).",
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "query": { "type": "string", "description": "Username or owner/repo" }
+                    },
+                    "required": ["query"]
+                }),
+            ),
+            Tool::YouTubeVideo => (
+                "Returns title, channel, publish date and statistics of a YouTube video.",
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "url": { "type": "string", "description": "YouTube video URL" }
+                    },
+                    "required": ["url"]
+                }),
+            ),
+            Tool::SetReminder => (
+                "Reminds the user by direct message after a delay.",
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "when": { "type": "string", "description": "Delay like `2h`, `30m` or `1day 3h`" },
+                        "message": { "type": "string", "description": "What to remind the user of" }
+                    },
+                    "required": ["when", "message"]
+                }),
+            ),
+            Tool::CurrentTime => (
+                "Returns the current date and time in a time zone.",
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "timezone": { "type": "string", "description": "IANA time zone like `Europe/Berlin`" }
+                    },
+                    "required": ["timezone"]
+                }),
+            ),
+        };
+
+        ToolSpec {
+            name: self.name(),
+            description,
+            parameters,
+        }
+    }
+
+    /// Whether the user may use the tool, following the command whitelists.
+    fn allowed(self, config: &Config, user_id: u64) -> bool {
+        match self {
+            Tool::MinecraftStatus => user_in_whitelist(
+                config.ping_whitelist_active,
+                &config.ping_whitelist,
+                user_id,
+            ),
+            Tool::YouTubeVideo => {
+                config
+                    .youtube_token
+                    .as_deref()
+                    .is_some_and(|k| !k.is_empty())
+                    && user_in_whitelist(
+                        config.youtube_whitelist_active,
+                        &config.youtube_whitelist,
+                        user_id,
+                    )
+            }
+            Tool::GitHubLookup | Tool::SetReminder | Tool::CurrentTime => true,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct PingArgs {
+    host: String,
+    port: Option<u16>,
+}
+
+#[derive(Deserialize)]
+struct GitHubArgs {
+    query: String,
+}
+
+#[derive(Deserialize)]
+struct YouTubeArgs {
+    url: String,
+}
+
+#[derive(Deserialize)]
+struct ReminderArgs {
+    when: String,
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct TimeArgs {
+    timezone: String,
+}
+
+/// The tools available to one user, with the settings needed to run them.
+pub(super) struct ToolBox {
+    user_id: u64,
+    tools: Vec<Tool>,
+    youtube_token: Option<String>,
+    github_token: Option<String>,
+}
+
+impl ToolBox {
+    pub(super) fn new(config: &Config, user_id: u64) -> Self {
+        Self {
+            user_id,
+            tools: TOOLS
+                .into_iter()
+                .filter(|tool| tool.allowed(config, user_id))
+                .collect(),
+            youtube_token: config.youtube_token.clone(),
+            github_token: config.github_token.clone(),
+        }
+    }
+
+    pub(super) fn specs(&self) -> Vec<ToolSpec> {
+        self.tools.iter().map(|tool| tool.spec()).collect()
+    }
+
+    /// Runs a tool call and returns its result for the model.
+    /// Failures are returned as text so the model can explain them.
+    pub(super) async fn call(&self, call: &ToolCall) -> String {
+        let Some(tool) = self
+            .tools
+            .iter()
+            .copied()
+            .find(|tool| tool.name() == call.function.name)
+        else {
+            return format!(
+                "Error: unknown or unavailable tool `{}`",
+                call.function.name
+            );
+        };
+
+        let arguments = if call.function.arguments.trim().is_empty() {
+            "{}"
+        } else {
+            call.function.arguments.as_str()
+        };
+        let result = match tool {
+            Tool::MinecraftStatus => self.minecraft_status(arguments).await,
+            Tool::GitHubLookup => self.github_lookup(arguments).await,
+            Tool::YouTubeVideo => self.youtube_video(arguments).await,
+            Tool::SetReminder => self.set_reminder(arguments).await,
+            Tool::CurrentTime => self.current_time(arguments).await,
+        };
+
+        let text = result.unwrap_or_else(|e| format!("Error: {}", e));
+        text.chars().take(MAX_RESULT_LEN).collect()
+    }
+
+    async fn minecraft_status(&self, arguments: &str) -> Result<String, String> {
+        let args: PingArgs = parse_args(arguments)?;
+        match ping(
+            &args.host,
+            args.port.unwrap_or(DEFAULT_PORT),
+            DEFAULT_PROTOCOL_VERSION,
+        )
+        .await
+        {
+            Ok(status) => Ok(json!({
+                "online": true,
+                "version": status.version.name,
+                "players_online": status.players.online,
+                "players_max": status.players.max,
+                "motd": status.description
+            })
+            .to_string()),
+            Err(e) => Ok(json!({ "online": false, "error": e.to_string() }).to_string()),
+        }
+    }
+
+    async fn github_lookup(&self, arguments: &str) -> Result<String, String> {
+        let args: GitHubArgs = parse_args(arguments)?;
+        match fetch_github(self.github_token.as_deref(), args.query.trim(), false).await {
+            Ok(Some(lookup)) => serde_json::to_string(&lookup).map_err(|e| e.to_string()),
+            Ok(None) => Err("GitHub user or repository not found".into()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    async fn youtube_video(&self, arguments: &str) -> Result<String, String> {
+        let args: YouTubeArgs = parse_args(arguments)?;
+        let id = youtube::parse_url(&args.url)
+            .and_then(|link| link.video_id)
+            .ok_or("invalid YouTube URL")?;
+        let key = self
+            .youtube_token
+            .as_deref()
+            .ok_or("no YouTube API key configured")?;
+        match YouTubeClient::new(key).video(&id).await {
+            Ok(Some(video)) => serde_json::to_string(&video).map_err(|e| e.to_string()),
+            Ok(None) => Err("video not found".into()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    async fn set_reminder(&self, arguments: &str) -> Result<String, String> {
+        let args: ReminderArgs = parse_args(arguments)?;
+        let duration = humantime::parse_duration(args.when.trim())
+            .map_err(|_| "invalid duration, use something like `2h` or `30m`".to_string())?;
+        if duration > MAX_REMINDER {
+            return Err("reminders can be at most a year ahead".into());
+        }
+
+        let id = create_reminder(self.user_id, duration, args.message).await;
+        Ok(json!({
+            "id": id,
+            "in": humantime::format_duration(duration).to_string(),
+            "delivery": "direct message"
+        })
+        .to_string())
+    }
+
+    async fn current_time(&self, arguments: &str) -> Result<String, String> {
+        let args: TimeArgs = parse_args(arguments)?;
+        let timezone = args.timezone.trim().to_string();
+        if timezone.parse::<Tz>().is_err() {
+            return Err(format!("unknown time zone `{}`", timezone));
+        }
+        let (time, zone) = get_time_and_tz(Some(timezone)).await;
+        Ok(json!({ "time": time, "timezone": zone }).to_string())
+    }
+}
+
+fn parse_args<T: for<'de> Deserialize<'de>>(arguments: &str) -> Result<T, String> {
+    serde_json::from_str(arguments).map_err(|e| format!("invalid arguments: {}", e))
+}
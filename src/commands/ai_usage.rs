@@ -0,0 +1,436 @@
+use std::{
+    collections::HashMap,
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+use chrono::{DateTime, Datelike, Months, TimeZone, Utc};
+use once_cell::sync::Lazy;
+use poise::CreateReply;
+use serde::{Deserialize, Serialize};
+use serenity::all::{CreateEmbed, CreateEmbedFooter};
+use tokio::{fs, sync::RwLock};
+
+use crate::{
+    Context, Error,
+    config::Config,
+    utils::{
+        bot::{self, error_text, is_admin},
+        llm::{self, TokenUsage},
+    },
+};
+
+const USAGE_PATH: &str = "ai_usage.json";
+/// Records older than this are dropped, which keeps the previous month for the report.
+const RETENTION: Duration = Duration::from_secs(62 * 24 * 60 * 60);
+const REPORT_TOP: usize = 10;
+
+/// Tokens used by a single AI request.
+#[derive(Serialize, Deserialize, Clone)]
+struct UsageRecord {
+    time: SystemTime,
+    user_id: u64,
+    guild_id: Option<u64>,
+    /// Model id as `provider/model`.
+    model: String,
+    prompt_tokens: u64,
+    completion_tokens: u64,
+    /// The provider did not report usage, so the tokens were estimated.
+    #[serde(default)]
+    estimated: bool,
+}
+
+static USAGE: Lazy<RwLock<Vec<UsageRecord>>> = Lazy::new(|| RwLock::new(Vec::new()));
+
+/// Load usage records from disk into memory at startup.
+pub async fn load_usage_from_file() -> Result<(), std::io::Error> {
+    if Path::new(USAGE_PATH).exists() {
+        let data = fs::read_to_string(USAGE_PATH).await?;
+        *USAGE.write().await = serde_json::from_str(&data)?;
+    }
+    Ok(())
+}
+
+async fn save_usage_to_file() {
+    let json = {
+        let usage = USAGE.read().await;
+        serde_json::to_string(&*usage)
+    };
+    match json {
+        Ok(json) => {
+            if let Err(e) = fs::write(USAGE_PATH, json).await {
+                tracing::warn!("Failed to save AI usage: {}", e);
+            }
+        }
+        Err(e) => tracing::warn!("Failed to serialize AI usage: {}", e),
+    }
+}
+
+#[derive(Default, Clone, Copy)]
+struct Totals {
+    prompt: u64,
+    completion: u64,
+    estimated: bool,
+}
+
+impl Totals {
+    fn add(&mut self, record: &UsageRecord) {
+        self.prompt += record.prompt_tokens;
+        self.completion += record.completion_tokens;
+        self.estimated |= record.estimated;
+    }
+
+    fn total(&self) -> u64 {
+        self.prompt + self.completion
+    }
+}
+
+/// Start of the current UTC day and calendar month.
+fn period_starts(now: DateTime<Utc>) -> (DateTime<Utc>, DateTime<Utc>) {
+    let day = now.date_naive().and_hms_opt(0, 0, 0).unwrap_or_default();
+    let month = day.with_day(1).unwrap_or(day);
+    (Utc.from_utc_datetime(&day), Utc.from_utc_datetime(&month))
+}
+
+/// Sums the tokens of all records since `start` matching `filter`.
+fn totals_since(
+    records: &[UsageRecord],
+    start: DateTime<Utc>,
+    filter: impl Fn(&UsageRecord) -> bool,
+) -> Totals {
+    let start = SystemTime::from(start);
+    let mut totals = Totals::default();
+    for record in records.iter().filter(|r| r.time >= start && filter(r)) {
+        totals.add(record);
+    }
+    totals
+}
+
+/// Estimated cost in USD, if a price is configured for the model.
+fn cost(config: &Config, model: &str, totals: &Totals) -> Option<f64> {
+    let (provider, name) = model.split_once('/')?;
+    let price = config.llm_providers.get(provider)?.prices.get(name)?;
+    Some(
+        (totals.prompt as f64 * price.prompt + totals.completion as f64 * price.completion)
+            / 1_000_000.0,
+    )
+}
+
+/// Records the tokens used by a request. Without reported usage the tokens
+/// are estimated from the prompt estimate and the answer.
+pub(super) async fn record_usage(
+    user_id: u64,
+    guild_id: Option<u64>,
+    model: &str,
+    reported: Option<TokenUsage>,
+    prompt_estimate: u64,
+    answer: &str,
+) {
+    let (usage, estimated) = match reported {
+        Some(usage) => (usage, false),
+        None => (
+            TokenUsage {
+                prompt_tokens: prompt_estimate,
+                completion_tokens: llm::estimate_text_tokens(answer),
+            },
+            true,
+        ),
+    };
+
+    {
+        let mut records = USAGE.write().await;
+        let cutoff = SystemTime::now() - RETENTION;
+        records.retain(|r| r.time >= cutoff);
+        records.push(UsageRecord {
+            time: SystemTime::now(),
+            user_id,
+            guild_id,
+            model: model.to_string(),
+            prompt_tokens: usage.prompt_tokens,
+            completion_tokens: usage.completion_tokens,
+            estimated,
+        });
+    }
+    save_usage_to_file().await;
+}
+
+/// Checks the configured budgets before a request.
+/// Returns a message explaining which budget is used up.
+pub(super) async fn check_budget(
+    config: &Config,
+    user_id: u64,
+    guild_id: Option<u64>,
+) -> Result<(), String> {
+    if config.admin_list.contains(&user_id.to_string()) {
+        return Ok(());
+    }
+
+    let now = Utc::now();
+    let (day, month) = period_starts(now);
+    let next_day = day + chrono::Duration::days(1);
+    let next_month = month + Months::new(1);
+    let budgets = &config.ai_budgets;
+    let records = USAGE.read().await;
+
+    let exceeded = |label: &str, limit: u64, used: u64, reset: DateTime<Utc>| {
+        format!(
+            "You reached {} AI budget ({} of {} tokens). It resets <t:{}:R>.",
+            label,
+            used,
+            limit,
+            reset.timestamp()
+        )
+    };
+
+    for (label, limit, start, reset) in [
+        ("your daily", budgets.user_daily, day, next_day),
+        ("your monthly", budgets.user_monthly, month, next_month),
+    ] {
+        if let Some(limit) = limit {
+            let used = totals_since(&records, start, |r| r.user_id == user_id).total();
+            if used >= limit {
+                return Err(exceeded(label, limit, used, reset));
+            }
+        }
+    }
+
+    if let Some(guild_id) = guild_id {
+        for (label, limit, start, reset) in [
+            ("this server's daily", budgets.guild_daily, day, next_day),
+            (
+                "this server's monthly",
+                budgets.guild_monthly,
+                month,
+                next_month,
+            ),
+        ] {
+            if let Some(limit) = limit {
+                let used = totals_since(&records, start, |r| r.guild_id == Some(guild_id)).total();
+                if used >= limit {
+                    return Err(exceeded(label, limit, used, reset));
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn usage_line(totals: &Totals, limit: Option<u64>) -> String {
+    let mut line = format!(
+        "{}{} tokens ({} prompt, {} completion)",
+        if totals.estimated { "~" } else { "" },
+        totals.total(),
+        totals.prompt,
+        totals.completion
+    );
+    if let Some(limit) = limit {
+        line.push_str(&format!("\nBudget: {} / {}", totals.total(), limit));
+    }
+    line
+}
+
+/// Cuts a list to Discord's field value limit at a line boundary.
+fn field_text(text: &str) -> String {
+    let mut out = String::new();
+    for line in text.lines() {
+        if out.len() + line.len() + 1 > 1024 {
+            break;
+        }
+        out.push_str(line);
+        out.push('\n');
+    }
+    out
+}
+
+fn format_cost(cost: f64) -> String {
+    format!("${:.4}", cost)
+}
+
+#[poise::command(slash_command)]
+/// Shows how many AI tokens you used today and this month.
+pub async fn ai_usage(
+    ctx: Context<'_>,
+    #[description = "Send the response directly to you?"] ephemeral: Option<bool>,
+) -> Result<(), Error> {
+    let ephemeral = bot::defer_based_on_ephemeral(ctx, ephemeral).await?;
+
+    let user_id = ctx.author().id.get();
+    let guild_id = ctx.guild_id().map(|g| g.get());
+    let (day, month) = period_starts(Utc::now());
+    let config = ctx.data().config.read().await;
+    let records = USAGE.read().await;
+
+    let today = totals_since(&records, day, |r| r.user_id == user_id);
+    let this_month = totals_since(&records, month, |r| r.user_id == user_id);
+
+    let mut per_model: HashMap<&str, Totals> = HashMap::new();
+    let month_start = SystemTime::from(month);
+    for record in records
+        .iter()
+        .filter(|r| r.user_id == user_id && r.time >= month_start)
+    {
+        per_model.entry(&record.model).or_default().add(record);
+    }
+    let month_cost: f64 = per_model
+        .iter()
+        .filter_map(|(model, totals)| cost(&config, model, totals))
+        .sum();
+
+    let mut embed = CreateEmbed::new()
+        .title("AI usage")
+        .field(
+            "Today",
+            usage_line(&today, config.ai_budgets.user_daily),
+            false,
+        )
+        .field(
+            "This month",
+            usage_line(&this_month, config.ai_budgets.user_monthly),
+            false,
+        )
+        .field("Estimated cost this month", format_cost(month_cost), false);
+
+    if let Some(guild_id) = guild_id
+        && (config.ai_budgets.guild_daily.is_some() || config.ai_budgets.guild_monthly.is_some())
+    {
+        let guild_today = totals_since(&records, day, |r| r.guild_id == Some(guild_id));
+        let guild_month = totals_since(&records, month, |r| r.guild_id == Some(guild_id));
+        embed = embed.field(
+            "This server",
+            format!(
+                "Today: {}\nThis month: {}",
+                usage_line(&guild_today, config.ai_budgets.guild_daily),
+                usage_line(&guild_month, config.ai_budgets.guild_monthly)
+            ),
+            false,
+        );
+    }
+
+    if today.estimated || this_month.estimated {
+        embed = embed.footer(CreateEmbedFooter::new(
+            "~ includes estimates where the provider did not report usage",
+        ));
+    }
+    drop(records);
+    drop(config);
+
+    ctx.send(CreateReply::default().embed(embed).ephemeral(ephemeral))
+        .await?;
+
+    Ok(())
+}
+
+#[poise::command(slash_command)]
+/// Shows this month's AI usage and estimated costs per model, user and server.
+pub async fn ai_usage_report(
+    ctx: Context<'_>,
+    #[description = "Send the response directly to you?"] ephemeral: Option<bool>,
+) -> Result<(), Error> {
+    let ephemeral = bot::defer_based_on_ephemeral(ctx, ephemeral).await?;
+
+    if !is_admin(ctx).await? {
+        error_text(
+            &ctx,
+            ephemeral,
+            "You are not allowed to run the /ai_usage_report command",
+        )
+        .await;
+        return Ok(());
+    }
+
+    let (_, month) = period_starts(Utc::now());
+    let month_start = SystemTime::from(month);
+    let config = ctx.data().config.read().await;
+    let records = USAGE.read().await;
+
+    let mut per_model: HashMap<&str, Totals> = HashMap::new();
+    let mut per_user: HashMap<u64, (Totals, f64)> = HashMap::new();
+    let mut per_guild: HashMap<Option<u64>, (Totals, f64)> = HashMap::new();
+    for record in records.iter().filter(|r| r.time >= month_start) {
+        per_model.entry(&record.model).or_default().add(record);
+
+        let mut single = Totals::default();
+        single.add(record);
+        let record_cost = cost(&config, &record.model, &single).unwrap_or_default();
+
+        let user = per_user.entry(record.user_id).or_default();
+        user.0.add(record);
+        user.1 += record_cost;
+        let guild = per_guild.entry(record.guild_id).or_default();
+        guild.0.add(record);
+        guild.1 += record_cost;
+    }
+
+    if per_model.is_empty() {
+        drop(records);
+        drop(config);
+        error_text(&ctx, ephemeral, "No AI usage recorded this month").await;
+        return Ok(());
+    }
+
+    let mut models: Vec<_> = per_model.into_iter().collect();
+    models.sort_by_key(|(_, totals)| std::cmp::Reverse(totals.total()));
+    let mut total_cost = 0.0;
+    let models_text = models
+        .iter()
+        .map(|(model, totals)| {
+            let model_cost = cost(&config, model, totals);
+            total_cost += model_cost.unwrap_or_default();
+            format!(
+                "`{}`: {} tokens, {}",
+                model,
+                totals.total(),
+                model_cost.map_or("no price configured".to_string(), format_cost)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let mut users: Vec<_> = per_user.into_iter().collect();
+    users.sort_by_key(|(_, (totals, _))| std::cmp::Reverse(totals.total()));
+    let users_text = users
+        .iter()
+        .take(REPORT_TOP)
+        .map(|(user, (totals, cost))| {
+            format!(
+                "<@{}>: {} tokens, {}",
+                user,
+                totals.total(),
+                format_cost(*cost)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let mut guilds: Vec<_> = per_guild.into_iter().collect();
+    guilds.sort_by_key(|(_, (totals, _))| std::cmp::Reverse(totals.total()));
+    let guilds_text = guilds
+        .iter()
+        .take(REPORT_TOP)
+        .map(|(guild, (totals, cost))| {
+            let name = guild.map_or("Direct Messages".to_string(), |g| format!("`{}`", g));
+            format!(
+                "{}: {} tokens, {}",
+                name,
+                totals.total(),
+                format_cost(*cost)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let embed = CreateEmbed::new()
+        .title(format!("AI usage report for {}", month.format("%B %Y")))
+        .field("Models", field_text(&models_text), false)
+        .field("Top users", field_text(&users_text), false)
+        .field("Servers", field_text(&guilds_text), false)
+        .field("Estimated total cost", format_cost(total_cost), false);
+    drop(records);
+    drop(config);
+
+    ctx.send(CreateReply::default().embed(embed).ephemeral(ephemeral))
+        .await?;
+
+    Ok(())
+}
@@ -1,48 +1,383 @@
-use std::{collections::HashMap, path::Path};
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::Path,
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, SystemTime},
+};
 
 use once_cell::sync::Lazy;
 use poise::CreateReply;
 use serde::{Deserialize, Serialize};
-use serenity::all::{Colour, CreateEmbed};
+use serenity::all::{
+    Attachment, AutocompleteChoice, CreateAttachment, CreateEmbed, CreateEmbedFooter,
+};
 use tokio::{fs, sync::RwLock};
 
+use super::alias_history::{RevisionAction, load_history_from_file, record_revision};
 use crate::{
     Context, Error,
-    utils::bot::{self, error_text},
+    utils::{
+        bot::{self, error_text, is_admin},
+        embed::{self, EmbedDefinition, FooterDefinition, ImageDefinition},
+        template::TemplateVars,
+    },
 };
 
+/// Largest alias import file that is accepted.
+const MAX_IMPORT_SIZE: u32 = 1024 * 1024;
+
+/// Minimum similarity (0..1) for an alias to be offered as "did you mean".
+const SUGGESTION_THRESHOLD: f64 = 0.7;
+const MAX_SUGGESTIONS: usize = 3;
+
 #[derive(Serialize, Deserialize, Clone)]
-struct SavedMessage {
-    title: String,
-    content: String,
-    image_url: Option<String>,
-    color: Option<u32>,
+#[serde(from = "StoredMessage")]
+pub(super) struct SavedMessage {
+    pub(super) embeds: Vec<EmbedDefinition>,
+    /// How often the alias has been posted.
+    #[serde(default)]
+    pub(super) uses: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(super) last_used: Option<SystemTime>,
+}
+
+/// On-disk representation of a saved message, accepting the single-embed
+/// format used before aliases supported full embed definitions.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredMessage {
+    Embeds {
+        embeds: Vec<EmbedDefinition>,
+        #[serde(default)]
+        uses: u64,
+        #[serde(default)]
+        last_used: Option<SystemTime>,
+    },
+    Legacy {
+        title: String,
+        content: String,
+        image_url: Option<String>,
+        color: Option<u32>,
+    },
+}
+
+impl From<StoredMessage> for SavedMessage {
+    fn from(stored: StoredMessage) -> Self {
+        match stored {
+            StoredMessage::Embeds {
+                embeds,
+                uses,
+                last_used,
+            } => Self {
+                embeds,
+                uses,
+                last_used,
+            },
+            StoredMessage::Legacy {
+                title,
+                content,
+                image_url,
+                color,
+            } => Self {
+                embeds: vec![EmbedDefinition {
+                    title: Some(title),
+                    description: Some(content),
+                    image: image_url.map(|url| ImageDefinition { url }),
+                    color,
+                    ..Default::default()
+                }],
+                uses: 0,
+                last_used: None,
+            },
+        }
+    }
+}
+
+impl SavedMessage {
+    /// Builds the reply for a saved message, rendering template placeholders in its text.
+    fn reply(&self, vars: &TemplateVars) -> CreateReply {
+        embed::build_all(&self.embeds, vars)
+            .into_iter()
+            .fold(CreateReply::default(), |reply, e| reply.embed(e))
+    }
 }
 
+/// Aliases keyed by owner id, which is a user id or a guild id depending on the store.
 type UserMessages = HashMap<u64, HashMap<String, SavedMessage>>;
 
 static SAVED_MESSAGES: Lazy<RwLock<UserMessages>> = Lazy::new(|| RwLock::new(HashMap::new()));
+static GUILD_MESSAGES: Lazy<RwLock<UserMessages>> = Lazy::new(|| RwLock::new(HashMap::new()));
 const SAVE_FILE_PATH: &str = "saved_messages.json";
+const GUILD_SAVE_FILE_PATH: &str = "guild_messages.json";
+/// Set when alias usage changed since the store was last written.
+static USAGE_DIRTY: AtomicBool = AtomicBool::new(false);
+static GUILD_USAGE_DIRTY: AtomicBool = AtomicBool::new(false);
+/// How often changed usage counters are written to disk.
+const USAGE_FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Where an alias is stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, poise::ChoiceParameter)]
+pub enum AliasScope {
+    /// Private to the user who saved it.
+    #[name = "user"]
+    User,
+    /// Shared with every member of the current server.
+    #[name = "server"]
+    Guild,
+}
+
+impl AliasScope {
+    pub(super) fn store(self) -> &'static RwLock<UserMessages> {
+        match self {
+            AliasScope::User => &SAVED_MESSAGES,
+            AliasScope::Guild => &GUILD_MESSAGES,
+        }
+    }
+
+    fn path(self) -> &'static str {
+        match self {
+            AliasScope::User => SAVE_FILE_PATH,
+            AliasScope::Guild => GUILD_SAVE_FILE_PATH,
+        }
+    }
+
+    fn usage_dirty(self) -> &'static AtomicBool {
+        match self {
+            AliasScope::User => &USAGE_DIRTY,
+            AliasScope::Guild => &GUILD_USAGE_DIRTY,
+        }
+    }
+
+    /// Resolves the owner id aliases of this scope are keyed by.
+    /// Returns None for guild scope outside of a server.
+    pub(super) fn owner(self, ctx: Context<'_>) -> Option<u64> {
+        match self {
+            AliasScope::User => Some(ctx.author().id.get()),
+            AliasScope::Guild => ctx.guild_id().map(|g| g.get()),
+        }
+    }
+}
 
 /// Load saved messages from disk into memory at startup.
 pub async fn load_messages_from_file() -> Result<(), std::io::Error> {
-    if Path::new(SAVE_FILE_PATH).exists() {
-        let data = fs::read_to_string(SAVE_FILE_PATH).await?;
-        let map: UserMessages = serde_json::from_str(&data)?;
-        let mut store = SAVED_MESSAGES.write().await;
-        *store = map;
+    for scope in [AliasScope::User, AliasScope::Guild] {
+        if Path::new(scope.path()).exists() {
+            let data = fs::read_to_string(scope.path()).await?;
+            let map: UserMessages = serde_json::from_str(&data)?;
+            let mut store = scope.store().write().await;
+            *store = map;
+        }
     }
-    Ok(())
+    load_history_from_file().await
 }
 
-/// Saves all in-memory saved messages to disk as pretty JSON.
-async fn save_messages_to_file() -> Result<(), std::io::Error> {
-    let store = SAVED_MESSAGES.read().await;
+/// Saves all in-memory saved messages of `scope` to disk as pretty JSON.
+pub(super) async fn save_messages_to_file(scope: AliasScope) -> Result<(), std::io::Error> {
+    let store = scope.store().read().await;
+    // The usage counters are written along with everything else
+    scope.usage_dirty().store(false, Ordering::Relaxed);
     let json = serde_json::to_string_pretty(&*store)?;
-    fs::write(SAVE_FILE_PATH, json).await?;
+    if let Err(e) = fs::write(scope.path(), json).await {
+        scope.usage_dirty().store(true, Ordering::Relaxed);
+        return Err(e);
+    }
     Ok(())
 }
 
+/// Starts the background task that writes changed alias usage counters to disk,
+/// so using an alias does not rewrite its store every time.
+pub async fn start_alias_usage_flush() {
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(USAGE_FLUSH_INTERVAL).await;
+            for scope in [AliasScope::User, AliasScope::Guild] {
+                if scope.usage_dirty().load(Ordering::Relaxed)
+                    && let Err(e) = save_messages_to_file(scope).await
+                {
+                    tracing::warn!("Failed to save alias usage: {}", e);
+                }
+            }
+        }
+    });
+}
+
+/// Returns true if the author may create, edit or delete aliases of the current guild.
+/// Admins always may, everyone else needs one of the guild's configured alias roles.
+async fn can_edit_guild_aliases(ctx: Context<'_>) -> Result<bool, Error> {
+    if is_admin(ctx).await? {
+        return Ok(true);
+    }
+
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(false);
+    };
+
+    let allowed_roles = ctx
+        .data()
+        .config
+        .read()
+        .await
+        .guild_alias_roles
+        .get(&guild_id.to_string())
+        .cloned()
+        .unwrap_or_default();
+    if allowed_roles.is_empty() {
+        return Ok(false);
+    }
+
+    Ok(ctx.author_member().await.is_some_and(|member| {
+        member
+            .roles
+            .iter()
+            .any(|role| allowed_roles.contains(&role.to_string()))
+    }))
+}
+
+/// Checks that the author may modify aliases in `scope` and resolves the owner id.
+/// Sends an error and returns None otherwise.
+pub(super) async fn editable_owner(
+    ctx: Context<'_>,
+    scope: AliasScope,
+    ephemeral: bool,
+) -> Result<Option<u64>, Error> {
+    let Some(owner) = scope.owner(ctx) else {
+        error_text(
+            &ctx,
+            ephemeral,
+            "Server aliases can only be used inside a server.",
+        )
+        .await;
+        return Ok(None);
+    };
+
+    if scope == AliasScope::Guild && !can_edit_guild_aliases(ctx).await? {
+        error_text(
+            &ctx,
+            ephemeral,
+            "You are not allowed to edit this server's aliases!",
+        )
+        .await;
+        return Ok(None);
+    }
+
+    Ok(Some(owner))
+}
+
+/// Looks up an alias, preferring the author's own aliases over the guild's,
+/// and records the use. The usage is saved by the flush task.
+async fn use_alias(ctx: Context<'_>, alias: &str) -> Option<SavedMessage> {
+    for scope in [AliasScope::User, AliasScope::Guild] {
+        let Some(owner) = scope.owner(ctx) else {
+            continue;
+        };
+
+        let found = {
+            let mut store = scope.store().write().await;
+            store
+                .get_mut(&owner)
+                .and_then(|m| m.get_mut(alias))
+                .map(|saved| {
+                    saved.uses += 1;
+                    saved.last_used = Some(SystemTime::now());
+                    saved.clone()
+                })
+        };
+
+        if let Some(saved) = found {
+            scope.usage_dirty().store(true, Ordering::Relaxed);
+            return Some(saved);
+        }
+    }
+    None
+}
+
+/// An alias visible to the author, with the stats used for sorting.
+struct AliasEntry {
+    name: String,
+    scope: AliasScope,
+    uses: u64,
+    last_used: Option<SystemTime>,
+}
+
+/// Collects the author's and the current guild's aliases, most used first.
+async fn visible_aliases(ctx: Context<'_>) -> Vec<AliasEntry> {
+    let mut entries = Vec::new();
+    for scope in [AliasScope::User, AliasScope::Guild] {
+        let Some(owner) = scope.owner(ctx) else {
+            continue;
+        };
+        let store = scope.store().read().await;
+        if let Some(map) = store.get(&owner) {
+            entries.extend(map.iter().map(|(name, saved)| AliasEntry {
+                name: name.clone(),
+                scope,
+                uses: saved.uses,
+                last_used: saved.last_used,
+            }));
+        }
+    }
+
+    entries.sort_by(|a, b| {
+        b.uses
+            .cmp(&a.uses)
+            .then(b.last_used.cmp(&a.last_used))
+            .then(a.name.cmp(&b.name))
+    });
+    entries
+}
+
+/// Suggests alias names for the author's and the guild's aliases.
+async fn autocomplete_alias(ctx: Context<'_>, partial: &str) -> Vec<AutocompleteChoice> {
+    let partial = partial.to_lowercase();
+    visible_aliases(ctx)
+        .await
+        .into_iter()
+        .filter(|e| e.name.to_lowercase().contains(&partial))
+        .take(25)
+        .map(|e| {
+            let label = match e.scope {
+                AliasScope::User => e.name.clone(),
+                AliasScope::Guild => format!("{} (server)", e.name),
+            };
+            AutocompleteChoice::new(label, e.name)
+        })
+        .collect()
+}
+
+/// Finds the visible aliases closest to a name that didn't match, best first.
+async fn suggest_aliases(ctx: Context<'_>, alias: &str) -> Vec<String> {
+    let alias = alias.to_lowercase();
+    let mut scored: Vec<(f64, String)> = visible_aliases(ctx)
+        .await
+        .into_iter()
+        .map(|e| (strsim::jaro_winkler(&alias, &e.name.to_lowercase()), e.name))
+        .filter(|(score, _)| *score >= SUGGESTION_THRESHOLD)
+        .collect();
+
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+    scored.dedup_by(|a, b| a.1 == b.1);
+    scored
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, name)| name)
+        .collect()
+}
+
+/// Sends the "not found" error, including close matches if there are any.
+async fn alias_not_found(ctx: Context<'_>, ephemeral: bool, alias: &str) {
+    let suggestions = suggest_aliases(ctx, alias).await;
+    let mut text = format!("No saved message found for alias `{}`.", alias);
+    if !suggestions.is_empty() {
+        let list = suggestions
+            .iter()
+            .map(|s| format!("`{}`", s))
+            .collect::<Vec<_>>()
+            .join(", ");
+        text.push_str(&format!("\nDid you mean {}?", list));
+    }
+    error_text(&ctx, ephemeral, &text).await;
+}
+
 /// Parses a hex color string (with or without leading '#') into a u32.
 /// Returns None if the input is invalid.
 fn parse_color(color_str: &str) -> Option<u32> {
@@ -51,7 +386,7 @@ fn parse_color(color_str: &str) -> Option<u32> {
         3 => {
             let expanded: String = trimmed
                 .chars()
-                .flat_map(|c| std::iter::repeat(c).take(2))
+                .flat_map(|c| std::iter::repeat_n(c, 2))
                 .collect();
             u32::from_str_radix(&expanded, 16).ok()
         }
@@ -60,25 +395,106 @@ fn parse_color(color_str: &str) -> Option<u32> {
     }
 }
 
+/// The embed fields asked for by `/save_alias`.
+#[derive(poise::Modal)]
+#[name = "Save alias"]
+struct AliasModal {
+    #[name = "Title"]
+    #[max_length = 256]
+    title: String,
+    #[name = "Content"]
+    #[paragraph]
+    #[max_length = 4000]
+    content: String,
+    #[name = "Color, e.g. #FF0000 or FF0000"]
+    #[max_length = 7]
+    color: Option<String>,
+    #[name = "Image URL"]
+    image_url: Option<String>,
+    #[name = "Footer"]
+    #[max_length = 2048]
+    footer: Option<String>,
+}
+
+impl AliasModal {
+    /// Prefills the form from the first embed of an existing alias.
+    fn from_saved(saved: &SavedMessage) -> Option<Self> {
+        let embed = saved.embeds.first()?;
+        Some(Self {
+            title: embed.title.clone().unwrap_or_default(),
+            content: embed.description.clone().unwrap_or_default(),
+            color: embed.color.map(|color| format!("#{:06X}", color)),
+            image_url: embed.image.as_ref().map(|image| image.url.clone()),
+            footer: embed.footer.as_ref().map(|footer| footer.text.clone()),
+        })
+    }
+
+    /// Fills the first of `embeds` from the form. Everything the form has no
+    /// room for, like fields, the timestamp and further embeds, is kept.
+    fn apply(self, color: Option<u32>, mut embeds: Vec<EmbedDefinition>) -> Vec<EmbedDefinition> {
+        if embeds.is_empty() {
+            embeds.push(EmbedDefinition::default());
+        }
+        let embed = &mut embeds[0];
+        embed.title = Some(self.title);
+        embed.description = Some(self.content);
+        embed.color = color;
+        embed.image = self.image_url.map(|url| ImageDefinition { url });
+        let icon_url = embed.footer.take().and_then(|footer| footer.icon_url);
+        embed.footer = self.footer.map(|text| FooterDefinition { text, icon_url });
+        embeds
+    }
+}
+
 #[poise::command(slash_command)]
-/// Saves a custom message under a user-defined alias.
+/// Saves a custom message under a user-defined alias, asking for its content in a form.
 pub async fn save_alias(
     ctx: Context<'_>,
     #[description = "Alias to save this message under"] alias: String,
-    #[description = "Title of the message"] title: String,
-    #[description = "Content of the message"] content: String,
-    #[description = "Optional image URL for the embed"] image_url: Option<String>,
-    #[description = "Optional hex color for the embed, e.g. #FF0000 or FF0000"] color: Option<
-        String,
+    #[description = "Save for yourself or for the whole server? (default: user)"] scope: Option<
+        AliasScope,
     >,
+    #[description = "Only show the rendered message without saving it"] preview: Option<bool>,
+    #[description = "Arguments to fill {arg1}, {args}, ... with in the preview"]
+    preview_args: Option<String>,
     #[description = "Send the response directly to you?"] ephemeral: Option<bool>,
 ) -> Result<(), Error> {
-    let ephemeral = bot::defer_based_on_ephemeral(ctx, ephemeral).await?;
-    let user_id = ctx.author().id.get();
+    let ephemeral = ephemeral.unwrap_or(false);
+    let scope = scope.unwrap_or(AliasScope::User);
+    let poise::Context::Application(app_ctx) = ctx else {
+        return Ok(());
+    };
+
+    let preview = preview.unwrap_or(false);
+    // Check the permission before the form is filled in, previews need none
+    let owner = if preview {
+        scope.owner(ctx)
+    } else {
+        match editable_owner(ctx, scope, ephemeral).await? {
+            Some(owner) => Some(owner),
+            None => return Ok(()),
+        }
+    };
+
+    // Editing an existing alias starts from its current content
+    let existing = match owner {
+        Some(owner) => scope
+            .store()
+            .read()
+            .await
+            .get(&owner)
+            .and_then(|m| m.get(&alias))
+            .cloned(),
+        None => None,
+    };
+    let defaults = existing.as_ref().and_then(AliasModal::from_saved);
+    let Some(form) = poise::execute_modal(app_ctx, defaults, None).await? else {
+        return Ok(());
+    };
 
     // Validate color format if provided
-    let color_int = if let Some(ref c) = color {
-        match parse_color(c) {
+    let color_int = match &form.color {
+        Some(c) => match parse_color(c) {
             Some(parsed) => Some(parsed),
             None => {
                 error_text(
@@ -89,26 +505,71 @@ pub async fn save_alias(
                 .await;
                 return Ok(());
             }
-        }
-    } else {
-        None
+        },
+        None => None,
     };
 
-    {
-        let mut store = SAVED_MESSAGES.write().await;
-        let user_map = store.entry(user_id).or_default();
-        user_map.insert(alias.clone(), SavedMessage {
-            title,
-            content,
-            image_url,
-            color: color_int,
-        });
-    }
-
-    println!("Saving messages to file...");
-    if let Err(e) = save_messages_to_file().await {
+    let saved = SavedMessage {
+        embeds: form.apply(
+            color_int,
+            existing.map(|saved| saved.embeds).unwrap_or_default(),
+        ),
+        uses: 0,
+        last_used: None,
+    };
+
+    if let Err(reason) = embed::validate(&saved.embeds) {
+        error_text(&ctx, ephemeral, &format!("Invalid embed: {}", reason)).await;
+        return Ok(());
+    }
+
+    if preview {
+        let vars = TemplateVars::from_ctx(ctx, preview_args.as_deref());
+        ctx.send(
+            saved
+                .reply(&vars)
+                .content(format!("👀 Preview of `{}` (not saved):", alias))
+                .ephemeral(ephemeral),
+        )
+        .await?;
+        return Ok(());
+    }
+
+    let Some(owner) = owner else {
+        return Ok(());
+    };
+
+    let previous = {
+        let mut store = scope.store().write().await;
+        let owner_map = store.entry(owner).or_default();
+        // Keep the usage stats when overwriting an existing alias
+        let saved = match owner_map.get(&alias) {
+            Some(old) => SavedMessage {
+                uses: old.uses,
+                last_used: old.last_used,
+                ..saved.clone()
+            },
+            None => saved.clone(),
+        };
+        owner_map.insert(alias.clone(), saved)
+    };
+
+    record_revision(
+        scope,
+        owner,
+        &alias,
+        ctx.author().id.get(),
+        RevisionAction::Saved,
+        previous,
+        saved,
+    )
+    .await;
+
+    tracing::debug!("Saving alias {} to {}", alias, scope.path());
+    if let Err(e) = save_messages_to_file(scope).await {
         error_text(&ctx, ephemeral, &format!("Failed to save: {}", e)).await;
     } else {
+        tracing::debug!("Saved alias {}", alias);
         ctx.send(
             CreateReply::default()
                 .content(format!("✅ Saved message with alias `{}`.", alias))
@@ -116,7 +577,6 @@ pub async fn save_alias(
         )
         .await?;
     }
-    println!("Messages saved to file successfully.");
 
     Ok(())
 }
@@ -125,38 +585,22 @@ pub async fn save_alias(
 /// Retrieves and displays a saved message by its alias.
 pub async fn alias(
     ctx: Context<'_>,
-    #[description = "Alias of the saved message"] alias: String,
+    #[description = "Alias of the saved message"]
+    #[autocomplete = "autocomplete_alias"]
+    alias: String,
+    #[description = "Arguments for the alias' {arg1}, {args}, ... placeholders"] args: Option<
+        String,
+    >,
     #[description = "Send the response directly to you?"] ephemeral: Option<bool>,
 ) -> Result<(), Error> {
     let ephemeral = bot::defer_based_on_ephemeral(ctx, ephemeral).await?;
-    let user_id = ctx.author().id.get();
-    let store = SAVED_MESSAGES.read().await;
 
-    match store.get(&user_id).and_then(|m| m.get(&alias)) {
+    match use_alias(ctx, &alias).await {
         Some(saved) => {
-            let mut embed = CreateEmbed::default()
-                .title(&saved.title)
-                .description(saved.content.replace("\\n", "\n"));
-
-            if let Some(ref image_url) = saved.image_url {
-                embed = embed.image(image_url);
-            }
-
-            if let Some(color) = saved.color {
-                embed = embed.color(Colour(color));
-            }
-
-            ctx.send(CreateReply::default().embed(embed).ephemeral(ephemeral))
-                .await?;
-        }
-        None => {
-            error_text(
-                &ctx,
-                ephemeral,
-                &format!("No saved message found for alias `{}`.", alias),
-            )
-            .await;
+            let vars = TemplateVars::from_ctx(ctx, args.as_deref());
+            ctx.send(saved.reply(&vars).ephemeral(ephemeral)).await?;
         }
+        None => alias_not_found(ctx, ephemeral, &alias).await,
     }
 
     Ok(())
@@ -165,30 +609,43 @@ pub async fn alias(
 #[poise::command(slash_command)]
 pub async fn delete_alias(
     ctx: Context<'_>,
-    #[description = "Alias to delete"] alias: String,
+    #[description = "Alias to delete"]
+    #[autocomplete = "autocomplete_alias"]
+    alias: String,
+    #[description = "Delete your own alias or the server's? (default: user)"] scope: Option<
+        AliasScope,
+    >,
     #[description = "Send the response directly to you?"] ephemeral: Option<bool>,
 ) -> Result<(), Error> {
     let ephemeral = bot::defer_based_on_ephemeral(ctx, ephemeral).await?;
-    let user_id = ctx.author().id.get();
+    let scope = scope.unwrap_or(AliasScope::User);
+
+    let Some(owner) = editable_owner(ctx, scope, ephemeral).await? else {
+        return Ok(());
+    };
+
     let removed = {
-        let mut store = SAVED_MESSAGES.write().await;
-        store
-            .get_mut(&user_id)
-            .and_then(|m| m.remove(&alias))
-            .is_some()
+        let mut store = scope.store().write().await;
+        store.get_mut(&owner).and_then(|m| m.remove(&alias))
     };
 
-    if !removed {
-        error_text(
-            &ctx,
-            ephemeral,
-            &format!("No saved message found for alias `{}`.", alias),
-        )
-        .await;
+    let Some(removed) = removed else {
+        alias_not_found(ctx, ephemeral, &alias).await;
         return Ok(());
-    }
+    };
+
+    record_revision(
+        scope,
+        owner,
+        &alias,
+        ctx.author().id.get(),
+        RevisionAction::Deleted,
+        None,
+        removed,
+    )
+    .await;
 
-    match save_messages_to_file().await {
+    match save_messages_to_file(scope).await {
         Ok(_) => {
             ctx.send(
                 CreateReply::default()
@@ -206,21 +663,44 @@ pub async fn delete_alias(
 }
 
 #[poise::command(slash_command)]
-/// Lists all saved aliases for the user.
+/// Lists all saved aliases for the user and the current server.
 pub async fn list_alias(
     ctx: Context<'_>,
     #[description = "Send the response directly to you?"] ephemeral: Option<bool>,
 ) -> Result<(), Error> {
     let ephemeral = bot::defer_based_on_ephemeral(ctx, ephemeral).await?;
-    let user_id = ctx.author().id.get();
-    let store = SAVED_MESSAGES.read().await;
 
-    let aliases = store
-        .get(&user_id)
-        .map(|m| m.keys().cloned().collect::<Vec<_>>())
-        .unwrap_or_default();
+    let entries = visible_aliases(ctx).await;
+    let mut embed = CreateEmbed::default()
+        .title("Saved aliases")
+        .footer(CreateEmbedFooter::new("Sorted by usage"));
+    let mut any = false;
 
-    if aliases.is_empty() {
+    for (scope, heading) in [
+        (AliasScope::User, "Your aliases"),
+        (AliasScope::Guild, "Server aliases"),
+    ] {
+        let mut list = String::new();
+        for entry in entries.iter().filter(|e| e.scope == scope) {
+            let item = format!("`{}` ({}×)", entry.name, entry.uses);
+            // Embed field values are limited to 1024 characters
+            if list.len() + item.len() + 2 > 1000 {
+                list.push_str(", …");
+                break;
+            }
+            if !list.is_empty() {
+                list.push_str(", ");
+            }
+            list.push_str(&item);
+        }
+
+        if !list.is_empty() {
+            embed = embed.field(heading, list, false);
+            any = true;
+        }
+    }
+
+    if !any {
         ctx.send(
             CreateReply::default()
                 .content("You have no saved aliases.")
@@ -228,13 +708,243 @@ pub async fn list_alias(
         )
         .await?;
     } else {
-        let embed = CreateEmbed::default()
-            .title("Your saved aliases")
-            .description(aliases.join(", "));
-
         ctx.send(CreateReply::default().embed(embed).ephemeral(ephemeral))
             .await?;
     }
 
     Ok(())
 }
+
+#[poise::command(slash_command)]
+/// Exports your or the server's aliases as a JSON file.
+pub async fn alias_export(
+    ctx: Context<'_>,
+    #[description = "Export your own aliases or the server's? (default: user)"] scope: Option<
+        AliasScope,
+    >,
+    #[description = "Send the response directly to you?"] ephemeral: Option<bool>,
+) -> Result<(), Error> {
+    let ephemeral = bot::defer_based_on_ephemeral(ctx, ephemeral).await?;
+    let scope = scope.unwrap_or(AliasScope::User);
+
+    let Some(owner) = scope.owner(ctx) else {
+        error_text(
+            &ctx,
+            ephemeral,
+            "Server aliases can only be used inside a server.",
+        )
+        .await;
+        return Ok(());
+    };
+
+    let aliases: BTreeMap<String, SavedMessage> = scope
+        .store()
+        .read()
+        .await
+        .get(&owner)
+        .map(|m| m.iter().map(|(k, v)| (k.clone(), v.clone())).collect())
+        .unwrap_or_default();
+
+    if aliases.is_empty() {
+        error_text(&ctx, ephemeral, "There are no aliases to export.").await;
+        return Ok(());
+    }
+
+    let json = serde_json::to_string_pretty(&aliases)?;
+    ctx.send(
+        CreateReply::default()
+            .content(format!("📦 Exported {} alias(es).", aliases.len()))
+            .attachment(CreateAttachment::bytes(json.into_bytes(), "aliases.json"))
+            .ephemeral(ephemeral),
+    )
+    .await?;
+
+    Ok(())
+}
+
+#[poise::command(slash_command)]
+/// Imports aliases from a JSON file created by /alias_export or an embed builder.
+pub async fn alias_import(
+    ctx: Context<'_>,
+    #[description = "JSON file with aliases, or a single message with an \"embeds\" list"]
+    file: Attachment,
+    #[description = "Alias name, required when the file contains a single message"] alias: Option<
+        String,
+    >,
+    #[description = "Import into your own aliases or the server's? (default: user)"] scope: Option<
+        AliasScope,
+    >,
+    #[description = "Replace aliases that already exist? (default: false)"] overwrite: Option<bool>,
+    #[description = "Send the response directly to you?"] ephemeral: Option<bool>,
+) -> Result<(), Error> {
+    let ephemeral = bot::defer_based_on_ephemeral(ctx, ephemeral).await?;
+    let scope = scope.unwrap_or(AliasScope::User);
+    let overwrite = overwrite.unwrap_or(false);
+
+    let Some(owner) = editable_owner(ctx, scope, ephemeral).await? else {
+        return Ok(());
+    };
+
+    if file.size > MAX_IMPORT_SIZE {
+        error_text(&ctx, ephemeral, "The file is too large (max 1 MB).").await;
+        return Ok(());
+    }
+
+    let data = file.download().await?;
+    let json: serde_json::Value = match serde_json::from_slice(&data) {
+        Ok(json) => json,
+        Err(e) => {
+            error_text(&ctx, ephemeral, &format!("Invalid JSON: {}", e)).await;
+            return Ok(());
+        }
+    };
+
+    // A single message (e.g. from an embed builder) or a map of alias name to message
+    let parsed: Result<BTreeMap<String, SavedMessage>, String> = if json.get("embeds").is_some() {
+        match alias {
+            Some(alias) => serde_json::from_value(json)
+                .map(|message| BTreeMap::from([(alias, message)]))
+                .map_err(|e| e.to_string()),
+            None => Err("the file contains a single message, please give an alias name".into()),
+        }
+    } else {
+        serde_json::from_value(json).map_err(|e| e.to_string())
+    };
+
+    let imported = match parsed {
+        Ok(imported) => imported,
+        Err(e) => {
+            error_text(&ctx, ephemeral, &format!("Invalid alias file: {}", e)).await;
+            return Ok(());
+        }
+    };
+
+    for (name, message) in &imported {
+        if let Err(reason) = embed::validate(&message.embeds) {
+            error_text(
+                &ctx,
+                ephemeral,
+                &format!("Alias `{}` is invalid: {}", name, reason),
+            )
+            .await;
+            return Ok(());
+        }
+    }
+
+    let (mut added, mut skipped) = (0, 0);
+    let mut changes = Vec::new();
+    {
+        let mut store = scope.store().write().await;
+        let owner_map = store.entry(owner).or_default();
+        for (name, message) in imported {
+            if !overwrite && owner_map.contains_key(&name) {
+                skipped += 1;
+            } else {
+                let previous = owner_map.insert(name.clone(), message.clone());
+                changes.push((name, previous, message));
+                added += 1;
+            }
+        }
+    }
+
+    for (name, previous, message) in changes {
+        record_revision(
+            scope,
+            owner,
+            &name,
+            ctx.author().id.get(),
+            RevisionAction::Imported,
+            previous,
+            message,
+        )
+        .await;
+    }
+
+    if let Err(e) = save_messages_to_file(scope).await {
+        error_text(&ctx, ephemeral, &format!("Failed to save: {}", e)).await;
+        return Ok(());
+    }
+
+    ctx.send(
+        CreateReply::default()
+            .content(format!(
+                "✅ Imported {} alias(es), skipped {} existing.",
+                added, skipped
+            ))
+            .ephemeral(ephemeral),
+    )
+    .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::embed::FieldDefinition;
+
+    fn form() -> AliasModal {
+        AliasModal {
+            title: "New title".into(),
+            content: "New content".into(),
+            color: None,
+            image_url: None,
+            footer: Some("New footer".into()),
+        }
+    }
+
+    #[test]
+    fn editing_keeps_everything_the_form_cannot_show() {
+        let embeds = vec![
+            EmbedDefinition {
+                title: Some("Old".into()),
+                url: Some("https://example.com".into()),
+                timestamp: Some("2026-01-01T00:00:00Z".into()),
+                footer: Some(FooterDefinition {
+                    text: "Old footer".into(),
+                    icon_url: Some("https://example.com/icon.png".into()),
+                }),
+                fields: vec![FieldDefinition {
+                    name: "Field".into(),
+                    value: "Value".into(),
+                    inline: true,
+                }],
+                ..Default::default()
+            },
+            EmbedDefinition {
+                title: Some("Second".into()),
+                ..Default::default()
+            },
+            EmbedDefinition {
+                description: Some("Third".into()),
+                ..Default::default()
+            },
+        ];
+
+        let embeds = form().apply(Some(0xFF0000), embeds);
+
+        assert_eq!(embeds.len(), 3);
+        assert_eq!(embeds[0].title.as_deref(), Some("New title"));
+        assert_eq!(embeds[0].description.as_deref(), Some("New content"));
+        assert_eq!(embeds[0].color, Some(0xFF0000));
+        assert_eq!(embeds[0].url.as_deref(), Some("https://example.com"));
+        assert_eq!(embeds[0].timestamp.as_deref(), Some("2026-01-01T00:00:00Z"));
+        assert_eq!(embeds[0].fields.len(), 1);
+        let footer = embeds[0].footer.as_ref().unwrap();
+        assert_eq!(footer.text, "New footer");
+        assert_eq!(
+            footer.icon_url.as_deref(),
+            Some("https://example.com/icon.png")
+        );
+        assert_eq!(embeds[1].title.as_deref(), Some("Second"));
+        assert_eq!(embeds[2].description.as_deref(), Some("Third"));
+    }
+
+    #[test]
+    fn new_alias_gets_one_embed() {
+        let embeds = form().apply(None, Vec::new());
+        assert_eq!(embeds.len(), 1);
+        assert_eq!(embeds[0].title.as_deref(), Some("New title"));
+        assert!(embeds[0].fields.is_empty());
+    }
+}
@@ -0,0 +1,462 @@
+use std::{
+    collections::HashMap,
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use once_cell::sync::Lazy;
+use poise::CreateReply;
+use serde::{Deserialize, Serialize};
+use serenity::all::{AutocompleteChoice, Colour, CreateEmbed};
+use tokio::{fs, sync::RwLock};
+
+use super::alias::{AliasScope, SavedMessage, editable_owner, save_messages_to_file};
+use crate::{
+    Context, Error,
+    utils::bot::{self, error_text},
+};
+
+/// How long the history of a deleted alias is kept so it can be restored.
+const DELETED_RETENTION: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+/// Revisions kept per alias, older ones are dropped.
+const MAX_REVISIONS: usize = 25;
+/// Longest diff shown in `/alias_history` before it is cut.
+const MAX_DIFF_LEN: usize = 1800;
+
+/// What happened to an alias in a revision.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum RevisionAction {
+    /// Content the alias had before its history was first recorded.
+    Existing,
+    Saved,
+    Imported,
+    Deleted,
+    Restored(u32),
+}
+
+/// One recorded change of an alias.
+#[derive(Serialize, Deserialize, Clone)]
+struct Revision {
+    rev: u32,
+    author: Option<u64>,
+    /// Unknown for the content an alias had before its history was recorded.
+    time: Option<SystemTime>,
+    action: RevisionAction,
+    /// Content after the change, or the removed content for deletions.
+    message: SavedMessage,
+}
+
+/// Revisions keyed by owner id and alias name, mirroring the alias stores.
+type AliasHistory = HashMap<u64, HashMap<String, Vec<Revision>>>;
+
+static USER_HISTORY: Lazy<RwLock<AliasHistory>> = Lazy::new(|| RwLock::new(HashMap::new()));
+static GUILD_HISTORY: Lazy<RwLock<AliasHistory>> = Lazy::new(|| RwLock::new(HashMap::new()));
+const HISTORY_FILE_PATH: &str = "alias_history.json";
+const GUILD_HISTORY_FILE_PATH: &str = "guild_alias_history.json";
+
+fn history_store(scope: AliasScope) -> &'static RwLock<AliasHistory> {
+    match scope {
+        AliasScope::User => &USER_HISTORY,
+        AliasScope::Guild => &GUILD_HISTORY,
+    }
+}
+
+fn history_path(scope: AliasScope) -> &'static str {
+    match scope {
+        AliasScope::User => HISTORY_FILE_PATH,
+        AliasScope::Guild => GUILD_HISTORY_FILE_PATH,
+    }
+}
+
+/// Load alias histories from disk into memory at startup.
+pub(super) async fn load_history_from_file() -> Result<(), std::io::Error> {
+    for scope in [AliasScope::User, AliasScope::Guild] {
+        if Path::new(history_path(scope)).exists() {
+            let data = fs::read_to_string(history_path(scope)).await?;
+            let map: AliasHistory = serde_json::from_str(&data)?;
+            *history_store(scope).write().await = map;
+        }
+    }
+    Ok(())
+}
+
+/// Drops the histories of aliases that were deleted longer than the retention period ago.
+fn prune_expired(history: &mut AliasHistory) {
+    let now = SystemTime::now();
+    for aliases in history.values_mut() {
+        aliases.retain(|_, revisions| {
+            revisions.last().is_none_or(|last| {
+                last.action != RevisionAction::Deleted
+                    || last
+                        .time
+                        .and_then(|time| now.duration_since(time).ok())
+                        .is_some_and(|age| age < DELETED_RETENTION)
+            })
+        });
+    }
+    history.retain(|_, aliases| !aliases.is_empty());
+}
+
+/// Saves the alias history of `scope` to disk as pretty JSON.
+async fn save_history_to_file(scope: AliasScope) -> Result<(), std::io::Error> {
+    let mut store = history_store(scope).write().await;
+    prune_expired(&mut store);
+    let json = serde_json::to_string_pretty(&*store)?;
+    fs::write(history_path(scope), json).await?;
+    Ok(())
+}
+
+/// Records a change of an alias as a new revision.
+/// `previous` is the content being replaced, it is kept as a baseline revision
+/// for aliases that existed before their history was recorded.
+/// Failing to persist the history is logged but doesn't fail the change itself.
+pub(super) async fn record_revision(
+    scope: AliasScope,
+    owner: u64,
+    alias: &str,
+    author: u64,
+    action: RevisionAction,
+    previous: Option<SavedMessage>,
+    message: SavedMessage,
+) {
+    {
+        let mut store = history_store(scope).write().await;
+        let revisions = store
+            .entry(owner)
+            .or_default()
+            .entry(alias.to_string())
+            .or_default();
+
+        if revisions.is_empty()
+            && let Some(previous) = previous
+        {
+            revisions.push(Revision {
+                rev: 1,
+                author: None,
+                time: None,
+                action: RevisionAction::Existing,
+                message: previous,
+            });
+        }
+
+        let rev = revisions.last().map_or(1, |r| r.rev + 1);
+        revisions.push(Revision {
+            rev,
+            author: Some(author),
+            time: Some(SystemTime::now()),
+            action,
+            message,
+        });
+        if revisions.len() > MAX_REVISIONS {
+            revisions.drain(..revisions.len() - MAX_REVISIONS);
+        }
+    }
+
+    if let Err(e) = save_history_to_file(scope).await {
+        tracing::warn!("Failed to save alias history: {}", e);
+    }
+}
+
+/// Suggests alias names with a history, including deleted ones.
+async fn autocomplete_history(ctx: Context<'_>, partial: &str) -> Vec<AutocompleteChoice> {
+    let partial = partial.to_lowercase();
+    let mut choices = Vec::new();
+
+    for scope in [AliasScope::User, AliasScope::Guild] {
+        let Some(owner) = scope.owner(ctx) else {
+            continue;
+        };
+        let store = history_store(scope).read().await;
+        let Some(aliases) = store.get(&owner) else {
+            continue;
+        };
+
+        let mut names: Vec<_> = aliases
+            .iter()
+            .filter(|(name, _)| name.to_lowercase().contains(&partial))
+            .collect();
+        names.sort_by(|a, b| a.0.cmp(b.0));
+
+        for (name, revisions) in names {
+            let deleted = revisions
+                .last()
+                .is_some_and(|r| r.action == RevisionAction::Deleted);
+            let label = match (scope, deleted) {
+                (AliasScope::User, false) => name.clone(),
+                (AliasScope::User, true) => format!("{} (deleted)", name),
+                (AliasScope::Guild, false) => format!("{} (server)", name),
+                (AliasScope::Guild, true) => format!("{} (server, deleted)", name),
+            };
+            choices.push(AutocompleteChoice::new(label, name.clone()));
+        }
+    }
+
+    choices.truncate(25);
+    choices
+}
+
+/// Serializes the content of a revision for diffing, one JSON line per property.
+/// Deletions are empty so their diff shows everything as removed.
+fn revision_text(revision: &Revision) -> String {
+    if revision.action == RevisionAction::Deleted {
+        return String::new();
+    }
+    serde_json::to_string_pretty(&revision.message.embeds).unwrap_or_default()
+}
+
+/// Produces a unified-style line diff between `old` and `new`.
+fn line_diff(old: &str, new: &str) -> String {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    // Longest common subsequence table, filled from the back
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut out = String::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            out.push_str(&format!("  {}\n", old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            out.push_str(&format!("- {}\n", old[i]));
+            i += 1;
+        } else {
+            out.push_str(&format!("+ {}\n", new[j]));
+            j += 1;
+        }
+    }
+    out
+}
+
+fn describe_action(action: RevisionAction) -> String {
+    match action {
+        RevisionAction::Existing => "existing version".to_string(),
+        RevisionAction::Saved => "saved".to_string(),
+        RevisionAction::Imported => "imported".to_string(),
+        RevisionAction::Deleted => "deleted".to_string(),
+        RevisionAction::Restored(rev) => format!("restored from rev {}", rev),
+    }
+}
+
+/// Breaks up runs of backticks so the text cannot close the code block it is shown in.
+fn escape_fence(text: &str) -> String {
+    text.replace("``", "`\u{200b}`")
+}
+
+fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[poise::command(slash_command)]
+/// Shows the revisions of an alias and what changed in one of them.
+pub async fn alias_history(
+    ctx: Context<'_>,
+    #[description = "Alias to show the history of"]
+    #[autocomplete = "autocomplete_history"]
+    alias: String,
+    #[description = "Revision to show the changes of (default: latest)"] rev: Option<u32>,
+    #[description = "Your own alias or the server's? (default: user)"] scope: Option<AliasScope>,
+    #[description = "Send the response directly to you?"] ephemeral: Option<bool>,
+) -> Result<(), Error> {
+    let ephemeral = bot::defer_based_on_ephemeral(ctx, ephemeral).await?;
+    let scope = scope.unwrap_or(AliasScope::User);
+
+    let Some(owner) = scope.owner(ctx) else {
+        error_text(
+            &ctx,
+            ephemeral,
+            "Server aliases can only be used inside a server.",
+        )
+        .await;
+        return Ok(());
+    };
+
+    let revisions = history_store(scope)
+        .read()
+        .await
+        .get(&owner)
+        .and_then(|m| m.get(&alias))
+        .cloned()
+        .unwrap_or_default();
+
+    if revisions.is_empty() {
+        error_text(
+            &ctx,
+            ephemeral,
+            &format!("No history found for alias `{}`.", alias),
+        )
+        .await;
+        return Ok(());
+    }
+
+    let Some(index) = (match rev {
+        Some(rev) => revisions.iter().position(|r| r.rev == rev),
+        None => Some(revisions.len() - 1),
+    }) else {
+        error_text(
+            &ctx,
+            ephemeral,
+            &format!("Alias `{}` has no revision {}.", alias, rev.unwrap_or(0)),
+        )
+        .await;
+        return Ok(());
+    };
+
+    let list = revisions
+        .iter()
+        .rev()
+        .take(10)
+        .map(|r| {
+            let by = r
+                .author
+                .map(|a| format!(" by <@{}>", a))
+                .unwrap_or_default();
+            let when = r
+                .time
+                .map(|time| format!(" <t:{}:R>", unix_secs(time)))
+                .unwrap_or_default();
+            format!(
+                "`rev {}` {}{}{}",
+                r.rev,
+                describe_action(r.action),
+                by,
+                when
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let selected = &revisions[index];
+    let previous = index
+        .checked_sub(1)
+        .map(|i| revision_text(&revisions[i]))
+        .unwrap_or_default();
+    let mut diff = escape_fence(&line_diff(&previous, &revision_text(selected)));
+    if diff.len() > MAX_DIFF_LEN {
+        let mut cut = MAX_DIFF_LEN;
+        while !diff.is_char_boundary(cut) {
+            cut -= 1;
+        }
+        diff.truncate(cut);
+        diff.push_str("…\n");
+    }
+
+    let embed = CreateEmbed::default()
+        .title(format!("History of `{}`", alias))
+        .field("Revisions", list, false)
+        .description(format!(
+            "Changes in rev {}:\n```diff\n{}```",
+            selected.rev, diff
+        ))
+        .color(Colour::BLUE);
+
+    ctx.send(CreateReply::default().embed(embed).ephemeral(ephemeral))
+        .await?;
+
+    Ok(())
+}
+
+#[poise::command(slash_command)]
+/// Restores an alias, including deleted ones, to an earlier revision.
+pub async fn alias_restore(
+    ctx: Context<'_>,
+    #[description = "Alias to restore"]
+    #[autocomplete = "autocomplete_history"]
+    alias: String,
+    #[description = "Revision to restore, see /alias_history"] rev: u32,
+    #[description = "Your own alias or the server's? (default: user)"] scope: Option<AliasScope>,
+    #[description = "Send the response directly to you?"] ephemeral: Option<bool>,
+) -> Result<(), Error> {
+    let ephemeral = bot::defer_based_on_ephemeral(ctx, ephemeral).await?;
+    let scope = scope.unwrap_or(AliasScope::User);
+
+    let Some(owner) = editable_owner(ctx, scope, ephemeral).await? else {
+        return Ok(());
+    };
+
+    let revision = history_store(scope)
+        .read()
+        .await
+        .get(&owner)
+        .and_then(|m| m.get(&alias))
+        .and_then(|revisions| revisions.iter().find(|r| r.rev == rev))
+        .cloned();
+
+    let Some(message) = revision.map(|r| r.message) else {
+        error_text(
+            &ctx,
+            ephemeral,
+            &format!("Alias `{}` has no revision {}.", alias, rev),
+        )
+        .await;
+        return Ok(());
+    };
+
+    let previous = {
+        let mut store = scope.store().write().await;
+        let owner_map = store.entry(owner).or_default();
+        // Keep the usage stats of the current version if there is one
+        let message = match owner_map.get(&alias) {
+            Some(current) => SavedMessage {
+                uses: current.uses,
+                last_used: current.last_used,
+                ..message.clone()
+            },
+            None => message.clone(),
+        };
+        owner_map.insert(alias.clone(), message)
+    };
+
+    record_revision(
+        scope,
+        owner,
+        &alias,
+        ctx.author().id.get(),
+        RevisionAction::Restored(rev),
+        previous,
+        message,
+    )
+    .await;
+
+    if let Err(e) = save_messages_to_file(scope).await {
+        error_text(&ctx, ephemeral, &format!("Failed to save: {}", e)).await;
+        return Ok(());
+    }
+
+    ctx.send(
+        CreateReply::default()
+            .content(format!("⏪ Restored `{}` to rev {}.", alias, rev))
+            .ephemeral(ephemeral),
+    )
+    .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diffs_cannot_close_their_code_block() {
+        for text in ["```", "````", "a```b``c", "`````\n```"] {
+            let escaped = escape_fence(text);
+            assert!(!escaped.contains("```"), "{:?}", escaped);
+            assert_eq!(escaped.replace('\u{200b}', ""), text);
+        }
+        assert_eq!(escape_fence("`code`"), "`code`");
+    }
+}
@@ -1,20 +1,525 @@
+use std::{collections::HashMap, path::Path, time::SystemTime};
+
+use once_cell::sync::Lazy;
 use poise::CreateReply;
+use serde::{Deserialize, Serialize};
 use serenity::{
-    all::{CreateAttachment, CreateEmbed},
+    all::{
+        Attachment, AutoArchiveDuration, AutocompleteChoice, CreateEmbed, CreateEmbedFooter,
+        CreateThread, Message,
+    },
     futures::StreamExt,
 };
+use tokio::{fs, sync::RwLock};
 
+use super::{
+    ai_tools::ToolBox,
+    ai_usage::{check_budget, record_usage},
+    personas::{autocomplete_persona, resolve_persona},
+};
 use crate::{
-    Context, Error,
-    utils::bot::{self, error_and_return, error_text, is_deepseek},
+    Context, Data, Error,
+    config::Persona,
+    utils::{
+        attachments::{PromptAttachments, read_attachments},
+        bot::{self, error_text, is_deepseek, user_in_whitelist},
+        llm::{
+            self, ChatEvent, ChatMessage, ChatRequest, DeltaStream, ImageInput, LlmError,
+            TokenUsage, ToolCall, ToolSpec,
+        },
+        stream_output::{ChannelSink, MessageSink, ReplySink, StreamOutput},
+    },
 };
 
+/// Model used by `/deepseek`, as `provider/model`.
+const DEEPSEEK_MODEL: &str = "deepseek/deepseek-chat";
+const SESSIONS_PATH: &str = "deepseek_sessions.json";
+/// How often the model may call tools before it has to answer.
+const MAX_TOOL_ROUNDS: usize = 3;
+
+/// The message history of one conversation.
+#[derive(Serialize, Deserialize, Clone)]
+struct Session {
+    messages: Vec<ChatMessage>,
+    /// Model the conversation was last continued with, as `provider/model`.
+    #[serde(default)]
+    model: Option<String>,
+    /// Persona the conversation was last continued with.
+    #[serde(default)]
+    persona: Option<String>,
+    /// Who started the conversation, set once it moved into a thread.
+    #[serde(default)]
+    owner: Option<u64>,
+    updated: SystemTime,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self {
+            messages: Vec::new(),
+            model: None,
+            persona: None,
+            owner: None,
+            updated: SystemTime::now(),
+        }
+    }
+}
+
+/// Conversations per user and command, and per Discord thread for conversations
+/// continued in a thread.
+#[derive(Serialize, Deserialize, Default)]
+struct Sessions {
+    /// `/deepseek` conversations.
+    users: HashMap<u64, Session>,
+    /// `/ai` conversations, kept apart so models and personas don't mix.
+    #[serde(default)]
+    ai_users: HashMap<u64, Session>,
+    threads: HashMap<u64, Session>,
+}
+
+/// The command a conversation outside of threads belongs to.
+#[derive(Clone, Copy)]
+enum AiCommand {
+    Deepseek,
+    Ai,
+}
+
+/// Identifies which conversation a request belongs to.
+#[derive(Clone, Copy)]
+enum SessionKey {
+    User(AiCommand, u64),
+    Thread(u64),
+}
+
+impl Sessions {
+    fn map(&self, key: SessionKey) -> (&HashMap<u64, Session>, u64) {
+        match key {
+            SessionKey::User(AiCommand::Deepseek, id) => (&self.users, id),
+            SessionKey::User(AiCommand::Ai, id) => (&self.ai_users, id),
+            SessionKey::Thread(id) => (&self.threads, id),
+        }
+    }
+
+    fn get(&self, key: SessionKey) -> Option<&Session> {
+        let (sessions, id) = self.map(key);
+        sessions.get(&id)
+    }
+
+    fn get_mut(&mut self, key: SessionKey) -> &mut Session {
+        let (sessions, id) = match key {
+            SessionKey::User(AiCommand::Deepseek, id) => (&mut self.users, id),
+            SessionKey::User(AiCommand::Ai, id) => (&mut self.ai_users, id),
+            SessionKey::Thread(id) => (&mut self.threads, id),
+        };
+        sessions.entry(id).or_default()
+    }
+}
+
+static SESSIONS: Lazy<RwLock<Sessions>> = Lazy::new(|| RwLock::new(Sessions::default()));
+
+/// Load conversation sessions from disk into memory at startup.
+pub async fn load_sessions_from_file() -> Result<(), std::io::Error> {
+    if Path::new(SESSIONS_PATH).exists() {
+        let data = fs::read_to_string(SESSIONS_PATH).await?;
+        *SESSIONS.write().await = serde_json::from_str(&data)?;
+    }
+    Ok(())
+}
+
+/// Saves all conversation sessions to disk as pretty JSON.
+async fn save_sessions_to_file() {
+    let json = {
+        let sessions = SESSIONS.read().await;
+        serde_json::to_string_pretty(&*sessions)
+    };
+    match json {
+        Ok(json) => {
+            if let Err(e) = fs::write(SESSIONS_PATH, json).await {
+                tracing::warn!("Failed to save deepseek sessions: {}", e);
+            }
+        }
+        Err(e) => tracing::warn!("Failed to serialize deepseek sessions: {}", e),
+    }
+}
+
+/// Drops the oldest messages until the history fits into `budget` tokens.
+/// The latest message is always kept.
+fn trim_to_budget(messages: &mut Vec<ChatMessage>, budget: usize) {
+    let mut total = llm::estimate_tokens(messages);
+    let mut drop = 0;
+    while total > budget as u64 && drop + 1 < messages.len() {
+        total -= llm::estimate_tokens(&messages[drop..=drop]);
+        drop += 1;
+    }
+    messages.drain(..drop);
+}
+
+/// Appends the user's prompt to a session and returns the history to send,
+/// starting with the persona's system prompt.
+async fn push_prompt(
+    key: SessionKey,
+    model: &str,
+    persona: Option<&(String, Persona)>,
+    prompt: &str,
+    budget: usize,
+) -> Vec<ChatMessage> {
+    let system = persona.map(|(_, persona)| ChatMessage::system(&persona.system_prompt));
+    let system_tokens = system
+        .as_ref()
+        .map_or(0, |m| llm::estimate_tokens(std::slice::from_ref(m)));
+
+    let mut sessions = SESSIONS.write().await;
+    let session = sessions.get_mut(key);
+    session.model = Some(model.to_string());
+    session.persona = persona.map(|(name, _)| name.clone());
+    session.messages.push(ChatMessage::user(prompt));
+    trim_to_budget(
+        &mut session.messages,
+        budget.saturating_sub(system_tokens as usize),
+    );
+    session.updated = SystemTime::now();
+
+    system
+        .into_iter()
+        .chain(session.messages.iter().cloned())
+        .collect()
+}
+
+/// Stores the assistant's answer in a session, or drops the unanswered
+/// prompt again if the request failed.
+async fn finish_exchange(key: SessionKey, answer: Option<&str>) {
+    {
+        let mut sessions = SESSIONS.write().await;
+        let session = sessions.get_mut(key);
+        match answer {
+            Some(answer) if !answer.is_empty() => {
+                session.messages.push(ChatMessage::assistant(answer));
+            }
+            _ => {
+                if session.messages.last().is_some_and(|m| m.role == "user") {
+                    session.messages.pop();
+                }
+            }
+        }
+        session.updated = SystemTime::now();
+    }
+    save_sessions_to_file().await;
+}
+
+/// Returns the model and persona a conversation was last continued with.
+async fn session_settings(key: SessionKey) -> (Option<String>, Option<String>) {
+    let sessions = SESSIONS.read().await;
+    sessions
+        .get(key)
+        .map_or((None, None), |s| (s.model.clone(), s.persona.clone()))
+}
+
+/// Returns the session key for a channel if it is a conversation thread.
+async fn thread_session(channel_id: u64) -> Option<SessionKey> {
+    SESSIONS
+        .read()
+        .await
+        .threads
+        .contains_key(&channel_id)
+        .then_some(SessionKey::Thread(channel_id))
+}
+
+/// Resolves the model and starts streaming the answer to `messages`.
+pub(super) async fn chat_stream(
+    data: &Data,
+    model: &str,
+    persona: Option<&Persona>,
+    messages: Vec<ChatMessage>,
+    tools: Vec<ToolSpec>,
+) -> Result<DeltaStream, LlmError> {
+    let (client, model) = {
+        let config = data.config.read().await;
+        llm::resolve_model(&config, model)?
+    };
+    client
+        .chat_stream(&ChatRequest {
+            model,
+            messages,
+            temperature: persona.and_then(|p| p.temperature),
+            max_tokens: persona.and_then(|p| p.max_tokens),
+            tools,
+        })
+        .await
+        .inspect_err(|e| tracing::error!("LLM request error: {}", e))
+}
+
+/// One streamed round of an answer.
+struct Round {
+    text: String,
+    usage: Option<TokenUsage>,
+    tool_calls: Vec<ToolCall>,
+    error: Option<LlmError>,
+}
+
+/// Streams the answer into `output`, after `prefix`, until it is complete or fails.
+async fn stream_answer<S: MessageSink>(
+    deltas: &mut DeltaStream,
+    output: &mut StreamOutput<S>,
+    prefix: &str,
+) -> Result<Round, Error> {
+    let mut round = Round {
+        text: String::new(),
+        usage: None,
+        tool_calls: Vec::new(),
+        error: None,
+    };
+
+    while let Some(item) = deltas.next().await {
+        match item {
+            Ok(ChatEvent::Delta(content)) => {
+                round.text.push_str(&content);
+                output.update(&format!("{}{}", prefix, round.text)).await?;
+            }
+            Ok(ChatEvent::Usage(reported)) => round.usage = Some(reported),
+            Ok(ChatEvent::ToolCalls(calls)) => round.tool_calls.extend(calls),
+            Err(e) => {
+                tracing::error!("Stream error: {}", e);
+                round.error = Some(e);
+                break;
+            }
+        }
+    }
+
+    Ok(round)
+}
+
+/// A complete answer, possibly produced over several tool calling rounds.
+struct Answer {
+    /// The model's final text, which is kept in the conversation.
+    text: String,
+    /// The text shown to the user, including the tool calls made.
+    shown: String,
+    /// Usage summed over all rounds, None if a round did not report it.
+    usage: Option<TokenUsage>,
+    error: Option<LlmError>,
+}
+
+/// Answers `messages`, running the tool calls the model requests in between.
+/// Fails with the request error if the first request cannot be made.
+async fn converse<S: MessageSink>(
+    data: &Data,
+    model: &str,
+    persona: Option<&Persona>,
+    user_id: u64,
+    mut messages: Vec<ChatMessage>,
+    output: &mut StreamOutput<S>,
+) -> Result<Result<Answer, LlmError>, Error> {
+    let toolbox = {
+        let config = data.config.read().await;
+        llm::supports_tools(&config, model).then(|| ToolBox::new(&config, user_id))
+    };
+    let specs = toolbox.as_ref().map(ToolBox::specs).unwrap_or_default();
+
+    let mut answer = Answer {
+        text: String::new(),
+        shown: String::new(),
+        usage: Some(TokenUsage::default()),
+        error: None,
+    };
+    for round_index in 0..=MAX_TOOL_ROUNDS {
+        // The last round gets no tools so the model has to answer
+        let tools = if round_index < MAX_TOOL_ROUNDS {
+            specs.clone()
+        } else {
+            Vec::new()
+        };
+        let mut deltas = match chat_stream(data, model, persona, messages.clone(), tools).await {
+            Ok(deltas) => deltas,
+            Err(e) if round_index == 0 => return Ok(Err(e)),
+            Err(e) => {
+                answer.error = Some(e);
+                break;
+            }
+        };
+
+        let round = stream_answer(&mut deltas, output, &answer.shown).await?;
+        answer.usage = answer
+            .usage
+            .zip(round.usage)
+            .map(|(sum, usage)| TokenUsage {
+                prompt_tokens: sum.prompt_tokens + usage.prompt_tokens,
+                completion_tokens: sum.completion_tokens + usage.completion_tokens,
+            });
+
+        let Some(toolbox) = toolbox
+            .as_ref()
+            .filter(|_| round.error.is_none() && !round.tool_calls.is_empty())
+        else {
+            answer.shown.push_str(&round.text);
+            answer.text = round.text;
+            answer.error = round.error;
+            break;
+        };
+
+        if !round.text.trim().is_empty() {
+            answer
+                .shown
+                .push_str(&format!("{}\n", round.text.trim_end()));
+        }
+        let mut results = Vec::new();
+        for call in &round.tool_calls {
+            let arguments: String = call.function.arguments.chars().take(100).collect();
+            answer
+                .shown
+                .push_str(&format!("> 🔧 `{}` {}\n", call.function.name, arguments));
+            output.update(&answer.shown).await?;
+            results.push(ChatMessage::tool_result(
+                call.id.clone(),
+                toolbox.call(call).await,
+            ));
+        }
+        answer.shown.push('\n');
+        messages.push(ChatMessage::tool_request(round.text, round.tool_calls));
+        messages.extend(results);
+    }
+
+    Ok(Ok(answer))
+}
+
+/// Suggests the configured models matching the input.
+pub(super) async fn autocomplete_model(ctx: Context<'_>, partial: &str) -> Vec<AutocompleteChoice> {
+    let config = ctx.data().config.read().await;
+    let partial = partial.to_lowercase();
+    llm::available_models(&config)
+        .into_iter()
+        .filter(|model| model.to_lowercase().contains(&partial))
+        .take(25)
+        .map(|model| AutocompleteChoice::new(model.clone(), model))
+        .collect()
+}
+
+/// Reads the attachments of a prompt, checking that the model accepts images.
+async fn prompt_attachments(
+    data: &Data,
+    model: &str,
+    attachments: &[Attachment],
+) -> Result<PromptAttachments, String> {
+    let files = read_attachments(attachments).await?;
+    if !files.images.is_empty() && !llm::supports_vision(&*data.config.read().await, model) {
+        return Err(format!("{} does not support images", model));
+    }
+    Ok(files)
+}
+
+/// Tells the user which attachments were left out of the prompt, and why.
+fn skipped_note(files: &PromptAttachments) -> Option<String> {
+    (!files.skipped.is_empty()).then(|| files.skipped.join("\n"))
+}
+
+/// The prompt with the attached text files inlined.
+fn prompt_text(text: &str, files: &PromptAttachments) -> String {
+    let text = if text.trim().is_empty() {
+        "Describe the attached files."
+    } else {
+        text
+    };
+    format!("{}{}", text, files.text)
+}
+
+/// Sends the images with the latest prompt only, they are not kept in the session.
+fn attach_images(history: &mut [ChatMessage], images: Vec<ImageInput>) {
+    if let Some(prompt) = history.last_mut() {
+        prompt.images = images;
+    }
+}
+
+/// Options of a prompt sent by one of the AI commands.
+struct Prompt {
+    command: AiCommand,
+    text: String,
+    /// Explicitly requested model, overriding the persona's.
+    model: Option<String>,
+    /// Model used when neither the request nor the persona names one.
+    fallback_model: String,
+    persona: Option<String>,
+    thread: Option<bool>,
+    /// Images and text files sent with the prompt.
+    attachments: Vec<Attachment>,
+}
+
 #[poise::command(slash_command)]
 pub async fn deepseek(
     ctx: Context<'_>,
     #[description = "Prompt"] text: String,
+    #[description = "Persona to answer as"]
+    #[autocomplete = "autocomplete_persona"]
+    persona: Option<String>,
+    #[description = "Continue the conversation in a new thread?"] thread: Option<bool>,
+    #[description = "Image or text file to ask about"] file: Option<Attachment>,
     #[description = "Send the response directly to you?"] ephemeral: Option<bool>,
 ) -> Result<(), Error> {
+    let prompt = Prompt {
+        command: AiCommand::Deepseek,
+        text,
+        model: None,
+        fallback_model: DEEPSEEK_MODEL.to_string(),
+        persona,
+        thread,
+        attachments: file.into_iter().collect(),
+    };
+    ask(ctx, prompt, ephemeral).await
+}
+
+#[poise::command(slash_command)]
+/// Asks one of the configured AI models.
+pub async fn ai(
+    ctx: Context<'_>,
+    #[description = "Prompt"] text: String,
+    #[description = "Model to use"]
+    #[autocomplete = "autocomplete_model"]
+    model: Option<String>,
+    #[description = "Persona to answer as"]
+    #[autocomplete = "autocomplete_persona"]
+    persona: Option<String>,
+    #[description = "Continue the conversation in a new thread?"] thread: Option<bool>,
+    #[description = "Image or text file to ask about"] file: Option<Attachment>,
+    #[description = "Send the response directly to you?"] ephemeral: Option<bool>,
+) -> Result<(), Error> {
+    let fallback_model = ctx.data().config.read().await.default_model.clone();
+    let prompt = Prompt {
+        command: AiCommand::Ai,
+        text,
+        model,
+        fallback_model,
+        persona,
+        thread,
+        attachments: file.into_iter().collect(),
+    };
+    ask(ctx, prompt, ephemeral).await
+}
+
+#[poise::command(context_menu_command = "Ask AI about this message")]
+/// Asks the default AI model about a message and its attachments.
+pub async fn ask_ai_message(ctx: Context<'_>, message: Message) -> Result<(), Error> {
+    let fallback_model = ctx.data().config.read().await.default_model.clone();
+    let prompt = Prompt {
+        command: AiCommand::Ai,
+        text: message.content,
+        model: None,
+        fallback_model,
+        persona: None,
+        thread: None,
+        attachments: message.attachments,
+    };
+    ask(ctx, prompt, None).await
+}
+
+/// Sends a prompt to its model, streaming the answer into the reply.
+async fn ask(ctx: Context<'_>, prompt: Prompt, ephemeral: Option<bool>) -> Result<(), Error> {
+    let Prompt {
+        command,
+        text,
+        model,
+        fallback_model,
+        persona,
+        thread,
+        attachments,
+    } = prompt;
     let ephemeral = bot::defer_based_on_ephemeral(ctx, ephemeral).await?;
 
     // Authorization check
@@ -23,116 +528,318 @@ pub async fn deepseek(
         return Ok(());
     }
 
-    if text.trim().is_empty() {
+    if text.trim().is_empty() && attachments.is_empty() {
         error_text(&ctx, ephemeral, "Please provide a prompt").await;
         return Ok(());
     }
 
-    // Get API key safely
-    let api_key = match ctx.data().config.read().await.deepseek_token.clone() {
-        Some(key) => key,
-        None => {
-            error_text(&ctx, ephemeral, "Sorry! but there's no deepseek key!").await;
+    // Inside a conversation thread the thread's history is continued
+    let key = thread_session(ctx.channel_id().get())
+        .await
+        .unwrap_or(SessionKey::User(command, ctx.author().id.get()));
+    let session_persona = match key {
+        SessionKey::Thread(_) => session_settings(key).await.1,
+        SessionKey::User(..) => None,
+    };
+
+    let guild_id = ctx.guild_id().map(|g| g.get());
+    let (budget, persona) = {
+        let config = ctx.data().config.read().await;
+        if let Err(reason) = check_budget(&config, ctx.author().id.get(), guild_id).await {
+            error_text(&ctx, ephemeral, &reason).await;
             return Ok(());
         }
+        let persona = resolve_persona(
+            &config,
+            persona.as_deref(),
+            session_persona.as_deref(),
+            guild_id,
+        );
+        (config.deepseek_history_tokens, persona)
     };
+    let persona = match persona {
+        Ok(persona) => persona,
+        Err(reason) => {
+            error_text(&ctx, ephemeral, &reason).await;
+            return Ok(());
+        }
+    };
+    let model = model
+        .or_else(|| persona.as_ref().and_then(|(_, p)| p.model.clone()))
+        .unwrap_or(fallback_model);
+
+    let files = match prompt_attachments(ctx.data(), &model, &attachments).await {
+        Ok(files) => files,
+        Err(reason) => {
+            error_text(&ctx, ephemeral, &reason).await;
+            return Ok(());
+        }
+    };
+    let note = skipped_note(&files);
+    if text.trim().is_empty() && files.is_empty() {
+        let reason = note.unwrap_or_else(|| "There is nothing to ask about".to_string());
+        error_text(&ctx, ephemeral, &reason).await;
+        return Ok(());
+    }
+    let text = prompt_text(&text, &files);
+    let mut history = push_prompt(key, &model, persona.as_ref(), &text, budget).await;
+    attach_images(&mut history, files.images);
+    let prompt_estimate = llm::estimate_tokens(&history);
+
+    let mut header = CreateEmbed::new()
+        .title(format!("{} response for prompt:", model))
+        .description(text.chars().take(4000).collect::<String>());
+    if let Some((name, _)) = &persona {
+        header = header.footer(CreateEmbedFooter::new(format!("Persona: {}", name)));
+    }
+    if let Some(note) = note {
+        header = header.field("Skipped attachments", note, false);
+    }
 
     // Initial reply (deferred) to user
     let reply = ctx
         .send(
             CreateReply::default()
                 .content("Please wait...")
+                .embed(header.clone())
                 .ephemeral(ephemeral),
         )
         .await?;
 
-    // Setup request
-    let client = reqwest::Client::new();
-    let response = client
-        .post("https://api.deepseek.com/v1/chat/completions")
-        .header("Authorization", format!("Bearer {}", api_key))
-        .header("Accept", "text/event-stream")
-        .json(&serde_json::json!({
-            "model": "deepseek-chat",
-            "messages": [{"role": "user", "content": text}],
-            "stream": true
-        }))
-        .send()
-        .await
-        .map_err(|e| {
-            tracing::error!("Deepseek request error: {}", e);
-            e
-        });
-
-    let response = match response {
-        Ok(resp) => resp,
-        Err(e) => return error_and_return(&ctx, ephemeral, e).await,
+    // Stream the response incrementally
+    let mut output = StreamOutput::new(ReplySink::new(ctx, ephemeral, reply, Some(header)));
+    let answer = match converse(
+        ctx.data(),
+        &model,
+        persona.as_ref().map(|(_, p)| p),
+        ctx.author().id.get(),
+        history,
+        &mut output,
+    )
+    .await?
+    {
+        Ok(answer) => answer,
+        Err(e) => {
+            finish_exchange(key, None).await;
+            output.sink().first().delete(ctx).await.ok();
+            error_text(&ctx, ephemeral, &e.to_string()).await;
+            return Ok(());
+        }
     };
-
-    if !response.status().is_success() {
-        let err_text = response.text().await.unwrap_or_default();
-        error_text(&ctx, ephemeral, &format!("API error: {}", err_text)).await;
-        reply.delete(ctx).await.ok();
-        return Ok(());
+    if let Some(e) = &answer.error {
+        error_text(&ctx, ephemeral, &format!("Stream error: {}", e)).await;
     }
 
-    // Stream the response incrementally
-    let mut stream = response.bytes_stream();
-    let mut collected = String::new();
+    finish_exchange(key, Some(&answer.text)).await;
+    record_usage(
+        ctx.author().id.get(),
+        ctx.guild_id().map(|g| g.get()),
+        &model,
+        answer.usage,
+        prompt_estimate,
+        &answer.shown,
+    )
+    .await;
+    output.finish(&answer.shown).await?;
 
-    while let Some(item) = stream.next().await {
-        match item {
-            Ok(chunk) => {
-                let text_chunk = String::from_utf8_lossy(&chunk);
-                for line in text_chunk.lines() {
-                    if let Some(data) = line.strip_prefix("data: ") {
-                        if data == "[DONE]" {
-                            break;
-                        }
-
-                        if let Ok(json) = serde_json::from_str::<serde_json::Value>(data) {
-                            if let Some(content) = json["choices"][0]["delta"]["content"].as_str() {
-                                collected.push_str(content);
-
-                                // Update reply every 5 characters or when big enough
-                                if collected.len() % 5 == 0 && !collected.is_empty() {
-                                    let content_to_send = if collected.len() > 1750 {
-                                        format!("{}\n(wait for the rest...)", &collected[..1750])
-                                    } else {
-                                        collected.clone()
-                                    };
-                                    reply
-                                        .edit(ctx, CreateReply::default().content(content_to_send))
-                                        .await?;
-                                }
-                            }
-                        }
-                    }
+    if thread.unwrap_or(false) && !ephemeral {
+        let message = output.sink().first().message().await?;
+        let title: String = text.chars().take(90).collect();
+        match message
+            .channel_id
+            .create_thread_from_message(
+                ctx.http(),
+                message.id,
+                CreateThread::new(title).auto_archive_duration(AutoArchiveDuration::OneDay),
+            )
+            .await
+        {
+            Ok(thread) => {
+                // Move the conversation so far into the thread
+                {
+                    let mut sessions = SESSIONS.write().await;
+                    let session = Session {
+                        owner: Some(ctx.author().id.get()),
+                        ..sessions.get_mut(key).clone()
+                    };
+                    sessions.threads.insert(thread.id.get(), session);
                 }
+                save_sessions_to_file().await;
+                thread
+                    .say(
+                        ctx.http(),
+                        "Reply in this thread to continue the conversation.",
+                    )
+                    .await?;
             }
             Err(e) => {
-                tracing::error!("Stream error: {}", e);
-                error_text(&ctx, ephemeral, &format!("Stream error: {}", e)).await;
-                break;
+                error_text(&ctx, ephemeral, &format!("Failed to create thread: {}", e)).await;
             }
         }
     }
 
-    reply.delete(ctx).await.ok();
+    Ok(())
+}
 
-    ctx.send(
-        CreateReply::default()
-            .embed(
-                CreateEmbed::new()
-                    .title("Deepseek response for prompt:")
-                    .description(text),
-            )
-            .attachment(CreateAttachment::bytes(
-                collected.as_bytes(),
-                "deepseek_response.txt",
-            )),
+/// Continues a conversation when someone writes in one of its threads.
+pub async fn handle_deepseek_thread_message(
+    ctx: &serenity::all::Context,
+    data: &Data,
+    msg: &Message,
+) -> Result<(), Error> {
+    if msg.author.bot {
+        return Ok(());
+    }
+    let Some(key) = thread_session(msg.channel_id.get()).await else {
+        return Ok(());
+    };
+
+    let guild_id = msg.guild_id.map(|g| g.get());
+    let (budget, allowed, over_budget) = {
+        let config = data.config.read().await;
+        (
+            config.deepseek_history_tokens,
+            user_in_whitelist(
+                config.deepseek_whitelist_active,
+                &config.deepseek_whitelist,
+                msg.author.id.get(),
+            ),
+            check_budget(&config, msg.author.id.get(), guild_id)
+                .await
+                .err(),
+        )
+    };
+    if !allowed {
+        return Ok(());
+    }
+
+    // Mentioning the bot is not part of the prompt
+    let bot_mention = format!("<@{}>", ctx.cache.current_user().id);
+    let prompt = msg.content.replace(&bot_mention, "").trim().to_string();
+    if prompt.is_empty() && msg.attachments.is_empty() {
+        return Ok(());
+    }
+    if let Some(reason) = over_budget {
+        msg.reply(&ctx.http, reason).await?;
+        return Ok(());
+    }
+
+    let (model, persona_name) = session_settings(key).await;
+    let model = model.unwrap_or_else(|| DEEPSEEK_MODEL.to_string());
+    let persona = {
+        let config = data.config.read().await;
+        resolve_persona(&config, None, persona_name.as_deref(), guild_id)
+            .ok()
+            .flatten()
+    };
+    let files = match prompt_attachments(data, &model, &msg.attachments).await {
+        Ok(files) => files,
+        Err(reason) => {
+            msg.reply(&ctx.http, reason).await?;
+            return Ok(());
+        }
+    };
+    if let Some(note) = skipped_note(&files) {
+        msg.reply(&ctx.http, note).await?;
+    }
+    if prompt.is_empty() && files.is_empty() {
+        return Ok(());
+    }
+    let prompt = prompt_text(&prompt, &files);
+    let mut history = push_prompt(key, &model, persona.as_ref(), &prompt, budget).await;
+    attach_images(&mut history, files.images);
+    let prompt_estimate = llm::estimate_tokens(&history);
+    let reply = msg.reply(&ctx.http, "Please wait...").await?;
+
+    let mut output = StreamOutput::new(ChannelSink::new(&ctx.http, reply));
+    let answer = match converse(
+        data,
+        &model,
+        persona.as_ref().map(|(_, p)| p),
+        msg.author.id.get(),
+        history,
+        &mut output,
+    )
+    .await?
+    {
+        Ok(answer) => answer,
+        Err(e) => {
+            finish_exchange(key, None).await;
+            output.finish(&e.to_string()).await?;
+            return Ok(());
+        }
+    };
+
+    finish_exchange(key, Some(&answer.text)).await;
+    record_usage(
+        msg.author.id.get(),
+        guild_id,
+        &model,
+        answer.usage,
+        prompt_estimate,
+        &answer.shown,
     )
-    .await?;
+    .await;
+    output.finish(&answer.shown).await?;
+
+    Ok(())
+}
+
+#[poise::command(slash_command)]
+/// Forgets your /deepseek and /ai conversations, or the current thread's.
+pub async fn deepseek_reset(
+    ctx: Context<'_>,
+    #[description = "Send the response directly to you?"] ephemeral: Option<bool>,
+) -> Result<(), Error> {
+    let ephemeral = bot::defer_based_on_ephemeral(ctx, ephemeral).await?;
+    let channel_id = ctx.channel_id().get();
+    let user_id = ctx.author().id.get();
+
+    // A thread's conversation is shared, only its starter or moderators may wipe it
+    let thread_owner = SESSIONS
+        .read()
+        .await
+        .threads
+        .get(&channel_id)
+        .map(|session| session.owner);
+    if let Some(owner) = thread_owner
+        && owner != Some(user_id)
+    {
+        let can_manage = ctx.author_member().await.is_some_and(|member| {
+            member
+                .permissions
+                .is_some_and(|permissions| permissions.manage_threads())
+        });
+        if !can_manage {
+            error_text(
+                &ctx,
+                ephemeral,
+                "Only the thread's starter or members who can manage threads may reset it.",
+            )
+            .await;
+            return Ok(());
+        }
+    }
+
+    let cleared = {
+        let mut sessions = SESSIONS.write().await;
+        if thread_owner.is_some() {
+            sessions.threads.remove(&channel_id).is_some()
+        } else {
+            let deepseek = sessions.users.remove(&user_id).is_some();
+            let ai = sessions.ai_users.remove(&user_id).is_some();
+            deepseek || ai
+        }
+    };
+    save_sessions_to_file().await;
+
+    let text = if cleared {
+        "🧹 Conversation cleared."
+    } else {
+        "There was no conversation to clear."
+    };
+    ctx.send(CreateReply::default().content(text).ephemeral(ephemeral))
+        .await?;
 
     Ok(())
 }
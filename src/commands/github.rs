@@ -1,14 +1,24 @@
+use std::collections::HashMap;
+
 use poise::CreateReply;
-use serde::Deserialize;
-use serenity::all::CreateEmbed;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serenity::all::{Color, CreateEmbed};
 
+use super::github_insights::insights_reply;
 use crate::{
     Context, Error,
-    utils::bot::{self, error_and_return, error_text},
+    utils::{
+        bot::{self, error_and_return, error_text},
+        github::{self, GitHubError},
+        markdown::truncate,
+    },
 };
 
-#[derive(Deserialize)]
-struct GitHubUser {
+/// Embed field values are limited to 1024 characters.
+const FIELD_LIMIT: usize = 1024;
+
+#[derive(Deserialize, Serialize)]
+pub(super) struct GitHubUser {
     login: Option<String>,
     public_repos: Option<u32>,
     followers: Option<u32>,
@@ -25,18 +35,18 @@ struct GitHubUser {
     updated_at: Option<String>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 struct GitHubLicense {
     name: Option<String>,
 }
 
-#[derive(Deserialize)]
-struct GitHubRepo {
+#[derive(Deserialize, Serialize)]
+pub(super) struct GitHubRepo {
     #[allow(unused)]
     name: Option<String>,
-    full_name: Option<String>,
-    private: Option<bool>,
-    html_url: Option<String>,
+    pub(super) full_name: Option<String>,
+    pub(super) private: Option<bool>,
+    pub(super) html_url: Option<String>,
     description: Option<String>,
     fork: Option<bool>,
     language: Option<String>,
@@ -50,6 +60,203 @@ struct GitHubRepo {
     pushed_at: Option<String>,
     license: Option<GitHubLicense>,
     owner: Option<GitHubUser>,
+    pub(super) default_branch: Option<String>,
+}
+
+/// A user or repository as returned by the GitHub API.
+#[derive(Serialize)]
+#[serde(untagged)]
+pub(super) enum GitHubLookup {
+    User(Box<GitHubUser>),
+    Repo(Box<GitHubRepo>),
+}
+
+#[derive(Deserialize)]
+struct GitHubLabel {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct GitHubIssue {
+    number: u64,
+    title: String,
+    state: String,
+    state_reason: Option<String>,
+    html_url: String,
+    body: Option<String>,
+    user: Option<GitHubUser>,
+    #[serde(default)]
+    labels: Vec<GitHubLabel>,
+    #[serde(default)]
+    assignees: Vec<GitHubUser>,
+    comments: Option<u32>,
+    created_at: Option<String>,
+    closed_at: Option<String>,
+    /// Only present for pull requests.
+    pull_request: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct GitHubCommitRef {
+    sha: String,
+}
+
+#[derive(Deserialize)]
+struct GitHubPull {
+    merged: bool,
+    draft: Option<bool>,
+    mergeable_state: Option<String>,
+    head: GitHubCommitRef,
+    additions: Option<u32>,
+    deletions: Option<u32>,
+    changed_files: Option<u32>,
+    #[serde(default)]
+    requested_reviewers: Vec<GitHubUser>,
+    merged_at: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct GitHubReview {
+    user: Option<GitHubUser>,
+    state: String,
+}
+
+#[derive(Deserialize)]
+struct GitHubCheckRun {
+    status: String,
+    conclusion: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct GitHubCheckRuns {
+    check_runs: Vec<GitHubCheckRun>,
+}
+
+#[derive(Deserialize)]
+struct GitHubTimelineSource {
+    issue: Option<GitHubIssue>,
+}
+
+#[derive(Deserialize)]
+struct GitHubTimelineEvent {
+    event: Option<String>,
+    source: Option<GitHubTimelineSource>,
+}
+
+#[derive(Deserialize)]
+struct GitHubAsset {
+    name: String,
+    size: u64,
+    download_count: u64,
+    browser_download_url: String,
+}
+
+#[derive(Deserialize)]
+struct GitHubRelease {
+    name: Option<String>,
+    tag_name: String,
+    html_url: String,
+    body: Option<String>,
+    draft: bool,
+    prerelease: bool,
+    published_at: Option<String>,
+    author: Option<GitHubUser>,
+    #[serde(default)]
+    assets: Vec<GitHubAsset>,
+}
+
+/// What a `/github` query asks for.
+enum GitHubQuery<'a> {
+    /// `owner/repo#123`
+    Issue { repo: &'a str, number: u64 },
+    /// `owner/repo@latest` or `owner/repo@v1.2`
+    Release { repo: &'a str, tag: &'a str },
+    /// A user or `owner/repo`
+    Lookup,
+}
+
+fn parse_query(query: &str) -> GitHubQuery<'_> {
+    if let Some((repo, number)) = query.split_once('#')
+        && repo.contains('/')
+        && let Ok(number) = number.parse()
+    {
+        return GitHubQuery::Issue { repo, number };
+    }
+    if let Some((repo, tag)) = query.split_once('@')
+        && repo.contains('/')
+        && !tag.is_empty()
+    {
+        return GitHubQuery::Release { repo, tag };
+    }
+    GitHubQuery::Lookup
+}
+
+/// Fetches an API path through the shared client. Returns None if it does not exist.
+async fn get_json<T: DeserializeOwned>(
+    token: Option<&str>,
+    path: &str,
+) -> Result<Option<T>, GitHubError> {
+    match github::client().get(token, path).await {
+        Ok(value) => Ok(Some(value)),
+        Err(GitHubError::NotFound) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Looks up a repository. Returns None if it does not exist.
+pub(super) async fn fetch_repo(
+    token: Option<&str>,
+    repo: &str,
+) -> Result<Option<GitHubRepo>, GitHubError> {
+    get_json(token, &format!("repos/{}", repo)).await
+}
+
+/// Whether `repo` exists and may be shown. The token can see private repositories,
+/// which only count with `private` set, e.g. for bot admins.
+async fn repo_visible(token: Option<&str>, repo: &str, private: bool) -> Result<bool, GitHubError> {
+    Ok(fetch_repo(token, repo)
+        .await?
+        .is_some_and(|repo| private || repo.private != Some(true)))
+}
+
+/// Looks up a user, or a repository for `owner/repo`. Returns None if it does not
+/// exist, or is private without `private` set.
+pub(super) async fn fetch_github(
+    token: Option<&str>,
+    query: &str,
+    private: bool,
+) -> Result<Option<GitHubLookup>, GitHubError> {
+    Ok(if query.contains('/') {
+        fetch_repo(token, query)
+            .await?
+            .filter(|repo| private || repo.private != Some(true))
+            .map(|repo| GitHubLookup::Repo(Box::new(repo)))
+    } else {
+        get_json(token, &format!("users/{}", query))
+            .await?
+            .map(|user| GitHubLookup::User(Box::new(user)))
+    })
+}
+
+/// Reports rate limits and token problems plainly, anything else as an error.
+pub(super) async fn github_error(
+    ctx: &Context<'_>,
+    ephemeral: bool,
+    e: GitHubError,
+) -> Result<(), Error> {
+    match e {
+        GitHubError::NotFound
+        | GitHubError::NoContent
+        | GitHubError::RateLimited(_)
+        | GitHubError::Unauthorized
+        | GitHubError::Pending => {
+            error_text(ctx, ephemeral, &e.to_string()).await;
+            Ok(())
+        }
+        GitHubError::Request(_) | GitHubError::Api { .. } => {
+            error_and_return(ctx, ephemeral, e).await
+        }
+    }
 }
 
 // Updated to return the updated embed, because .field() consumes and returns new CreateEmbed
@@ -68,123 +275,629 @@ fn add_field_if_some(
     embed
 }
 
-#[poise::command(slash_command)]
-pub async fn github(
-    ctx: Context<'_>,
-    #[description = "Username or username/repo"] query: String,
-    #[description = "Send the response directly to you?"] ephemeral: Option<bool>,
-) -> Result<(), Error> {
-    let ephemeral = bot::defer_based_on_ephemeral(ctx, ephemeral).await?;
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+/// Joins lines until the field limit, noting how many were left out.
+pub(super) fn join_limited(lines: &[String]) -> String {
+    let mut out = String::new();
+    for (i, line) in lines.iter().enumerate() {
+        let more = format!("…and {} more", lines.len() - i);
+        if out.len() + line.len() + more.len() + 2 > FIELD_LIMIT {
+            out.push_str(&more);
+            break;
+        }
+        out.push_str(line);
+        out.push('\n');
+    }
+    out.trim_end().to_string()
+}
+
+fn logins(users: &[GitHubUser]) -> String {
+    users
+        .iter()
+        .filter_map(|user| user.login.as_deref())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Sums up the latest review of every reviewer and who is still asked to review.
+fn review_summary(reviews: &[GitHubReview], requested: &[GitHubUser]) -> String {
+    let mut latest: HashMap<&str, &str> = HashMap::new();
+    for review in reviews {
+        // Comments don't change an earlier verdict
+        if let Some(login) = review.user.as_ref().and_then(|u| u.login.as_deref())
+            && matches!(
+                review.state.as_str(),
+                "APPROVED" | "CHANGES_REQUESTED" | "DISMISSED"
+            )
+        {
+            latest.insert(login, &review.state);
+        }
+    }
+    let approved = latest.values().filter(|s| **s == "APPROVED").count();
+    let changes = latest
+        .values()
+        .filter(|s| **s == "CHANGES_REQUESTED")
+        .count();
 
-    let is_repo = query.contains('/');
-    let url = if is_repo {
-        format!("https://api.github.com/repos/{}", query)
+    let mut parts = Vec::new();
+    if approved > 0 {
+        parts.push(format!("✅ {} approved", approved));
+    }
+    if changes > 0 {
+        parts.push(format!("❌ {} requested changes", changes));
+    }
+    if !requested.is_empty() {
+        parts.push(format!("⏳ waiting on {}", logins(requested)));
+    }
+    if parts.is_empty() {
+        "No reviews".to_string()
     } else {
-        format!("https://api.github.com/users/{}", query)
+        parts.join("\n")
+    }
+}
+
+/// Counts check runs by outcome. Returns None if there are none.
+fn checks_summary(runs: &[GitHubCheckRun]) -> Option<String> {
+    if runs.is_empty() {
+        return None;
+    }
+    let (mut passed, mut failed, mut skipped, mut running) = (0, 0, 0, 0);
+    for run in runs {
+        match (run.status.as_str(), run.conclusion.as_deref()) {
+            ("completed", Some("success")) => passed += 1,
+            ("completed", Some("neutral" | "skipped")) => skipped += 1,
+            ("completed", _) => failed += 1,
+            _ => running += 1,
+        }
+    }
+
+    let mut parts = Vec::new();
+    for (count, label) in [
+        (passed, "✅ passed"),
+        (failed, "❌ failed"),
+        (running, "⏳ running"),
+        (skipped, "⏭️ skipped"),
+    ] {
+        if count > 0 {
+            parts.push(format!("{} {}", count, label));
+        }
+    }
+    Some(parts.join(" · "))
+}
+
+/// Lists the pull requests cross-referencing an issue in its timeline.
+fn linked_pulls(timeline: Vec<GitHubTimelineEvent>) -> Vec<String> {
+    let mut pulls: Vec<_> = timeline
+        .into_iter()
+        .filter(|event| event.event.as_deref() == Some("cross-referenced"))
+        .filter_map(|event| event.source?.issue)
+        .filter(|source| source.pull_request.is_some())
+        .collect();
+    // A pull request referencing the issue several times is listed once
+    pulls.sort_by_key(|pr| pr.number);
+    pulls.dedup_by_key(|pr| pr.number);
+    pulls
+        .iter()
+        .map(|pr| {
+            format!(
+                "[#{}]({}) {}",
+                pr.number,
+                pr.html_url,
+                truncate(&pr.title, 60)
+            )
+        })
+        .collect()
+}
+
+pub(super) async fn issue_embed(
+    token: Option<&str>,
+    repo: &str,
+    number: u64,
+    private: bool,
+) -> Result<Option<CreateEmbed>, GitHubError> {
+    if !repo_visible(token, repo, private).await? {
+        return Ok(None);
+    }
+    let Some(issue) =
+        get_json::<GitHubIssue>(token, &format!("repos/{}/issues/{}", repo, number)).await?
+    else {
+        return Ok(None);
     };
 
-    let client = reqwest::Client::new();
-    let res = client
-        .get(&url)
-        .header("User-Agent", "poise-bot")
-        .send()
-        .await
-        .map_err(|e| {
-            tracing::error!("Request failed: {}", e);
-            e
-        })?;
-
-    if !res.status().is_success() {
-        error_text(&ctx, ephemeral, "GitHub user or repository not found.").await;
-        return Ok(());
+    let mut embed = CreateEmbed::default()
+        .title(truncate(
+            &format!("{}#{}: {}", repo, issue.number, issue.title),
+            256,
+        ))
+        .url(&issue.html_url);
+    if let Some(body) = &issue.body {
+        embed = embed.description(truncate(body, 1000));
+    }
+    if let Some(author) = &issue.user {
+        embed = add_field_if_some(embed, "Author", author.login.clone(), true);
+        if let Some(avatar) = &author.avatar_url {
+            embed = embed.thumbnail(avatar);
+        }
     }
 
-    if is_repo {
-        let repo: GitHubRepo = match res.json().await {
-            Ok(repo) => repo,
-            Err(e) => return error_and_return(&ctx, ephemeral, e).await,
+    let (state, color) = if issue.pull_request.is_some() {
+        let pull =
+            get_json::<GitHubPull>(token, &format!("repos/{}/pulls/{}", repo, number)).await?;
+        let Some(pull) = pull else {
+            return Ok(None);
         };
+        let reviews_path = format!("repos/{}/pulls/{}/reviews?per_page=100", repo, number);
+        let checks_path = format!(
+            "repos/{}/commits/{}/check-runs?per_page=100",
+            repo, pull.head.sha
+        );
+        let (reviews, checks) = tokio::join!(
+            get_json::<Vec<GitHubReview>>(token, &reviews_path),
+            get_json::<GitHubCheckRuns>(token, &checks_path),
+        );
 
-        let mut embed = CreateEmbed::default()
-            .title(repo.full_name.clone().unwrap_or_default())
-            .url(repo.html_url.clone().unwrap_or_default());
+        embed = embed.field(
+            "Reviews",
+            review_summary(&reviews?.unwrap_or_default(), &pull.requested_reviewers),
+            true,
+        );
+        embed = add_field_if_some(
+            embed,
+            "Checks",
+            // Tokens without the checks permission are refused, which should not hide the rest
+            checks
+                .ok()
+                .flatten()
+                .and_then(|checks| checks_summary(&checks.check_runs)),
+            true,
+        );
+        embed = add_field_if_some(
+            embed,
+            "Changes",
+            pull.changed_files.map(|files| {
+                format!(
+                    "{} files, +{} −{}",
+                    files,
+                    pull.additions.unwrap_or(0),
+                    pull.deletions.unwrap_or(0)
+                )
+            }),
+            true,
+        );
+        if issue.state == "open" {
+            embed = add_field_if_some(embed, "Mergeable", pull.mergeable_state.clone(), true);
+        }
+        embed = add_field_if_some(embed, "Merged At", pull.merged_at.clone(), true);
 
-        if let Some(owner) = &repo.owner {
-            if let Some(avatar) = &owner.avatar_url {
-                embed = embed.thumbnail(avatar.clone());
-            }
+        if pull.merged {
+            ("🟣 Merged", Color::PURPLE)
+        } else if issue.state == "closed" {
+            ("🔴 Closed", Color::RED)
+        } else if pull.draft.unwrap_or(false) {
+            ("📝 Draft", Color::LIGHT_GREY)
+        } else {
+            ("🟢 Open", Color::DARK_GREEN)
+        }
+    } else {
+        // Pull requests mentioning the issue show up as cross-references
+        let timeline = get_json::<Vec<GitHubTimelineEvent>>(
+            token,
+            &format!("repos/{}/issues/{}/timeline?per_page=100", repo, number),
+        )
+        .await?
+        .unwrap_or_default();
+        let linked = linked_pulls(timeline);
+        if !linked.is_empty() {
+            embed = embed.field("Linked Pull Requests", join_limited(&linked), false);
         }
 
-        if let Some(desc) = &repo.description {
-            embed = embed.description(desc);
+        match (issue.state.as_str(), issue.state_reason.as_deref()) {
+            ("open", _) => ("🟢 Open", Color::DARK_GREEN),
+            (_, Some("not_planned")) => ("⚪ Closed (not planned)", Color::LIGHT_GREY),
+            _ => ("🟣 Closed", Color::PURPLE),
         }
+    };
+    embed = embed.field("State", state, true).color(color);
 
-        embed = add_field_if_some(embed, "Stars", repo.stargazers_count, true);
-        embed = add_field_if_some(embed, "Watchers", repo.watchers_count, true);
-        embed = add_field_if_some(embed, "Forks", repo.forks_count, true);
-        embed = add_field_if_some(embed, "Open Issues", repo.open_issues_count, true);
-        embed = add_field_if_some(embed, "Language", repo.language.clone(), true);
-        embed = add_field_if_some(embed, "Private", repo.private.map(|b| b.to_string()), true);
-        embed = add_field_if_some(embed, "Forked Repo", repo.fork.map(|b| b.to_string()), true);
+    let labels: Vec<&str> = issue.labels.iter().map(|l| l.name.as_str()).collect();
+    if !labels.is_empty() {
+        embed = embed.field("Labels", truncate(&labels.join(", "), FIELD_LIMIT), false);
+    }
+    embed = add_field_if_some(embed, "Assignees", Some(logins(&issue.assignees)), true);
+    embed = add_field_if_some(embed, "Comments", issue.comments, true);
+    embed = add_field_if_some(embed, "Created At", issue.created_at.clone(), true);
+    embed = add_field_if_some(embed, "Closed At", issue.closed_at.clone(), true);
 
-        if let Some(homepage) = &repo.homepage {
-            if !homepage.is_empty() {
-                embed = embed.field("Homepage", homepage, false);
-            }
+    Ok(Some(embed))
+}
+
+async fn release_embed(
+    token: Option<&str>,
+    repo: &str,
+    tag: &str,
+    private: bool,
+) -> Result<Option<CreateEmbed>, GitHubError> {
+    if !repo_visible(token, repo, private).await? {
+        return Ok(None);
+    }
+    let path = if tag == "latest" {
+        format!("repos/{}/releases/latest", repo)
+    } else {
+        format!("repos/{}/releases/tags/{}", repo, urlencoding::encode(tag))
+    };
+    let Some(release) = get_json::<GitHubRelease>(token, &path).await? else {
+        return Ok(None);
+    };
+
+    let name = release
+        .name
+        .as_deref()
+        .filter(|name| !name.is_empty())
+        .unwrap_or(&release.tag_name);
+    let mut embed = CreateEmbed::default()
+        .title(truncate(&format!("{} {}", repo, name), 256))
+        .url(&release.html_url)
+        .color(Color::DARK_GREEN);
+    if let Some(body) = &release.body {
+        embed = embed.description(truncate(body, 4000));
+    }
+
+    embed = embed.field("Tag", &release.tag_name, true);
+    if let Some(author) = &release.author {
+        embed = add_field_if_some(embed, "Author", author.login.clone(), true);
+        if let Some(avatar) = &author.avatar_url {
+            embed = embed.thumbnail(avatar);
         }
+    }
+    embed = add_field_if_some(embed, "Published At", release.published_at.clone(), true);
+    if release.prerelease {
+        embed = embed.field("Pre-release", "true", true);
+    }
+    if release.draft {
+        embed = embed.field("Draft", "true", true);
+    }
 
-        embed = add_field_if_some(embed, "Created At", repo.created_at.clone(), true);
-        embed = add_field_if_some(embed, "Last Updated", repo.updated_at.clone(), true);
-        embed = add_field_if_some(embed, "Last Push", repo.pushed_at.clone(), true);
+    let assets: Vec<String> = release
+        .assets
+        .iter()
+        .map(|asset| {
+            format!(
+                "[{}]({}) ({}, {} downloads)",
+                asset.name,
+                asset.browser_download_url,
+                format_size(asset.size),
+                asset.download_count
+            )
+        })
+        .collect();
+    if !assets.is_empty() {
+        embed = embed.field(
+            format!("Assets ({})", assets.len()),
+            join_limited(&assets),
+            false,
+        );
+    }
+
+    Ok(Some(embed))
+}
+
+fn repo_embed(repo: &GitHubRepo) -> CreateEmbed {
+    let mut embed = CreateEmbed::default()
+        .title(repo.full_name.clone().unwrap_or_default())
+        .url(repo.html_url.clone().unwrap_or_default());
+
+    if let Some(owner) = &repo.owner
+        && let Some(avatar) = &owner.avatar_url
+    {
+        embed = embed.thumbnail(avatar.clone());
+    }
+
+    if let Some(desc) = &repo.description {
+        embed = embed.description(desc);
+    }
+
+    embed = add_field_if_some(embed, "Stars", repo.stargazers_count, true);
+    embed = add_field_if_some(embed, "Watchers", repo.watchers_count, true);
+    embed = add_field_if_some(embed, "Forks", repo.forks_count, true);
+    embed = add_field_if_some(embed, "Open Issues", repo.open_issues_count, true);
+    embed = add_field_if_some(embed, "Language", repo.language.clone(), true);
+    embed = add_field_if_some(embed, "Private", repo.private.map(|b| b.to_string()), true);
+    embed = add_field_if_some(embed, "Forked Repo", repo.fork.map(|b| b.to_string()), true);
+
+    if let Some(homepage) = &repo.homepage
+        && !homepage.is_empty()
+    {
+        embed = embed.field("Homepage", homepage, false);
+    }
+
+    embed = add_field_if_some(embed, "Created At", repo.created_at.clone(), true);
+    embed = add_field_if_some(embed, "Last Updated", repo.updated_at.clone(), true);
+    embed = add_field_if_some(embed, "Last Push", repo.pushed_at.clone(), true);
 
-        if let Some(license) = &repo.license {
-            embed = add_field_if_some(embed, "📄 License", license.name.clone(), true);
+    if let Some(license) = &repo.license {
+        embed = add_field_if_some(embed, "📄 License", license.name.clone(), true);
+    }
+    embed
+}
+
+fn user_embed(user: &GitHubUser) -> CreateEmbed {
+    let mut embed = CreateEmbed::default()
+        .title(user.login.clone().unwrap_or_default())
+        .url(user.html_url.clone().unwrap_or_default());
+
+    if let Some(avatar) = &user.avatar_url {
+        embed = embed.thumbnail(avatar.clone());
+    }
+
+    embed = add_field_if_some(embed, "Public Repos", user.public_repos, true);
+    embed = add_field_if_some(embed, "Followers", user.followers, true);
+    embed = add_field_if_some(embed, "Name", user.name.clone(), true);
+    embed = add_field_if_some(embed, "Company", user.company.clone(), true);
+
+    if let Some(blog) = &user.blog
+        && !blog.is_empty()
+    {
+        embed = embed.field("Blog", blog.clone(), false);
+    }
+
+    embed = add_field_if_some(embed, "Location", user.location.clone(), true);
+    embed = add_field_if_some(embed, "Email", user.email.clone(), true);
+
+    if let Some(bio) = &user.bio {
+        embed = embed.description(bio.clone());
+    }
+
+    if let Some(twitter) = &user.twitter_username {
+        embed = embed.field("Twitter", format!("@{}", twitter), true);
+    }
+
+    embed = add_field_if_some(embed, "Account Created", user.created_at.clone(), true);
+    embed = add_field_if_some(embed, "Last Updated", user.updated_at.clone(), true);
+    embed
+}
+
+/// The `/github` embed of a user or repository.
+pub(super) fn lookup_embed(lookup: &GitHubLookup) -> CreateEmbed {
+    match lookup {
+        GitHubLookup::Repo(repo) => repo_embed(repo),
+        GitHubLookup::User(user) => user_embed(user),
+    }
+}
+
+#[poise::command(slash_command)]
+pub async fn github(
+    ctx: Context<'_>,
+    #[description = "Username, owner/repo, owner/repo#123 or owner/repo@latest"] query: String,
+    #[description = "Show languages, contributors and activity charts for a repository"]
+    insights: Option<bool>,
+    #[description = "Send the response directly to you?"] ephemeral: Option<bool>,
+) -> Result<(), Error> {
+    let ephemeral = bot::defer_based_on_ephemeral(ctx, ephemeral).await?;
+
+    let token = ctx.data().config.read().await.github_token.clone();
+    let token = token.as_deref();
+    // Bot admins may look into private repositories the token can see
+    let private = bot::is_admin(ctx).await?;
+    let query = query.trim();
+    let detail = match parse_query(query) {
+        GitHubQuery::Issue { repo, number } => Some((
+            issue_embed(token, repo, number, private).await,
+            "Issue or pull request not found.",
+        )),
+        GitHubQuery::Release { repo, tag } => Some((
+            release_embed(token, repo, tag, private).await,
+            "Release not found.",
+        )),
+        GitHubQuery::Lookup => None,
+    };
+    if let Some((embed, missing)) = detail {
+        match embed {
+            Ok(Some(embed)) => {
+                ctx.send(CreateReply::default().embed(embed).ephemeral(ephemeral))
+                    .await?;
+            }
+            Ok(None) => error_text(&ctx, ephemeral, missing).await,
+            Err(e) => return github_error(&ctx, ephemeral, e).await,
         }
+        return Ok(());
+    }
 
-        ctx.send(CreateReply::default().embed(embed).ephemeral(ephemeral))
-            .await?;
-    } else {
-        let user: GitHubUser = match res.json().await {
-            Ok(user) => user,
-            Err(e) => return error_and_return(&ctx, ephemeral, e).await,
+    let lookup = match fetch_github(token, query, private).await {
+        Ok(Some(lookup)) => lookup,
+        Ok(None) => {
+            error_text(&ctx, ephemeral, "GitHub user or repository not found.").await;
+            return Ok(());
+        }
+        Err(e) => return github_error(&ctx, ephemeral, e).await,
+    };
+
+    if insights.unwrap_or(false) {
+        let GitHubLookup::Repo(repo) = &lookup else {
+            error_text(
+                &ctx,
+                ephemeral,
+                "Insights are only available for repositories.",
+            )
+            .await;
+            return Ok(());
         };
+        return match insights_reply(token, repo).await {
+            Ok(reply) => {
+                ctx.send(reply.ephemeral(ephemeral)).await?;
+                Ok(())
+            }
+            Err(e) => github_error(&ctx, ephemeral, e).await,
+        };
+    }
 
-        let mut embed = CreateEmbed::default()
-            .title(user.login.clone().unwrap_or_default())
-            .url(user.html_url.clone().unwrap_or_default());
+    let embed = lookup_embed(&lookup);
+    ctx.send(CreateReply::default().embed(embed).ephemeral(ephemeral))
+        .await?;
 
-        if let Some(avatar) = &user.avatar_url {
-            embed = embed.thumbnail(avatar.clone());
-        }
+    Ok(())
+}
 
-        embed = add_field_if_some(embed, "Public Repos", user.public_repos, true);
-        embed = add_field_if_some(embed, "Followers", user.followers, true);
-        embed = add_field_if_some(embed, "Name", user.name.clone(), true);
-        embed = add_field_if_some(embed, "Company", user.company.clone(), true);
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
 
-        if let Some(blog) = &user.blog {
-            if !blog.is_empty() {
-                embed = embed.field("Blog", blog.clone(), false);
+    use super::*;
+
+    fn from_json<T: DeserializeOwned>(value: serde_json::Value) -> T {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn parses_queries() {
+        assert!(matches!(
+            parse_query("serenity-rs/poise#42"),
+            GitHubQuery::Issue {
+                repo: "serenity-rs/poise",
+                number: 42
             }
+        ));
+        assert!(matches!(
+            parse_query("serenity-rs/poise@v0.6.1"),
+            GitHubQuery::Release {
+                repo: "serenity-rs/poise",
+                tag: "v0.6.1"
+            }
+        ));
+        for query in [
+            "serenity-rs/poise#abc",
+            "serenity-rs/poise#",
+            "poise#42",
+            "serenity-rs/poise@",
+            "octocat@latest",
+            "serenity-rs/poise",
+            "octocat",
+        ] {
+            assert!(
+                matches!(parse_query(query), GitHubQuery::Lookup),
+                "{}",
+                query
+            );
         }
+    }
+
+    fn review(login: &str, state: &str) -> serde_json::Value {
+        json!({ "user": { "login": login }, "state": state })
+    }
 
-        embed = add_field_if_some(embed, "Location", user.location.clone(), true);
-        embed = add_field_if_some(embed, "Email", user.email.clone(), true);
+    #[test]
+    fn sums_up_latest_verdicts() {
+        let reviews: Vec<GitHubReview> = from_json(json!([
+            review("alice", "CHANGES_REQUESTED"),
+            review("alice", "APPROVED"),
+            review("alice", "COMMENTED"),
+            review("bob", "CHANGES_REQUESTED"),
+            review("carol", "COMMENTED"),
+        ]));
+        let requested: Vec<GitHubUser> = from_json(json!([{ "login": "dave" }]));
+        assert_eq!(
+            review_summary(&reviews, &requested),
+            "✅ 1 approved\n❌ 1 requested changes\n⏳ waiting on dave"
+        );
 
-        if let Some(bio) = &user.bio {
-            embed = embed.description(bio.clone());
-        }
+        let dismissed: Vec<GitHubReview> = from_json(json!([
+            review("alice", "APPROVED"),
+            review("alice", "DISMISSED"),
+        ]));
+        assert_eq!(review_summary(&dismissed, &[]), "No reviews");
+        assert_eq!(review_summary(&[], &[]), "No reviews");
+    }
 
-        if let Some(twitter) = &user.twitter_username {
-            embed = embed.field("Twitter", format!("@{}", twitter), true);
-        }
+    #[test]
+    fn counts_checks_by_outcome() {
+        let runs: Vec<GitHubCheckRun> = from_json(json!([
+            { "status": "completed", "conclusion": "success" },
+            { "status": "completed", "conclusion": "success" },
+            { "status": "completed", "conclusion": "failure" },
+            { "status": "completed", "conclusion": "timed_out" },
+            { "status": "completed", "conclusion": "skipped" },
+            { "status": "completed", "conclusion": "neutral" },
+            { "status": "in_progress", "conclusion": null },
+            { "status": "queued" },
+        ]));
+        assert_eq!(
+            checks_summary(&runs).as_deref(),
+            Some("2 ✅ passed · 2 ❌ failed · 2 ⏳ running · 2 ⏭️ skipped")
+        );
+        assert_eq!(checks_summary(&[]), None);
+    }
 
-        embed = add_field_if_some(embed, "Account Created", user.created_at.clone(), true);
-        embed = add_field_if_some(embed, "Last Updated", user.updated_at.clone(), true);
+    #[test]
+    fn joins_lines_within_the_field_limit() {
+        let short: Vec<String> = vec!["a".into(), "b".into()];
+        assert_eq!(join_limited(&short), "a\nb");
+        assert_eq!(join_limited(&[]), "");
 
-        ctx.send(CreateReply::default().embed(embed).ephemeral(ephemeral))
-            .await?;
+        let lines: Vec<String> = (0..100)
+            .map(|i| format!("{:03}{}", i, "x".repeat(47)))
+            .collect();
+        let joined = join_limited(&lines);
+        assert!(joined.len() <= FIELD_LIMIT);
+        let kept = joined.lines().count() - 1;
+        assert_eq!(
+            joined.lines().last(),
+            Some(format!("…and {} more", lines.len() - kept).as_str())
+        );
+        assert!(joined.starts_with(&lines[..kept].join("\n")));
     }
 
-    Ok(())
+    #[test]
+    fn formats_sizes() {
+        assert_eq!(format_size(0), "0 B");
+        assert_eq!(format_size(1023), "1023 B");
+        assert_eq!(format_size(1024), "1.0 KB");
+        assert_eq!(format_size(1536), "1.5 KB");
+        assert_eq!(format_size(5 * 1024 * 1024), "5.0 MB");
+        assert_eq!(format_size(3 * 1024 * 1024 * 1024 * 1024), "3072.0 GB");
+    }
+
+    fn cross_reference(number: u64, pull: bool) -> serde_json::Value {
+        let mut issue = json!({
+            "number": number,
+            "title": format!("Change {}", number),
+            "state": "open",
+            "html_url": format!("https://github.com/o/r/pull/{}", number),
+        });
+        if pull {
+            issue["pull_request"] = json!({});
+        }
+        json!({ "event": "cross-referenced", "source": { "issue": issue } })
+    }
+
+    #[test]
+    fn lists_each_linked_pull_once() {
+        let timeline: Vec<GitHubTimelineEvent> = from_json(json!([
+            cross_reference(7, true),
+            { "event": "labeled" },
+            cross_reference(3, true),
+            cross_reference(7, true),
+            cross_reference(5, false),
+            { "event": "referenced", "source": cross_reference(9, true)["source"] },
+        ]));
+        assert_eq!(
+            linked_pulls(timeline),
+            [
+                "[#3](https://github.com/o/r/pull/3) Change 3",
+                "[#7](https://github.com/o/r/pull/7) Change 7",
+            ]
+        );
+    }
 }
@@ -0,0 +1,319 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, Utc};
+use poise::CreateReply;
+use serde::{Deserialize, de::DeserializeOwned};
+use serenity::all::{Color, CreateAttachment, CreateEmbed, CreateEmbedFooter};
+
+use super::github::{GitHubRepo, join_limited};
+use crate::utils::{
+    chart::{self, Series},
+    github::{self, GitHubError},
+};
+
+const CHART_WIDTH: u32 = 640;
+const CHART_HEIGHT: u32 = 200;
+/// Weeks shown in the issue trend chart.
+const TREND_WEEKS: usize = 12;
+const MAX_LANGUAGES: usize = 6;
+const MAX_CONTRIBUTORS: usize = 10;
+const LANGUAGE_BAR: usize = 12;
+
+const OPENED_COLOR: [u8; 3] = [0x3f, 0xb9, 0x50];
+const CLOSED_COLOR: [u8; 3] = [0xa3, 0x71, 0xf7];
+
+#[derive(Deserialize)]
+struct CommitWeek {
+    total: u32,
+}
+
+#[derive(Deserialize)]
+struct Contributor {
+    login: Option<String>,
+    html_url: Option<String>,
+    contributions: u32,
+}
+
+#[derive(Deserialize)]
+struct IssueDates {
+    created_at: String,
+    closed_at: Option<String>,
+}
+
+/// A page of issue search results.
+#[derive(Deserialize, Default)]
+struct IssueSearch {
+    total_count: u64,
+    items: Vec<IssueDates>,
+}
+
+/// Fetches a list that may be missing, like the contributors of an empty
+/// repository, which GitHub answers with no content at all.
+async fn get_or_default<T: DeserializeOwned + Default>(
+    token: Option<&str>,
+    path: &str,
+) -> Result<T, GitHubError> {
+    match github::client().get(token, path).await {
+        Ok(value) => Ok(value),
+        Err(GitHubError::NotFound | GitHubError::NoContent) => Ok(T::default()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Text bars of the largest languages by bytes of code.
+fn language_bars(languages: HashMap<String, u64>) -> String {
+    let total: u64 = languages.values().sum();
+    if total == 0 {
+        return "No code detected".to_string();
+    }
+    let mut languages: Vec<_> = languages.into_iter().collect();
+    languages.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+    let line = |name: &str, bytes: u64| {
+        let share = bytes as f64 / total as f64;
+        let filled = (share * LANGUAGE_BAR as f64).round() as usize;
+        format!(
+            "`{}{}` {:>5.1}% {}",
+            "█".repeat(filled),
+            "░".repeat(LANGUAGE_BAR - filled),
+            share * 100.0,
+            name
+        )
+    };
+    let mut lines: Vec<_> = languages
+        .iter()
+        .take(MAX_LANGUAGES)
+        .map(|(name, bytes)| line(name, *bytes))
+        .collect();
+    let rest: u64 = languages.iter().skip(MAX_LANGUAGES).map(|(_, b)| b).sum();
+    if rest > 0 {
+        lines.push(line("Other", rest));
+    }
+    lines.join("\n")
+}
+
+fn contributor_lines(contributors: &[Contributor]) -> String {
+    let lines: Vec<_> = contributors
+        .iter()
+        .take(MAX_CONTRIBUTORS)
+        .enumerate()
+        .map(|(i, contributor)| {
+            let login = contributor.login.as_deref().unwrap_or("ghost");
+            let name = match &contributor.html_url {
+                Some(url) => format!("[{}]({})", login, url),
+                None => login.to_string(),
+            };
+            format!(
+                "{}. {} – {} commits",
+                i + 1,
+                name,
+                contributor.contributions
+            )
+        })
+        .collect();
+    if lines.is_empty() {
+        "No contributors yet".to_string()
+    } else {
+        join_limited(&lines)
+    }
+}
+
+/// Counts dates per week, oldest week first, ending with the current week.
+fn weekly_counts<'a>(dates: impl Iterator<Item = &'a str>, now: DateTime<Utc>) -> Vec<f64> {
+    let mut counts = vec![0.0; TREND_WEEKS];
+    for date in dates {
+        let Ok(date) = DateTime::parse_from_rfc3339(date) else {
+            continue;
+        };
+        let age = now - date.with_timezone(&Utc);
+        // num_weeks rounds toward zero, which would count the next days as this week
+        if age < Duration::zero() {
+            continue;
+        }
+        let weeks_ago = age.num_weeks();
+        if (0..TREND_WEEKS as i64).contains(&weeks_ago) {
+            counts[TREND_WEEKS - 1 - weeks_ago as usize] += 1.0;
+        }
+    }
+    counts
+}
+
+/// The `/github owner/repo insights:true` reply: languages, top contributors,
+/// weekly commits for the last year and recent issue trends as PNG charts.
+pub(super) async fn insights_reply(
+    token: Option<&str>,
+    repo: &GitHubRepo,
+) -> Result<CreateReply, GitHubError> {
+    let name = repo.full_name.clone().unwrap_or_default();
+    let languages_path = format!("repos/{}/languages", name);
+    let contributors_path = format!("repos/{}/contributors?per_page={}", name, MAX_CONTRIBUTORS);
+    let activity_path = format!("repos/{}/stats/commit_activity", name);
+    // The issues endpoint lists pull requests too, the search can leave them out
+    let since = (Utc::now() - Duration::weeks(TREND_WEEKS as i64)).format("%Y-%m-%d");
+    // There is no sort by closing time, closing an issue updates it though
+    let search_path = |qualifier: &str, sort: &str| {
+        let query = format!("repo:{} is:issue {}:>={}", name, qualifier, since);
+        format!(
+            "search/issues?q={}&sort={}&order=desc&per_page=100",
+            urlencoding::encode(&query),
+            sort
+        )
+    };
+    let opened_path = search_path("created", "created");
+    let closed_path = search_path("closed", "updated");
+    let (languages, contributors, activity, opened, closed) = tokio::join!(
+        get_or_default::<HashMap<String, u64>>(token, &languages_path),
+        get_or_default::<Vec<Contributor>>(token, &contributors_path),
+        github::client().get::<Vec<CommitWeek>>(token, &activity_path),
+        get_or_default::<IssueSearch>(token, &opened_path),
+        get_or_default::<IssueSearch>(token, &closed_path),
+    );
+
+    let mut reply = CreateReply::default();
+    let mut embed = CreateEmbed::default()
+        .title(format!("{} insights", name))
+        .url(repo.html_url.clone().unwrap_or_default())
+        .color(Color::DARK_GREEN)
+        .field("Languages", language_bars(languages?), false)
+        .field("Top Contributors", contributor_lines(&contributors?), false);
+
+    match activity {
+        Ok(weeks) if !weeks.is_empty() => {
+            let totals: Vec<f64> = weeks.iter().map(|week| week.total as f64).collect();
+            let sum: u32 = weeks.iter().map(|week| week.total).sum();
+            let busiest = weeks.iter().map(|week| week.total).max().unwrap_or(0);
+            let png = chart::bar_chart(
+                &[Series {
+                    values: &totals,
+                    color: OPENED_COLOR,
+                }],
+                CHART_WIDTH,
+                CHART_HEIGHT,
+            );
+            reply = reply.attachment(CreateAttachment::bytes(png, "commits.png"));
+            embed = embed
+                .field(
+                    "Commit Activity",
+                    format!(
+                        "{} commits in the last {} weeks, {} in the busiest week",
+                        sum,
+                        weeks.len(),
+                        busiest
+                    ),
+                    false,
+                )
+                .image("attachment://commits.png");
+        }
+        Ok(_) => embed = embed.field("Commit Activity", "No commits in the last year", false),
+        Err(GitHubError::Pending) => {
+            embed = embed.field(
+                "Commit Activity",
+                "GitHub is still computing commit statistics, try again in a moment",
+                false,
+            )
+        }
+        Err(e) => return Err(e),
+    }
+    reply = reply.embed(embed);
+
+    let (opened, closed) = (opened?, closed?);
+    let now = Utc::now();
+    let opened_counts = weekly_counts(
+        opened.items.iter().map(|issue| issue.created_at.as_str()),
+        now,
+    );
+    let closed_counts = weekly_counts(
+        closed
+            .items
+            .iter()
+            .filter_map(|issue| issue.closed_at.as_deref()),
+        now,
+    );
+    let png = chart::bar_chart(
+        &[
+            Series {
+                values: &opened_counts,
+                color: OPENED_COLOR,
+            },
+            Series {
+                values: &closed_counts,
+                color: CLOSED_COLOR,
+            },
+        ],
+        CHART_WIDTH,
+        CHART_HEIGHT,
+    );
+    let mut issues = CreateEmbed::default()
+        .title("Issue Trends")
+        .color(Color::DARK_GREEN)
+        .description(format!(
+            "🟩 {} issues opened and 🟪 {} closed in the last {} weeks",
+            opened.total_count, closed.total_count, TREND_WEEKS
+        ))
+        .image("attachment://issues.png");
+    // Search results come in pages, the chart only shows the first one
+    if opened.total_count > opened.items.len() as u64
+        || closed.total_count > closed.items.len() as u64
+    {
+        issues = issues.footer(CreateEmbedFooter::new(format!(
+            "The chart shows the latest {} opened and {} closed issues",
+            opened.items.len(),
+            closed.items.len()
+        )));
+    }
+    Ok(reply
+        .attachment(CreateAttachment::bytes(png, "issues.png"))
+        .embed(issues))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_weeks_up_to_the_trend_start() {
+        let now = DateTime::parse_from_rfc3339("2026-10-18T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let ago = |duration: Duration| (now - duration).to_rfc3339();
+        let dates = [
+            now.to_rfc3339(),
+            ago(Duration::days(6)),
+            ago(Duration::days(7)),
+            ago(Duration::weeks(TREND_WEEKS as i64) + Duration::seconds(1)),
+            ago(Duration::weeks(TREND_WEEKS as i64)),
+            ago(Duration::weeks(TREND_WEEKS as i64) - Duration::seconds(1)),
+            ago(-Duration::hours(1)),
+            ago(-Duration::weeks(2)),
+            "not a date".to_string(),
+        ];
+
+        let counts = weekly_counts(dates.iter().map(String::as_str), now);
+        assert_eq!(counts.len(), TREND_WEEKS);
+        // Now and six days ago are this week, seven days ago the one before
+        assert_eq!(counts[TREND_WEEKS - 1], 2.0);
+        assert_eq!(counts[TREND_WEEKS - 2], 1.0);
+        // Exactly twelve weeks ago is already outside, a second later inside
+        assert_eq!(counts[0], 1.0);
+        assert_eq!(counts.iter().sum::<f64>(), 4.0);
+    }
+
+    #[test]
+    fn shows_the_largest_languages() {
+        assert_eq!(language_bars(HashMap::new()), "No code detected");
+
+        let languages = HashMap::from([("Rust".to_string(), 750), ("Shell".to_string(), 250)]);
+        assert_eq!(
+            language_bars(languages),
+            "`█████████░░░`  75.0% Rust\n`███░░░░░░░░░`  25.0% Shell"
+        );
+
+        let languages: HashMap<String, u64> = (0..MAX_LANGUAGES + 2)
+            .map(|i| (format!("L{}", i), 100 - i as u64))
+            .collect();
+        let bars = language_bars(languages);
+        assert_eq!(bars.lines().count(), MAX_LANGUAGES + 1);
+        assert!(bars.lines().next().unwrap().ends_with("L0"));
+        assert!(bars.ends_with("Other"));
+    }
+}
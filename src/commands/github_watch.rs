@@ -0,0 +1,1164 @@
+use std::{
+    collections::HashMap,
+    path::Path,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::{Duration, SystemTime},
+};
+
+use once_cell::sync::Lazy;
+use poise::CreateReply;
+use serde::{Deserialize, Serialize};
+use serenity::all::{
+    AutocompleteChoice, ChannelId, Color, CreateEmbed, CreateEmbedAuthor, CreateEmbedFooter,
+    CreateMessage, GuildChannel, Http,
+};
+use tokio::{fs, net::TcpListener, sync::RwLock};
+
+use super::github::{fetch_repo, github_error};
+use crate::{
+    Context, Error,
+    config::Config,
+    utils::{
+        bot::{self, can_manage_guild, error_text},
+        github::{self, Conditional, GitHubError},
+        markdown::truncate,
+        webhook::{self, WebhookError},
+    },
+};
+
+const WATCHES_PATH: &str = "github_watches.json";
+const MAX_GUILD_WATCHES: usize = 25;
+/// How often the poller looks for repositories that are due.
+const TICK: Duration = Duration::from_secs(15);
+/// Path GitHub delivers webhooks to.
+const WEBHOOK_PATH: &str = "/github";
+/// Time a webhook sender gets to deliver its request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// Polling resumes for a repository whose webhook stayed silent this long.
+const WEBHOOK_SILENCE: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+/// Commits listed in a push announcement.
+const MAX_COMMITS: usize = 5;
+
+/// Whether the webhook receiver runs, which replaces polling for repositories delivering to it.
+static RECEIVER_RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// Kinds of repository activity a watch can post.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[serde(rename_all = "snake_case")]
+enum WatchEvent {
+    Releases,
+    Issues,
+    PullRequests,
+    Pushes,
+}
+
+impl WatchEvent {
+    const ALL: [WatchEvent; 4] = [
+        WatchEvent::Releases,
+        WatchEvent::Issues,
+        WatchEvent::PullRequests,
+        WatchEvent::Pushes,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            WatchEvent::Releases => "releases",
+            WatchEvent::Issues => "issues",
+            WatchEvent::PullRequests => "pulls",
+            WatchEvent::Pushes => "pushes",
+        }
+    }
+
+    /// The kind of activity a webhook event carries, None for e.g. `ping`.
+    fn from_webhook(event: &str) -> Option<Self> {
+        match event {
+            "release" => Some(WatchEvent::Releases),
+            "issues" => Some(WatchEvent::Issues),
+            "pull_request" => Some(WatchEvent::PullRequests),
+            "push" => Some(WatchEvent::Pushes),
+            _ => None,
+        }
+    }
+
+    fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "release" | "releases" => Some(WatchEvent::Releases),
+            "issue" | "issues" => Some(WatchEvent::Issues),
+            "pr" | "prs" | "pull" | "pulls" | "pull_requests" => Some(WatchEvent::PullRequests),
+            "push" | "pushes" | "commits" => Some(WatchEvent::Pushes),
+            _ => None,
+        }
+    }
+}
+
+/// Parses a comma separated list of event kinds, all of them if empty.
+fn parse_events(input: Option<&str>) -> Result<Vec<WatchEvent>, String> {
+    let Some(input) = input.filter(|input| !input.trim().is_empty()) else {
+        return Ok(WatchEvent::ALL.to_vec());
+    };
+    let mut events = Vec::new();
+    for name in input.split(',').filter(|name| !name.trim().is_empty()) {
+        let event = WatchEvent::parse(name).ok_or_else(|| {
+            format!(
+                "Unknown event `{}`, use releases, issues, pulls or pushes",
+                name.trim()
+            )
+        })?;
+        if !events.contains(&event) {
+            events.push(event);
+        }
+    }
+    Ok(events)
+}
+
+fn event_names(events: &[WatchEvent]) -> String {
+    events
+        .iter()
+        .map(|event| event.name())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// A guild's watch of a GitHub repository.
+#[derive(Serialize, Deserialize, Clone)]
+struct Watch {
+    guild_id: u64,
+    discord_channel_id: u64,
+    /// `owner/repo` as GitHub spells it.
+    repo: String,
+    events: Vec<WatchEvent>,
+}
+
+/// What the poller knows about a watched repository.
+#[derive(Serialize, Deserialize, Default)]
+struct RepoState {
+    /// ETag of the last events page, so unchanged pages cost no rate limit.
+    etag: Option<String>,
+    /// Newest event already seen. None until the first poll.
+    last_event_id: Option<u64>,
+    /// Only pushes to this branch are posted.
+    default_branch: Option<String>,
+    /// Minimum seconds between polls GitHub asked for.
+    poll_interval: Option<u64>,
+    last_polled: Option<SystemTime>,
+    /// Last webhook delivery per kind of activity. Kinds delivered by webhook are not polled.
+    #[serde(default)]
+    webhooks: HashMap<WatchEvent, SystemTime>,
+}
+
+impl RepoState {
+    /// Whether webhooks deliver this kind of activity, so it need not be polled.
+    fn webhook_delivers(&self, kind: WatchEvent) -> bool {
+        RECEIVER_RUNNING.load(Ordering::Relaxed)
+            && self
+                .webhooks
+                .get(&kind)
+                .is_some_and(|last| last.elapsed().is_ok_and(|e| e < WEBHOOK_SILENCE))
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct GitHubWatches {
+    watches: Vec<Watch>,
+    /// Poller state by lowercase `owner/repo`.
+    repos: HashMap<String, RepoState>,
+}
+
+impl GitHubWatches {
+    /// The kinds of activity any watch of the repository posts.
+    fn watched_kinds(&self, key: &str) -> Vec<WatchEvent> {
+        WatchEvent::ALL
+            .into_iter()
+            .filter(|kind| {
+                self.watches
+                    .iter()
+                    .any(|w| w.repo.to_lowercase() == key && w.events.contains(kind))
+            })
+            .collect()
+    }
+
+    /// Whether webhooks deliver everything the repository's watches post.
+    fn webhooks_deliver_all(&self, key: &str) -> bool {
+        self.repos.get(key).is_some_and(|state| {
+            self.watched_kinds(key)
+                .into_iter()
+                .all(|kind| state.webhook_delivers(kind))
+        })
+    }
+}
+
+static WATCHES: Lazy<RwLock<GitHubWatches>> = Lazy::new(|| RwLock::new(GitHubWatches::default()));
+
+/// Load GitHub watches from disk into memory at startup.
+pub async fn load_github_watches_from_file() -> Result<(), std::io::Error> {
+    if Path::new(WATCHES_PATH).exists() {
+        let data = fs::read_to_string(WATCHES_PATH).await?;
+        *WATCHES.write().await = serde_json::from_str(&data)?;
+    }
+    Ok(())
+}
+
+/// Saves the watches and poller state to disk as pretty JSON.
+async fn save_github_watches_to_file() {
+    let json = {
+        let watches = WATCHES.read().await;
+        serde_json::to_string_pretty(&*watches)
+    };
+    match json {
+        Ok(json) => {
+            if let Err(e) = fs::write(WATCHES_PATH, json).await {
+                tracing::warn!("Failed to save GitHub watches: {}", e);
+            }
+        }
+        Err(e) => tracing::warn!("Failed to serialize GitHub watches: {}", e),
+    }
+}
+
+#[derive(Deserialize, Clone)]
+struct Actor {
+    login: String,
+    avatar_url: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ReleasePayload {
+    name: Option<String>,
+    tag_name: String,
+    html_url: String,
+    body: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct IssuePayload {
+    number: u64,
+    title: Option<String>,
+    html_url: Option<String>,
+    #[serde(default)]
+    merged: bool,
+}
+
+#[derive(Deserialize)]
+struct CommitPayload {
+    /// `sha` in the events API, `id` in webhooks.
+    #[serde(alias = "id")]
+    sha: String,
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct RepositoryPayload {
+    full_name: String,
+    default_branch: Option<String>,
+}
+
+/// The payload of an events API entry or a webhook delivery. Both share most fields.
+#[derive(Deserialize)]
+struct Payload {
+    action: Option<String>,
+    release: Option<ReleasePayload>,
+    issue: Option<IssuePayload>,
+    pull_request: Option<IssuePayload>,
+    #[serde(rename = "ref")]
+    git_ref: Option<String>,
+    before: Option<String>,
+    /// `head` in the events API, `after` in webhooks.
+    #[serde(alias = "after")]
+    head: Option<String>,
+    size: Option<usize>,
+    #[serde(default)]
+    commits: Vec<CommitPayload>,
+    compare: Option<String>,
+    /// Only sent with webhooks.
+    repository: Option<RepositoryPayload>,
+    /// Only sent with webhooks.
+    sender: Option<Actor>,
+}
+
+#[derive(Deserialize)]
+struct RepoEvent {
+    id: String,
+    #[serde(rename = "type")]
+    kind: String,
+    actor: Option<Actor>,
+    payload: Payload,
+}
+
+/// Repository activity to post.
+struct Activity {
+    kind: WatchEvent,
+    title: String,
+    url: String,
+    description: Option<String>,
+    actor: Option<Actor>,
+    color: Color,
+}
+
+/// Turns a payload into an activity worth posting. `event` is the webhook
+/// event name, like `push` or `pull_request`.
+fn activity(
+    event: &str,
+    payload: Payload,
+    repo: &str,
+    default_branch: Option<&str>,
+    actor: Option<Actor>,
+) -> Option<Activity> {
+    let action = payload.action.as_deref().unwrap_or_default();
+    let activity = match event {
+        "release" if action == "published" => {
+            let release = payload.release?;
+            let name = release
+                .name
+                .filter(|name| !name.is_empty())
+                .unwrap_or_else(|| release.tag_name.clone());
+            Activity {
+                kind: WatchEvent::Releases,
+                title: format!("Release {}", name),
+                url: release.html_url,
+                description: release.body.map(|body| truncate(&body, 1000)),
+                actor,
+                color: Color::DARK_GREEN,
+            }
+        }
+        "issues" | "pull_request" => {
+            let is_pull = event == "pull_request";
+            let item = if is_pull {
+                payload.pull_request?
+            } else {
+                payload.issue?
+            };
+            let (verb, color) = match action {
+                "opened" => ("opened", Color::DARK_GREEN),
+                "reopened" => ("reopened", Color::DARK_GREEN),
+                "closed" if item.merged => ("merged", Color::PURPLE),
+                "closed" if is_pull => ("closed", Color::RED),
+                "closed" => ("closed", Color::PURPLE),
+                _ => return None,
+            };
+            let noun = if is_pull { "Pull request" } else { "Issue" };
+            let path = if is_pull { "pull" } else { "issues" };
+            Activity {
+                kind: if is_pull {
+                    WatchEvent::PullRequests
+                } else {
+                    WatchEvent::Issues
+                },
+                title: match item.title {
+                    Some(title) => format!("{} #{} {}: {}", noun, item.number, verb, title),
+                    None => format!("{} #{} {}", noun, item.number, verb),
+                },
+                url: item.html_url.unwrap_or_else(|| {
+                    format!("https://github.com/{}/{}/{}", repo, path, item.number)
+                }),
+                description: None,
+                actor,
+                color,
+            }
+        }
+        "push" => {
+            let branch = payload.git_ref?.strip_prefix("refs/heads/")?.to_string();
+            if default_branch.is_some_and(|default| default != branch) {
+                return None;
+            }
+            let count = payload.size.unwrap_or(payload.commits.len());
+            let mut lines: Vec<String> = payload
+                .commits
+                .iter()
+                .rev()
+                .take(MAX_COMMITS)
+                .map(|commit| {
+                    format!(
+                        "[`{}`](https://github.com/{}/commit/{}) {}",
+                        commit.sha.get(..7).unwrap_or(&commit.sha),
+                        repo,
+                        commit.sha,
+                        truncate(commit.message.lines().next().unwrap_or_default(), 80)
+                    )
+                })
+                .collect();
+            if count > lines.len() && !lines.is_empty() {
+                lines.push(format!("…and {} more", count - lines.len()));
+            }
+            let url = match (payload.compare, payload.before, payload.head) {
+                (Some(compare), _, _) => compare,
+                (None, Some(before), Some(head)) => {
+                    format!("https://github.com/{}/compare/{}...{}", repo, before, head)
+                }
+                _ => format!("https://github.com/{}/commits/{}", repo, branch),
+            };
+            Activity {
+                kind: WatchEvent::Pushes,
+                title: match count {
+                    0 => format!("New push to {}", branch),
+                    1 => format!("1 new commit on {}", branch),
+                    n => format!("{} new commits on {}", n, branch),
+                },
+                url,
+                description: (!lines.is_empty()).then(|| lines.join("\n")),
+                actor,
+                color: Color::BLURPLE,
+            }
+        }
+        _ => return None,
+    };
+    Some(activity)
+}
+
+fn activity_message(repo: &str, activity: &Activity) -> CreateMessage {
+    let mut embed = CreateEmbed::default()
+        .title(truncate(&activity.title, 256))
+        .url(&activity.url)
+        .footer(CreateEmbedFooter::new(repo))
+        .color(activity.color);
+    if let Some(description) = &activity.description {
+        embed = embed.description(description);
+    }
+    if let Some(actor) = &activity.actor {
+        let mut author =
+            CreateEmbedAuthor::new(&actor.login).url(format!("https://github.com/{}", actor.login));
+        if let Some(avatar) = &actor.avatar_url {
+            author = author.icon_url(avatar);
+        }
+        embed = embed.author(author);
+    }
+    CreateMessage::new().embed(embed)
+}
+
+/// Posts the activities in every channel watching `repo` for their kind.
+async fn announce(http: &Http, repo: &str, activities: &[Activity]) {
+    let targets: Vec<Watch> = WATCHES
+        .read()
+        .await
+        .watches
+        .iter()
+        .filter(|w| w.repo.eq_ignore_ascii_case(repo))
+        .cloned()
+        .collect();
+
+    for watch in targets {
+        for activity in activities
+            .iter()
+            .filter(|activity| watch.events.contains(&activity.kind))
+        {
+            if let Err(e) = ChannelId::new(watch.discord_channel_id)
+                .send_message(http, activity_message(&watch.repo, activity))
+                .await
+            {
+                tracing::warn!(
+                    "Failed to post {} activity in {}: {}",
+                    watch.repo,
+                    watch.discord_channel_id,
+                    e
+                );
+            }
+        }
+    }
+}
+
+/// Checks one repository for new events and returns what to post.
+async fn check_repo(token: Option<&str>, key: &str, repo: &str) -> Vec<Activity> {
+    let etag = {
+        let mut watches = WATCHES.write().await;
+        let state = watches.repos.entry(key.to_string()).or_default();
+        state.last_polled = Some(SystemTime::now());
+        state.etag.clone()
+    };
+
+    let path = format!("repos/{}/events?per_page=100", repo);
+    let fresh = match github::client()
+        .get_if_changed::<Vec<RepoEvent>>(token, &path, etag.as_deref())
+        .await
+    {
+        Ok(Conditional::Modified(fresh)) => fresh,
+        Ok(Conditional::NotModified) => return Vec::new(),
+        Err(GitHubError::RateLimited(_)) => {
+            tracing::warn!("GitHub rate limit reached, pausing repository checks");
+            return Vec::new();
+        }
+        Err(e) => {
+            tracing::warn!("Failed to fetch events of {}: {}", repo, e);
+            return Vec::new();
+        }
+    };
+
+    let mut events: Vec<(u64, RepoEvent)> = fresh
+        .value
+        .into_iter()
+        .filter_map(|event| Some((event.id.parse().ok()?, event)))
+        .collect();
+    events.sort_by_key(|(id, _)| *id);
+
+    let mut watches = WATCHES.write().await;
+    let state = watches.repos.entry(key.to_string()).or_default();
+    state.etag = fresh.etag;
+    state.poll_interval = fresh.poll_interval;
+    let newest = events.last().map_or(0, |(id, _)| *id);
+    let Some(cursor) = state.last_event_id else {
+        // Events from before the watch are not posted
+        state.last_event_id = Some(newest);
+        return Vec::new();
+    };
+    state.last_event_id = Some(cursor.max(newest));
+    let default_branch = state.default_branch.clone();
+    drop(watches);
+
+    events
+        .into_iter()
+        .filter(|(id, _)| *id > cursor)
+        .filter_map(|(_, event)| {
+            // `PullRequestEvent` is delivered as the `pull_request` webhook
+            let name = match event.kind.as_str() {
+                "ReleaseEvent" => "release",
+                "IssuesEvent" => "issues",
+                "PullRequestEvent" => "pull_request",
+                "PushEvent" => "push",
+                _ => return None,
+            };
+            activity(
+                name,
+                event.payload,
+                repo,
+                default_branch.as_deref(),
+                event.actor,
+            )
+        })
+        .collect()
+}
+
+/// Periodically checks the watched repositories for new activity, except those webhooks deliver.
+pub async fn start_github_poller(ctx: serenity::all::Context, config: Arc<RwLock<Config>>) {
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(TICK).await;
+            // Events count against the core limit, like every repos/ request
+            if github::client().limited_until("repos").await.is_some() {
+                continue;
+            }
+
+            let (token, seconds) = {
+                let config = config.read().await;
+                (config.github_token.clone(), config.github_poll_seconds)
+            };
+            let due: Vec<(String, String)> = {
+                let watches = WATCHES.read().await;
+                let mut repos: Vec<(String, String)> = watches
+                    .watches
+                    .iter()
+                    .map(|w| (w.repo.to_lowercase(), w.repo.clone()))
+                    .collect();
+                repos.sort();
+                repos.dedup_by(|a, b| a.0 == b.0);
+
+                repos
+                    .into_iter()
+                    .filter(|(key, _)| {
+                        if watches.webhooks_deliver_all(key) {
+                            return false;
+                        }
+                        let state = watches.repos.get(key);
+                        let interval = Duration::from_secs(
+                            seconds.max(state.and_then(|s| s.poll_interval).unwrap_or(0)),
+                        );
+                        state.and_then(|s| s.last_polled).is_none_or(|last| {
+                            last.elapsed().is_ok_and(|elapsed| elapsed >= interval)
+                        })
+                    })
+                    .collect()
+            };
+
+            for (key, repo) in &due {
+                let mut activities = check_repo(token.as_deref(), key, repo).await;
+                // What webhooks deliver was already posted by them
+                if let Some(state) = WATCHES.read().await.repos.get(key) {
+                    activities.retain(|activity| !state.webhook_delivers(activity.kind));
+                }
+                announce(&ctx.http, repo, &activities).await;
+            }
+            if !due.is_empty() {
+                save_github_watches_to_file().await;
+            }
+        }
+    });
+}
+
+/// Verifies and posts one webhook delivery.
+async fn handle_delivery(
+    http: &Http,
+    secret: &str,
+    stream: &mut tokio::net::TcpStream,
+) -> Result<(), WebhookError> {
+    let request = match webhook::read_request(stream).await {
+        Ok(request) => request,
+        Err(WebhookError::Io(e)) => return Err(e.into()),
+        Err(WebhookError::TooLarge) => {
+            webhook::respond(stream, 413, "Payload Too Large").await?;
+            return Ok(());
+        }
+        Err(e) => {
+            webhook::respond(stream, 400, "Bad Request").await?;
+            return Err(e);
+        }
+    };
+    if request.path.split('?').next() != Some(WEBHOOK_PATH) {
+        webhook::respond(stream, 404, "Not Found").await?;
+        return Ok(());
+    }
+    if request.method != "POST" {
+        webhook::respond(stream, 405, "Method Not Allowed").await?;
+        return Ok(());
+    }
+    if !webhook::verify_signature(
+        secret.as_bytes(),
+        &request.body,
+        request.header("X-Hub-Signature-256"),
+    ) {
+        tracing::warn!("Rejected GitHub webhook with an invalid signature");
+        webhook::respond(stream, 401, "Unauthorized").await?;
+        return Ok(());
+    }
+    let Ok(payload) = serde_json::from_slice::<Payload>(&request.body) else {
+        webhook::respond(stream, 400, "Bad Request").await?;
+        return Ok(());
+    };
+    // GitHub gives up after ten seconds, so answer before posting
+    webhook::respond(stream, 204, "No Content").await?;
+
+    let event = request.header("X-GitHub-Event").unwrap_or_default();
+    let Some(repository) = payload.repository.as_ref() else {
+        return Ok(());
+    };
+    let repo = repository.full_name.clone();
+    let default_branch = repository.default_branch.clone();
+    let key = repo.to_lowercase();
+    // Only activity the watches post proves that the webhook replaces polling
+    let recorded = match WatchEvent::from_webhook(event) {
+        Some(kind) => {
+            let mut watches = WATCHES.write().await;
+            let watched = watches.watched_kinds(&key).contains(&kind);
+            if watched && let Some(state) = watches.repos.get_mut(&key) {
+                state.webhooks.insert(kind, SystemTime::now());
+            }
+            if watched && watches.webhooks_deliver_all(&key) {
+                // Polling starts over from the newest event if the webhook goes silent
+                if let Some(state) = watches.repos.get_mut(&key) {
+                    state.last_event_id = None;
+                    state.etag = None;
+                }
+            }
+            watched
+        }
+        None => false,
+    };
+    if recorded {
+        save_github_watches_to_file().await;
+    }
+    let actor = payload.sender.clone();
+    if let Some(activity) = activity(event, payload, &repo, default_branch.as_deref(), actor) {
+        announce(http, &repo, &[activity]).await;
+    }
+    Ok(())
+}
+
+/// Receives GitHub webhooks if `github_webhook_addr` is configured. Repositories
+/// delivering webhooks are not polled while it runs.
+pub async fn start_github_webhook_receiver(
+    ctx: serenity::all::Context,
+    config: Arc<RwLock<Config>>,
+) {
+    let (addr, secret) = {
+        let config = config.read().await;
+        (
+            config.github_webhook_addr.clone(),
+            config.github_webhook_secret.clone(),
+        )
+    };
+    let Some(addr) = addr else {
+        return;
+    };
+    let Some(secret) = secret.filter(|secret| !secret.is_empty()) else {
+        tracing::error!(
+            "github_webhook_addr is set without github_webhook_secret, not receiving webhooks"
+        );
+        return;
+    };
+    let listener = match TcpListener::bind(&addr).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            tracing::error!("Failed to listen for GitHub webhooks on {}: {}", addr, e);
+            return;
+        }
+    };
+    RECEIVER_RUNNING.store(true, Ordering::Relaxed);
+    tracing::info!("Receiving GitHub webhooks on {}", addr);
+
+    tokio::spawn(async move {
+        loop {
+            let (mut stream, peer) = match listener.accept().await {
+                Ok(connection) => connection,
+                Err(e) => {
+                    tracing::warn!("Failed to accept webhook connection: {}", e);
+                    continue;
+                }
+            };
+            let http = ctx.http.clone();
+            let secret = secret.clone();
+            tokio::spawn(async move {
+                match tokio::time::timeout(
+                    REQUEST_TIMEOUT,
+                    handle_delivery(&http, &secret, &mut stream),
+                )
+                .await
+                {
+                    Ok(Ok(())) => {}
+                    Ok(Err(e)) => tracing::warn!("Bad webhook delivery from {}: {}", peer, e),
+                    Err(_) => tracing::warn!("Webhook delivery from {} timed out", peer),
+                }
+            });
+        }
+    });
+}
+
+/// Suggests the current server's watched repositories.
+async fn autocomplete_watch(ctx: Context<'_>, partial: &str) -> Vec<AutocompleteChoice> {
+    let Some(guild_id) = ctx.guild_id() else {
+        return Vec::new();
+    };
+    let partial = partial.to_lowercase();
+    let watches = WATCHES.read().await;
+    let mut matching: Vec<&str> = watches
+        .watches
+        .iter()
+        .filter(|w| w.guild_id == guild_id.get())
+        .map(|w| w.repo.as_str())
+        .filter(|repo| repo.to_lowercase().contains(&partial))
+        .collect();
+    matching.sort();
+    matching.dedup();
+    matching.truncate(25);
+    matching
+        .into_iter()
+        .map(|repo| AutocompleteChoice::new(repo, repo))
+        .collect()
+}
+
+#[poise::command(slash_command, guild_only)]
+/// Posts a GitHub repository's releases, issues, pull requests and pushes in a channel.
+pub async fn github_watch(
+    ctx: Context<'_>,
+    #[description = "Repository as owner/repo"] repo: String,
+    #[description = "Where to post the activity"]
+    #[channel_types("Text", "News")]
+    discord_channel: GuildChannel,
+    #[description = "Comma separated: releases, issues, pulls, pushes (default all)"]
+    events: Option<String>,
+    #[description = "Send the response directly to you?"] ephemeral: Option<bool>,
+) -> Result<(), Error> {
+    let ephemeral = bot::defer_based_on_ephemeral(ctx, ephemeral).await?;
+
+    if !can_manage_guild(ctx).await? {
+        error_text(
+            &ctx,
+            ephemeral,
+            "You need the Manage Server permission to watch repositories",
+        )
+        .await;
+        return Ok(());
+    }
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+    if discord_channel.guild_id != guild_id {
+        error_text(&ctx, ephemeral, "Please pick a channel of this server").await;
+        return Ok(());
+    }
+    let events = match parse_events(events.as_deref()) {
+        Ok(events) => events,
+        Err(e) => {
+            error_text(&ctx, ephemeral, &e).await;
+            return Ok(());
+        }
+    };
+
+    let repo = repo.trim().trim_start_matches("https://github.com/");
+    if repo.split('/').filter(|part| !part.is_empty()).count() != 2 {
+        error_text(&ctx, ephemeral, "Please give the repository as owner/repo").await;
+        return Ok(());
+    }
+    let token = ctx.data().config.read().await.github_token.clone();
+    let (repo, default_branch) = match fetch_repo(token.as_deref(), repo).await {
+        // The token may see private repositories, their activity is for bot admins to share
+        Ok(Some(found)) if found.private == Some(true) && !bot::is_admin(ctx).await? => {
+            error_text(
+                &ctx,
+                ephemeral,
+                "Only bot admins may watch private repositories",
+            )
+            .await;
+            return Ok(());
+        }
+        Ok(Some(found)) => (
+            found.full_name.unwrap_or_else(|| repo.to_string()),
+            found.default_branch,
+        ),
+        Ok(None) => {
+            error_text(&ctx, ephemeral, "GitHub repository not found").await;
+            return Ok(());
+        }
+        Err(e) => return github_error(&ctx, ephemeral, e).await,
+    };
+
+    let updated = {
+        let mut watches = WATCHES.write().await;
+        let existing = watches.watches.iter_mut().find(|w| {
+            w.guild_id == guild_id.get()
+                && w.discord_channel_id == discord_channel.id.get()
+                && w.repo.eq_ignore_ascii_case(&repo)
+        });
+        if let Some(watch) = existing {
+            watch.events = events.clone();
+            true
+        } else {
+            let guild_watches = watches
+                .watches
+                .iter()
+                .filter(|w| w.guild_id == guild_id.get())
+                .count();
+            if guild_watches >= MAX_GUILD_WATCHES {
+                drop(watches);
+                error_text(
+                    &ctx,
+                    ephemeral,
+                    &format!(
+                        "This server already watches {} repositories",
+                        MAX_GUILD_WATCHES
+                    ),
+                )
+                .await;
+                return Ok(());
+            }
+            watches.watches.push(Watch {
+                guild_id: guild_id.get(),
+                discord_channel_id: discord_channel.id.get(),
+                repo: repo.clone(),
+                events: events.clone(),
+            });
+            false
+        }
+    };
+    WATCHES
+        .write()
+        .await
+        .repos
+        .entry(repo.to_lowercase())
+        .or_default()
+        .default_branch = default_branch;
+    save_github_watches_to_file().await;
+
+    let seconds = ctx.data().config.read().await.github_poll_seconds;
+    let delivery = if RECEIVER_RUNNING.load(Ordering::Relaxed) {
+        format!(
+            "Checked every {} seconds until a webhook for this repository is delivered",
+            seconds
+        )
+    } else {
+        format!(
+            "Checked every {} seconds, earlier activity is not posted",
+            seconds
+        )
+    };
+    let embed = CreateEmbed::default()
+        .title(format!(
+            "{} {}",
+            if updated {
+                "Updated watch of"
+            } else {
+                "Watching"
+            },
+            repo
+        ))
+        .url(format!("https://github.com/{}", repo))
+        .description(format!(
+            "Posting {} in <#{}>",
+            event_names(&events),
+            discord_channel.id
+        ))
+        .footer(CreateEmbedFooter::new(delivery))
+        .color(Color::DARK_GREEN);
+    ctx.send(CreateReply::default().embed(embed).ephemeral(ephemeral))
+        .await?;
+
+    Ok(())
+}
+
+#[poise::command(slash_command, guild_only)]
+/// Stops posting a GitHub repository's activity in this server.
+pub async fn github_unwatch(
+    ctx: Context<'_>,
+    #[description = "Watched repository"]
+    #[autocomplete = "autocomplete_watch"]
+    repo: String,
+    #[description = "Only stop posting in this channel"]
+    #[channel_types("Text", "News")]
+    discord_channel: Option<GuildChannel>,
+    #[description = "Send the response directly to you?"] ephemeral: Option<bool>,
+) -> Result<(), Error> {
+    let ephemeral = bot::defer_based_on_ephemeral(ctx, ephemeral).await?;
+
+    if !can_manage_guild(ctx).await? {
+        error_text(
+            &ctx,
+            ephemeral,
+            "You need the Manage Server permission to unwatch repositories",
+        )
+        .await;
+        return Ok(());
+    }
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+
+    let removed = {
+        let mut watches = WATCHES.write().await;
+        let before = watches.watches.len();
+        watches.watches.retain(|w| {
+            !(w.guild_id == guild_id.get()
+                && w.repo.eq_ignore_ascii_case(&repo)
+                && discord_channel
+                    .as_ref()
+                    .is_none_or(|c| c.id.get() == w.discord_channel_id))
+        });
+        let removed = before - watches.watches.len();
+
+        // Forget the poller state of repositories nobody watches anymore
+        if !watches
+            .watches
+            .iter()
+            .any(|w| w.repo.eq_ignore_ascii_case(&repo))
+        {
+            watches.repos.remove(&repo.to_lowercase());
+        }
+        removed
+    };
+    if removed == 0 {
+        error_text(&ctx, ephemeral, "No such watch").await;
+        return Ok(());
+    }
+    save_github_watches_to_file().await;
+
+    ctx.send(
+        CreateReply::default()
+            .content(format!("Removed {} watch(es)", removed))
+            .ephemeral(ephemeral),
+    )
+    .await?;
+
+    Ok(())
+}
+
+#[poise::command(slash_command, guild_only)]
+/// Lists the GitHub repositories this server watches.
+pub async fn github_watches(
+    ctx: Context<'_>,
+    #[description = "Send the response directly to you?"] ephemeral: Option<bool>,
+) -> Result<(), Error> {
+    let ephemeral = bot::defer_based_on_ephemeral(ctx, ephemeral).await?;
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+
+    let lines: Vec<String> = WATCHES
+        .read()
+        .await
+        .watches
+        .iter()
+        .filter(|w| w.guild_id == guild_id.get())
+        .map(|w| {
+            format!(
+                "[{}](https://github.com/{}) → <#{}> ({})",
+                w.repo,
+                w.repo,
+                w.discord_channel_id,
+                event_names(&w.events)
+            )
+        })
+        .collect();
+    if lines.is_empty() {
+        error_text(
+            &ctx,
+            ephemeral,
+            "This server watches no GitHub repositories",
+        )
+        .await;
+        return Ok(());
+    }
+
+    let embed = CreateEmbed::default()
+        .title("GitHub watches")
+        .description(lines.join("\n"))
+        .color(Color::DARK_GREEN);
+    ctx.send(CreateReply::default().embed(embed).ephemeral(ephemeral))
+        .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn watch(repo: &str, events: Vec<WatchEvent>) -> Watch {
+        Watch {
+            guild_id: 1,
+            discord_channel_id: 2,
+            repo: repo.into(),
+            events,
+        }
+    }
+
+    #[test]
+    fn polling_pauses_only_for_kinds_webhooks_deliver() {
+        RECEIVER_RUNNING.store(true, Ordering::Relaxed);
+        let mut watches = GitHubWatches {
+            watches: vec![
+                watch("Owner/Repo", vec![WatchEvent::Pushes]),
+                watch("owner/repo", vec![WatchEvent::Releases]),
+            ],
+            repos: HashMap::from([("owner/repo".to_string(), RepoState::default())]),
+        };
+        assert_eq!(
+            watches.watched_kinds("owner/repo"),
+            vec![WatchEvent::Releases, WatchEvent::Pushes]
+        );
+        assert!(!watches.webhooks_deliver_all("owner/repo"));
+
+        let state = watches.repos.get_mut("owner/repo").unwrap();
+        state.webhooks.insert(WatchEvent::Pushes, SystemTime::now());
+        assert!(state.webhook_delivers(WatchEvent::Pushes));
+        assert!(!state.webhook_delivers(WatchEvent::Releases));
+        assert!(!watches.webhooks_deliver_all("owner/repo"));
+
+        let state = watches.repos.get_mut("owner/repo").unwrap();
+        state
+            .webhooks
+            .insert(WatchEvent::Releases, SystemTime::now());
+        assert!(watches.webhooks_deliver_all("owner/repo"));
+
+        // A webhook that went silent no longer replaces polling
+        let state = watches.repos.get_mut("owner/repo").unwrap();
+        state.webhooks.insert(
+            WatchEvent::Releases,
+            SystemTime::now() - WEBHOOK_SILENCE - Duration::from_secs(1),
+        );
+        assert!(!watches.webhooks_deliver_all("owner/repo"));
+    }
+
+    #[test]
+    fn webhook_events_map_to_watched_kinds() {
+        assert_eq!(WatchEvent::from_webhook("push"), Some(WatchEvent::Pushes));
+        assert_eq!(
+            WatchEvent::from_webhook("pull_request"),
+            Some(WatchEvent::PullRequests)
+        );
+        assert_eq!(WatchEvent::from_webhook("ping"), None);
+        assert_eq!(WatchEvent::from_webhook("star"), None);
+    }
+
+    fn webhook_activity(event: &str, json: &str) -> Option<Activity> {
+        let payload: Payload = serde_json::from_str(json).unwrap();
+        activity(event, payload, "owner/repo", Some("main"), None)
+    }
+
+    #[test]
+    fn pushes_only_to_the_default_branch() {
+        let push = |branch: &str| {
+            webhook_activity(
+                "push",
+                &format!(
+                    r#"{{"ref":"refs/heads/{}","before":"a","after":"b","commits":[
+                        {{"id":"0123456789abcdef","message":"First\n\nbody"}},
+                        {{"id":"fedcba9876543210","message":"Second"}}]}}"#,
+                    branch
+                ),
+            )
+        };
+        assert!(push("feature").is_none());
+        assert!(webhook_activity("push", r#"{"ref":"refs/tags/v1"}"#).is_none());
+
+        let activity = push("main").unwrap();
+        assert_eq!(activity.kind, WatchEvent::Pushes);
+        assert_eq!(activity.title, "2 new commits on main");
+        assert_eq!(activity.url, "https://github.com/owner/repo/compare/a...b");
+        let description = activity.description.unwrap();
+        // Newest first, only the subject line
+        assert!(description.starts_with("[`fedcba9`]"));
+        assert!(description.ends_with(") First"));
+    }
+
+    #[test]
+    fn merged_and_closed_pull_requests_differ() {
+        let pull = |merged: bool| {
+            webhook_activity(
+                "pull_request",
+                &format!(
+                    r#"{{"action":"closed","pull_request":{{"number":7,"title":"Fix","merged":{}}}}}"#,
+                    merged
+                ),
+            )
+            .unwrap()
+        };
+        let merged = pull(true);
+        assert_eq!(merged.kind, WatchEvent::PullRequests);
+        assert_eq!(merged.title, "Pull request #7 merged: Fix");
+        assert_eq!(merged.url, "https://github.com/owner/repo/pull/7");
+        assert_eq!(pull(false).title, "Pull request #7 closed: Fix");
+
+        assert!(
+            webhook_activity(
+                "pull_request",
+                r#"{"action":"labeled","pull_request":{"number":7}}"#
+            )
+            .is_none()
+        );
+        let issue =
+            webhook_activity("issues", r#"{"action":"opened","issue":{"number":3}}"#).unwrap();
+        assert_eq!(issue.kind, WatchEvent::Issues);
+        assert_eq!(issue.title, "Issue #3 opened");
+    }
+
+    #[test]
+    fn only_published_releases_are_posted() {
+        let release = |action: &str| {
+            webhook_activity(
+                "release",
+                &format!(
+                    r#"{{"action":"{}","release":{{"name":"","tag_name":"v1.0",
+                        "html_url":"https://github.com/owner/repo/releases/v1.0","body":"Notes"}}}}"#,
+                    action
+                ),
+            )
+        };
+        let published = release("published").unwrap();
+        assert_eq!(published.kind, WatchEvent::Releases);
+        assert_eq!(published.title, "Release v1.0");
+        assert_eq!(published.description.as_deref(), Some("Notes"));
+        assert!(release("created").is_none());
+        assert!(webhook_activity("ping", r#"{"zen":"Keep it simple"}"#).is_none());
+    }
+
+    #[test]
+    fn parses_event_lists() {
+        assert_eq!(parse_events(None).unwrap(), WatchEvent::ALL.to_vec());
+        assert_eq!(parse_events(Some("  ")).unwrap(), WatchEvent::ALL.to_vec());
+        assert_eq!(
+            parse_events(Some("PRs, push,,pulls")).unwrap(),
+            vec![WatchEvent::PullRequests, WatchEvent::Pushes]
+        );
+        assert!(
+            parse_events(Some("releases, stars"))
+                .unwrap_err()
+                .contains("`stars`")
+        );
+    }
+}
@@ -12,8 +12,8 @@ use crate::{
 };
 
 const DEFAULT_SERVER: &str = "2b2t.org";
-const DEFAULT_PORT: u16 = 25565;
-const DEFAULT_PROTOCOL_VERSION: i32 = 770;
+pub(super) const DEFAULT_PORT: u16 = 25565;
+pub(super) const DEFAULT_PROTOCOL_VERSION: i32 = 770;
 
 /// Returns server info, filling in defaults if any parameter is None.
 fn default_server_info(
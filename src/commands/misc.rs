@@ -9,7 +9,7 @@ use crate::{
     utils::bot::{self, error_text, is_admin},
 };
 
-async fn get_time_and_tz(timezone: Option<String>) -> (String, String) {
+pub(super) async fn get_time_and_tz(timezone: Option<String>) -> (String, String) {
     let utc_now: DateTime<Utc> = Utc::now();
 
     match timezone {
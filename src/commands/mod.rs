@@ -6,15 +6,34 @@ pub mod morse;
 pub use morse::*;
 pub mod deepseek;
 pub use deepseek::*;
+pub mod ai_usage;
+pub use ai_usage::*;
+pub mod personas;
+pub use personas::*;
+pub mod summarize;
+pub use summarize::*;
+
+pub mod ai_tools;
+
 pub mod youtube;
 pub use youtube::*;
+pub mod yt_subscriptions;
+pub use yt_subscriptions::*;
+
 pub mod mc_server;
 pub use mc_server::*;
 pub mod cat;
 pub use cat::*;
 pub mod alias;
 pub use alias::*;
+pub mod alias_history;
+pub use alias_history::*;
 pub mod reminders;
 pub use reminders::*;
 pub mod github;
 pub use github::*;
+pub mod github_insights;
+pub mod github_watch;
+pub use github_watch::*;
+pub mod unfurl;
+pub use unfurl::*;
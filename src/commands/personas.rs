@@ -0,0 +1,287 @@
+use poise::CreateReply;
+use serenity::all::{AutocompleteChoice, CreateEmbed};
+
+use crate::{
+    Context, Error,
+    config::{Config, Persona},
+    utils::{
+        bot::{self, can_manage_guild, error_text, is_admin},
+        llm,
+    },
+};
+
+const MAX_SYSTEM_PROMPT: usize = 4000;
+
+/// Suggests persona names matching the input.
+pub(super) async fn autocomplete_persona(
+    ctx: Context<'_>,
+    partial: &str,
+) -> Vec<AutocompleteChoice> {
+    let config = ctx.data().config.read().await;
+    let partial = partial.to_lowercase();
+    config
+        .personas
+        .keys()
+        .filter(|name| name.to_lowercase().contains(&partial))
+        .take(25)
+        .map(|name| AutocompleteChoice::new(name.clone(), name.clone()))
+        .collect()
+}
+
+/// Resolves the persona to use: the requested one, else `fallback`
+/// (e.g. a conversation's persona), else the guild's default.
+/// Returns an error message for unknown persona names.
+pub(super) fn resolve_persona(
+    config: &Config,
+    requested: Option<&str>,
+    fallback: Option<&str>,
+    guild_id: Option<u64>,
+) -> Result<Option<(String, Persona)>, String> {
+    if let Some(name) = requested {
+        return match config.personas.get(name) {
+            Some(persona) => Ok(Some((name.to_string(), persona.clone()))),
+            None => Err(format!("Unknown persona `{}`", name)),
+        };
+    }
+
+    let guild_default = guild_id.and_then(|g| config.guild_personas.get(&g.to_string()));
+    Ok(fallback
+        .or(guild_default.map(String::as_str))
+        .and_then(|name| {
+            config
+                .personas
+                .get(name)
+                .map(|persona| (name.to_string(), persona.clone()))
+        }))
+}
+
+async fn save_config(ctx: Context<'_>) -> Result<(), Error> {
+    ctx.data().config.read().await.save("config.json").await?;
+    Ok(())
+}
+
+#[poise::command(slash_command)]
+/// Creates or replaces a persona for the AI commands.
+pub async fn persona_set(
+    ctx: Context<'_>,
+    #[description = "Persona name"]
+    #[autocomplete = "autocomplete_persona"]
+    name: String,
+    #[description = "System prompt"] system_prompt: String,
+    #[description = "Sampling temperature (0-2)"]
+    #[min = 0]
+    #[max = 2]
+    temperature: Option<f32>,
+    #[description = "Model as provider/model"] model: Option<String>,
+    #[description = "Maximum tokens of an answer"]
+    #[min = 1]
+    max_tokens: Option<u32>,
+    #[description = "Send the response directly to you?"] ephemeral: Option<bool>,
+) -> Result<(), Error> {
+    let ephemeral = bot::defer_based_on_ephemeral(ctx, ephemeral).await?;
+
+    if !is_admin(ctx).await? {
+        error_text(
+            &ctx,
+            ephemeral,
+            "You are not allowed to run the /persona_set command",
+        )
+        .await;
+        return Ok(());
+    }
+
+    let name = name.trim().to_string();
+    if name.is_empty() || system_prompt.chars().count() > MAX_SYSTEM_PROMPT {
+        error_text(
+            &ctx,
+            ephemeral,
+            &format!(
+                "Please provide a name and a system prompt of at most {} characters",
+                MAX_SYSTEM_PROMPT
+            ),
+        )
+        .await;
+        return Ok(());
+    }
+
+    {
+        let mut config = ctx.data().config.write().await;
+        if let Some(model) = &model
+            && !llm::available_models(&config).contains(model)
+        {
+            drop(config);
+            error_text(&ctx, ephemeral, &format!("Unknown model `{}`", model)).await;
+            return Ok(());
+        }
+
+        config.personas.insert(
+            name.clone(),
+            Persona {
+                system_prompt: system_prompt.replace("\\n", "\n"),
+                temperature,
+                model,
+                max_tokens,
+            },
+        );
+    }
+    save_config(ctx).await?;
+
+    ctx.send(
+        CreateReply::default()
+            .content(format!("Saved persona `{}`", name))
+            .ephemeral(ephemeral),
+    )
+    .await?;
+
+    Ok(())
+}
+
+#[poise::command(slash_command)]
+/// Deletes a persona.
+pub async fn persona_delete(
+    ctx: Context<'_>,
+    #[description = "Persona name"]
+    #[autocomplete = "autocomplete_persona"]
+    name: String,
+    #[description = "Send the response directly to you?"] ephemeral: Option<bool>,
+) -> Result<(), Error> {
+    let ephemeral = bot::defer_based_on_ephemeral(ctx, ephemeral).await?;
+
+    if !is_admin(ctx).await? {
+        error_text(
+            &ctx,
+            ephemeral,
+            "You are not allowed to run the /persona_delete command",
+        )
+        .await;
+        return Ok(());
+    }
+
+    let removed = {
+        let mut config = ctx.data().config.write().await;
+        let removed = config.personas.remove(&name).is_some();
+        config.guild_personas.retain(|_, persona| *persona != name);
+        removed
+    };
+    if !removed {
+        error_text(&ctx, ephemeral, &format!("Unknown persona `{}`", name)).await;
+        return Ok(());
+    }
+    save_config(ctx).await?;
+
+    ctx.send(
+        CreateReply::default()
+            .content(format!("Deleted persona `{}`", name))
+            .ephemeral(ephemeral),
+    )
+    .await?;
+
+    Ok(())
+}
+
+#[poise::command(slash_command, guild_only)]
+/// Sets or clears this server's default persona.
+pub async fn persona_default(
+    ctx: Context<'_>,
+    #[description = "Persona name, empty to clear"]
+    #[autocomplete = "autocomplete_persona"]
+    name: Option<String>,
+    #[description = "Send the response directly to you?"] ephemeral: Option<bool>,
+) -> Result<(), Error> {
+    let ephemeral = bot::defer_based_on_ephemeral(ctx, ephemeral).await?;
+
+    if !can_manage_guild(ctx).await? {
+        error_text(
+            &ctx,
+            ephemeral,
+            "You need the Manage Server permission to set the default persona",
+        )
+        .await;
+        return Ok(());
+    }
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+
+    {
+        let mut config = ctx.data().config.write().await;
+        match &name {
+            Some(name) if !config.personas.contains_key(name) => {
+                drop(config);
+                error_text(&ctx, ephemeral, &format!("Unknown persona `{}`", name)).await;
+                return Ok(());
+            }
+            Some(name) => {
+                config
+                    .guild_personas
+                    .insert(guild_id.to_string(), name.clone());
+            }
+            None => {
+                config.guild_personas.remove(&guild_id.to_string());
+            }
+        }
+    }
+    save_config(ctx).await?;
+
+    let text = match name {
+        Some(name) => format!("Default persona set to `{}`", name),
+        None => "Default persona cleared".to_string(),
+    };
+    ctx.send(CreateReply::default().content(text).ephemeral(ephemeral))
+        .await?;
+
+    Ok(())
+}
+
+#[poise::command(slash_command)]
+/// Lists the available personas.
+pub async fn personas(
+    ctx: Context<'_>,
+    #[description = "Send the response directly to you?"] ephemeral: Option<bool>,
+) -> Result<(), Error> {
+    let ephemeral = bot::defer_based_on_ephemeral(ctx, ephemeral).await?;
+
+    let config = ctx.data().config.read().await;
+    if config.personas.is_empty() {
+        drop(config);
+        error_text(&ctx, ephemeral, "No personas configured").await;
+        return Ok(());
+    }
+
+    let guild_default = ctx
+        .guild_id()
+        .and_then(|g| config.guild_personas.get(&g.to_string()));
+
+    let mut embed = CreateEmbed::new().title("Personas");
+    for (name, persona) in config.personas.iter().take(25) {
+        let mut details = Vec::new();
+        if let Some(model) = &persona.model {
+            details.push(format!("model `{}`", model));
+        }
+        if let Some(temperature) = persona.temperature {
+            details.push(format!("temperature {}", temperature));
+        }
+        if let Some(max_tokens) = persona.max_tokens {
+            details.push(format!("max {} tokens", max_tokens));
+        }
+
+        let prompt: String = persona.system_prompt.chars().take(200).collect();
+        let mut value = format!("> {}", prompt.replace('\n', " "));
+        if !details.is_empty() {
+            value.push_str(&format!("\n{}", details.join(", ")));
+        }
+
+        let title = if guild_default == Some(name) {
+            format!("{} (server default)", name)
+        } else {
+            name.clone()
+        };
+        embed = embed.field(title, value, false);
+    }
+    drop(config);
+
+    ctx.send(CreateReply::default().embed(embed).ephemeral(ephemeral))
+        .await?;
+
+    Ok(())
+}
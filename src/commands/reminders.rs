@@ -1,32 +1,84 @@
 use std::time::{Duration, SystemTime};
 
+use once_cell::sync::Lazy;
 use poise::CreateReply;
+use rand::Rng;
 use serde::{Deserialize, Serialize};
-use serenity::all::{CreateMessage, UserId};
-use tokio::fs;
+use serenity::all::{
+    ActionRowComponent, AutocompleteChoice, ButtonStyle, Colour, ComponentInteraction,
+    ComponentInteractionCollector, CreateActionRow, CreateButton, CreateEmbed, CreateEmbedFooter,
+    CreateInputText, CreateInteractionResponse, CreateInteractionResponseMessage, CreateMessage,
+    CreateModal, InputTextStyle, Interaction, ModalInteraction, Timestamp, UserId,
+};
+use tokio::{fs, sync::Mutex};
 
 use crate::{
     Context, Error,
-    utils::bot::{self, error_text},
+    utils::{
+        bot::{self, error_text},
+        markdown::truncate,
+    },
 };
 
 const REMINDERS_PATH: &str = "reminders.json";
 
+/// Prefix shared by all custom ids of reminder buttons and modals.
+const COMPONENT_PREFIX: &str = "reminder:";
+const SNOOZE_PREFIX: &str = "reminder:snooze:";
+const CUSTOM_SNOOZE_ID: &str = "reminder:custom";
+const CUSTOM_SNOOZE_MODAL_ID: &str = "reminder:custom_modal";
+const CUSTOM_SNOOZE_INPUT_ID: &str = "reminder:custom_input";
+const DONE_ID: &str = "reminder:done";
+
+/// How long a delivered reminder is kept around so it can still be snoozed.
+const DELIVERED_RETENTION: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+const ID_LENGTH: usize = 5;
+const ID_ALPHABET: &[u8] = b"abcdefghijkmnpqrstuvwxyz23456789";
+
+/// Reminders shown per `/reminders` page.
+const PAGE_SIZE: usize = 5;
+const PAGINATION_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+const INVALID_TIME_TEXT: &str =
+    "Invalid time format. Use formats like 1h1m1s, 1h10m, 10h, 1d, 1w, or 1y.";
+
+/// Serializes every read-modify-write of the reminders file.
+static REMINDERS_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
 /// Represents a reminder set by a user.
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Reminder {
+    /// Short id shown to the user, stable for the reminder's lifetime.
+    #[serde(default)]
+    pub id: String,
     pub time: SystemTime,
     pub message: String,
     pub user_id: u64,
     pub direct: bool,
+    /// Link to the message the reminder was created from.
+    #[serde(default)]
+    pub origin: Option<String>,
+    /// Id of the DM the reminder was delivered in, set once it fired.
+    #[serde(default)]
+    pub delivered_message: Option<u64>,
 }
 
 /// Load reminders from disk asynchronously.
+/// Reminders stored before ids existed are given one here.
 async fn load_reminders() -> Vec<Reminder> {
-    match fs::read_to_string(REMINDERS_PATH).await {
+    let mut reminders: Vec<Reminder> = match fs::read_to_string(REMINDERS_PATH).await {
         Ok(data) => serde_json::from_str(&data).unwrap_or_default(),
         Err(_) => vec![],
+    };
+
+    for i in 0..reminders.len() {
+        if reminders[i].id.is_empty() {
+            reminders[i].id = generate_id(&reminders);
+        }
     }
+
+    reminders
 }
 
 /// Save reminders to disk asynchronously.
@@ -36,6 +88,71 @@ async fn save_reminders(reminders: &[Reminder]) {
     }
 }
 
+/// Generates a short id that isn't used by any of `existing`.
+fn generate_id(existing: &[Reminder]) -> String {
+    let mut rng = rand::thread_rng();
+    loop {
+        let id: String = (0..ID_LENGTH)
+            .map(|_| ID_ALPHABET[rng.gen_range(0..ID_ALPHABET.len())] as char)
+            .collect();
+        if !existing.iter().any(|r| r.id == id) {
+            return id;
+        }
+    }
+}
+
+/// Returns the user's pending (not yet delivered) reminders, soonest first.
+fn pending_for_user(reminders: &[Reminder], user_id: u64) -> Vec<Reminder> {
+    let mut pending: Vec<Reminder> = reminders
+        .iter()
+        .filter(|r| r.user_id == user_id && r.delivered_message.is_none())
+        .cloned()
+        .collect();
+    pending.sort_by_key(|r| r.time);
+    pending
+}
+
+/// Formats how long until `time`, rounded to whole seconds.
+fn format_remaining(time: SystemTime) -> String {
+    let remaining = time.duration_since(SystemTime::now()).unwrap_or_default();
+    humantime::format_duration(Duration::from_secs(remaining.as_secs())).to_string()
+}
+
+/// Suggests the invoking user's reminder ids with a preview of each.
+async fn autocomplete_reminder_id(ctx: Context<'_>, partial: &str) -> Vec<AutocompleteChoice> {
+    let reminders = load_reminders().await;
+    let partial = partial.to_lowercase();
+
+    pending_for_user(&reminders, ctx.author().id.get())
+        .into_iter()
+        .filter(|r| r.id.starts_with(&partial) || r.message.to_lowercase().contains(&partial))
+        .take(25)
+        .map(|r| {
+            let name = format!("{} · in {} · {}", r.id, format_remaining(r.time), r.message);
+            AutocompleteChoice::new(truncate(&name, 100), r.id)
+        })
+        .collect()
+}
+
+/// Stores a new reminder delivered by direct message and returns its id.
+pub(super) async fn create_reminder(user_id: u64, duration: Duration, message: String) -> String {
+    let _guard = REMINDERS_LOCK.lock().await;
+    let mut reminders = load_reminders().await;
+    let id = generate_id(&reminders);
+
+    reminders.push(Reminder {
+        id: id.clone(),
+        time: SystemTime::now() + duration,
+        message,
+        user_id,
+        direct: true,
+        origin: None,
+        delivered_message: None,
+    });
+    save_reminders(&reminders).await;
+    id
+}
+
 /// Slash command to set a new reminder.
 #[poise::command(slash_command)]
 pub async fn reminder(
@@ -50,40 +167,103 @@ pub async fn reminder(
     let duration = match humantime::parse_duration(&when) {
         Ok(d) => d,
         Err(_) => {
-            error_text(
-                &ctx,
-                ephemeral,
-                "Invalid time format. Use formats like 1h1m1s, 1h10m, 10h, 1d, 1w, or 1y.",
-            )
-            .await;
+            error_text(&ctx, ephemeral, INVALID_TIME_TEXT).await;
             return Ok(());
         }
     };
 
-    let reminder = Reminder {
-        time: SystemTime::now() + duration,
-        message: what.clone(),
-        user_id: ctx.author().id.get(),
-        direct: ephemeral,
+    // Store the reminder before replying so the lock isn't held across Discord calls
+    let id = {
+        let _guard = REMINDERS_LOCK.lock().await;
+        let mut reminders = load_reminders().await;
+        let id = generate_id(&reminders);
+        reminders.push(Reminder {
+            id: id.clone(),
+            time: SystemTime::now() + duration,
+            message: what,
+            user_id: ctx.author().id.get(),
+            direct: ephemeral,
+            origin: None,
+            delivered_message: None,
+        });
+        save_reminders(&reminders).await;
+        id
     };
 
-    let mut reminders = load_reminders().await;
-    reminders.push(reminder);
-    save_reminders(&reminders).await;
+    let reply = ctx
+        .send(
+            CreateReply::default()
+                .content(format!(
+                    "Reminder `{}` set for {} from now!",
+                    id,
+                    humantime::format_duration(duration)
+                ))
+                .ephemeral(ephemeral),
+        )
+        .await?;
 
-    ctx.send(
-        CreateReply::default()
-            .content(format!(
-                "Reminder set for {} from now!",
-                humantime::format_duration(duration)
-            ))
-            .ephemeral(ephemeral),
-    )
-    .await?;
+    if let Ok(message) = reply.message().await {
+        let _guard = REMINDERS_LOCK.lock().await;
+        let mut reminders = load_reminders().await;
+        if let Some(reminder) = reminders.iter_mut().find(|r| r.id == id) {
+            reminder.origin = Some(message.link());
+            save_reminders(&reminders).await;
+        }
+    }
 
     Ok(())
 }
 
+/// Builds one page of the `/reminders` listing, including its delete and navigation buttons.
+fn reminders_page(pending: &[Reminder], page: usize, ctx_id: u64, ephemeral: bool) -> CreateReply {
+    let pages = pending.len().div_ceil(PAGE_SIZE).max(1);
+    let items = pending.iter().skip(page * PAGE_SIZE).take(PAGE_SIZE);
+
+    let mut embed = CreateEmbed::default()
+        .title("Your reminders")
+        .color(Colour::GOLD)
+        .footer(CreateEmbedFooter::new(format!(
+            "Page {}/{} · {} reminder(s)",
+            page + 1,
+            pages,
+            pending.len()
+        )));
+    let mut delete_buttons = Vec::new();
+
+    for reminder in items.clone() {
+        embed = embed.field(
+            format!("`{}` · in {}", reminder.id, format_remaining(reminder.time)),
+            truncate(&reminder.message, 1024),
+            false,
+        );
+        delete_buttons.push(
+            CreateButton::new(format!("{}del:{}", ctx_id, reminder.id))
+                .label(format!("Delete {}", reminder.id))
+                .style(ButtonStyle::Danger),
+        );
+    }
+
+    if pending.is_empty() {
+        embed = embed.description("You have no reminders.");
+    }
+
+    let mut components = Vec::new();
+    if !delete_buttons.is_empty() {
+        components.push(CreateActionRow::Buttons(delete_buttons));
+    }
+    if pages > 1 {
+        components.push(CreateActionRow::Buttons(vec![
+            CreateButton::new(format!("{}prev", ctx_id)).emoji('◀'),
+            CreateButton::new(format!("{}next", ctx_id)).emoji('▶'),
+        ]));
+    }
+
+    CreateReply::default()
+        .embed(embed)
+        .components(components)
+        .ephemeral(ephemeral)
+}
+
 /// Slash command to list all reminders for the user.
 #[poise::command(slash_command)]
 pub async fn reminders(
@@ -91,17 +271,11 @@ pub async fn reminders(
     #[description = "Send the response directly to you?"] ephemeral: Option<bool>,
 ) -> Result<(), Error> {
     let ephemeral = bot::defer_based_on_ephemeral(ctx, ephemeral).await?;
-    let reminders = load_reminders().await;
     let user_id = ctx.author().id.get();
+    let ctx_id = ctx.id();
 
-    // Collect reminders belonging to the user with their global indices
-    let user_reminders: Vec<(usize, &Reminder)> = reminders
-        .iter()
-        .enumerate()
-        .filter(|(_, r)| r.user_id == user_id)
-        .collect();
-
-    if user_reminders.is_empty() {
+    let mut pending = pending_for_user(&load_reminders().await, user_id);
+    if pending.is_empty() {
         ctx.send(
             CreateReply::default()
                 .content("You have no reminders.")
@@ -111,61 +285,193 @@ pub async fn reminders(
         return Ok(());
     }
 
-    let mut reply = String::from("Your reminders:\n");
-    for (i, reminder) in &user_reminders {
-        let remaining = reminder
-            .time
-            .duration_since(SystemTime::now())
-            .unwrap_or_default();
-        reply.push_str(&format!(
-            "`{}`: {} (in {})\n",
-            i,
-            reminder.message,
-            humantime::format_duration(remaining)
-        ));
+    let mut page = 0;
+    let reply = ctx
+        .send(reminders_page(&pending, page, ctx_id, ephemeral))
+        .await?;
+
+    let prefix_len = ctx_id.to_string().len();
+    while let Some(press) = ComponentInteractionCollector::new(ctx)
+        .filter(move |press| press.data.custom_id.starts_with(&ctx_id.to_string()))
+        .timeout(PAGINATION_TIMEOUT)
+        .await
+    {
+        // Answer every press, otherwise Discord reports the interaction as failed
+        if press.user.id != ctx.author().id {
+            press
+                .create_response(
+                    ctx.serenity_context(),
+                    CreateInteractionResponse::Message(
+                        CreateInteractionResponseMessage::new()
+                            .content("These buttons belong to someone else's reminder list.")
+                            .ephemeral(true),
+                    ),
+                )
+                .await?;
+            continue;
+        }
+
+        let action = &press.data.custom_id[prefix_len..];
+        let pages = pending.len().div_ceil(PAGE_SIZE).max(1);
+
+        if action == "next" {
+            page = (page + 1) % pages;
+        } else if action == "prev" {
+            page = page.checked_sub(1).unwrap_or(pages - 1);
+        } else if let Some(id) = action.strip_prefix("del:") {
+            let _guard = REMINDERS_LOCK.lock().await;
+            let mut reminders = load_reminders().await;
+            reminders.retain(|r| !(r.id == id && r.user_id == user_id));
+            save_reminders(&reminders).await;
+        } else {
+            press
+                .create_response(
+                    ctx.serenity_context(),
+                    CreateInteractionResponse::Acknowledge,
+                )
+                .await?;
+            continue;
+        }
+
+        // Refresh remaining times and drop reminders that fired in the meantime
+        pending = pending_for_user(&load_reminders().await, user_id);
+        page = page.min(pending.len().div_ceil(PAGE_SIZE).max(1) - 1);
+
+        let CreateReply {
+            embeds, components, ..
+        } = reminders_page(&pending, page, ctx_id, ephemeral);
+        press
+            .create_response(
+                ctx.serenity_context(),
+                CreateInteractionResponse::UpdateMessage(
+                    CreateInteractionResponseMessage::new()
+                        .embeds(embeds)
+                        .components(components.unwrap_or_default()),
+                ),
+            )
+            .await?;
     }
 
-    ctx.send(CreateReply::default().content(reply).ephemeral(ephemeral))
+    // Navigation timed out, remove the now dead buttons
+    reply
+        .edit(
+            ctx,
+            reminders_page(&pending, page, ctx_id, ephemeral).components(vec![]),
+        )
+        .await
+        .ok();
+
+    Ok(())
+}
+
+/// Slash command to change the time or text of a reminder.
+#[poise::command(slash_command)]
+pub async fn reminder_edit(
+    ctx: Context<'_>,
+    #[description = "Reminder id from /reminders"]
+    #[autocomplete = "autocomplete_reminder_id"]
+    id: String,
+    #[description = "New time from now, e.g. 2h"] when: Option<String>,
+    #[description = "New text"] what: Option<String>,
+    #[description = "Send the response directly to you?"] ephemeral: Option<bool>,
+) -> Result<(), Error> {
+    let ephemeral = bot::defer_based_on_ephemeral(ctx, ephemeral).await?;
+
+    if when.is_none() && what.is_none() {
+        error_text(
+            &ctx,
+            ephemeral,
+            "Nothing to change, give a new time or text.",
+        )
+        .await;
+        return Ok(());
+    }
+
+    let duration = match when.as_deref().map(humantime::parse_duration) {
+        Some(Ok(d)) => Some(d),
+        Some(Err(_)) => {
+            error_text(&ctx, ephemeral, INVALID_TIME_TEXT).await;
+            return Ok(());
+        }
+        None => None,
+    };
+
+    let user_id = ctx.author().id.get();
+    let summary = {
+        let _guard = REMINDERS_LOCK.lock().await;
+        let mut reminders = load_reminders().await;
+        let reminder = reminders
+            .iter_mut()
+            .find(|r| r.id == id && r.user_id == user_id && r.delivered_message.is_none());
+        let summary = reminder.map(|reminder| {
+            if let Some(duration) = duration {
+                reminder.time = SystemTime::now() + duration;
+            }
+            if let Some(what) = what {
+                reminder.message = what;
+            }
+            format!(
+                "Reminder `{}` updated: {} (in {})",
+                reminder.id,
+                reminder.message,
+                format_remaining(reminder.time)
+            )
+        });
+        if summary.is_some() {
+            save_reminders(&reminders).await;
+        }
+        summary
+    };
+
+    let Some(summary) = summary else {
+        error_text(&ctx, ephemeral, &format!("No reminder with id `{}`.", id)).await;
+        return Ok(());
+    };
+
+    ctx.send(CreateReply::default().content(summary).ephemeral(ephemeral))
         .await?;
+
     Ok(())
 }
 
-/// Slash command to delete a reminder by its user-visible index.
+/// Slash command to delete a reminder by its id.
 #[poise::command(slash_command)]
 pub async fn delete_reminder(
     ctx: Context<'_>,
-    #[description = "Reminder index from /reminders"] index: usize,
+    #[description = "Reminder id from /reminders"]
+    #[autocomplete = "autocomplete_reminder_id"]
+    id: String,
     #[description = "Send the response directly to you?"] ephemeral: Option<bool>,
 ) -> Result<(), Error> {
     let ephemeral = bot::defer_based_on_ephemeral(ctx, ephemeral).await?;
-    let mut reminders = load_reminders().await;
     let user_id = ctx.author().id.get();
 
-    // Find the global indices of reminders belonging to the user
-    let user_indices: Vec<usize> = reminders
-        .iter()
-        .enumerate()
-        .filter(|(_, r)| r.user_id == user_id)
-        .map(|(i, _)| i)
-        .collect();
+    let deleted = {
+        let _guard = REMINDERS_LOCK.lock().await;
+        let mut reminders = load_reminders().await;
+        let before = reminders.len();
+        reminders
+            .retain(|r| !(r.id == id && r.user_id == user_id && r.delivered_message.is_none()));
+        let deleted = reminders.len() != before;
+        if deleted {
+            save_reminders(&reminders).await;
+        }
+        deleted
+    };
 
-    if index >= user_indices.len() {
+    if !deleted {
         ctx.send(
             CreateReply::default()
-                .content("Invalid reminder index.")
+                .content(format!("No reminder with id `{}`.", id))
                 .ephemeral(ephemeral),
         )
         .await?;
         return Ok(());
     }
 
-    let global_index = user_indices[index];
-    reminders.remove(global_index);
-    save_reminders(&reminders).await;
-
     ctx.send(
         CreateReply::default()
-            .content("Reminder deleted.")
+            .content(format!("Reminder `{}` deleted.", id))
             .ephemeral(ephemeral),
     )
     .await?;
@@ -173,29 +479,234 @@ pub async fn delete_reminder(
     Ok(())
 }
 
+/// Builds the embed and buttons a reminder is delivered with.
+fn reminder_message(reminder: &Reminder) -> CreateMessage {
+    let mut embed = CreateEmbed::default()
+        .title("⏰ Reminder")
+        // Slash command strings may be longer than an embed description
+        .description(truncate(&reminder.message, 4096))
+        .color(Colour::GOLD)
+        .timestamp(Timestamp::now());
+
+    if let Some(origin) = &reminder.origin {
+        embed = embed.field("Created", format!("[Jump to message]({})", origin), false);
+    }
+
+    let buttons = CreateActionRow::Buttons(vec![
+        CreateButton::new(format!("{}{}", SNOOZE_PREFIX, 10 * 60))
+            .label("Snooze 10m")
+            .style(ButtonStyle::Secondary),
+        CreateButton::new(format!("{}{}", SNOOZE_PREFIX, 60 * 60))
+            .label("Snooze 1h")
+            .style(ButtonStyle::Secondary),
+        CreateButton::new(CUSTOM_SNOOZE_ID)
+            .label("Snooze…")
+            .style(ButtonStyle::Secondary),
+        CreateButton::new(DONE_ID)
+            .label("Done")
+            .style(ButtonStyle::Success),
+    ]);
+
+    CreateMessage::default()
+        .embed(embed)
+        .components(vec![buttons])
+}
+
+/// Reschedules the delivered reminder behind `message_id`.
+/// Returns false if the reminder no longer exists.
+async fn snooze_delivered(message_id: u64, duration: Duration) -> bool {
+    let _guard = REMINDERS_LOCK.lock().await;
+    let mut reminders = load_reminders().await;
+    let Some(reminder) = reminders
+        .iter_mut()
+        .find(|r| r.delivered_message == Some(message_id))
+    else {
+        return false;
+    };
+
+    reminder.time = SystemTime::now() + duration;
+    reminder.delivered_message = None;
+    save_reminders(&reminders).await;
+    true
+}
+
+/// Removes the delivered reminder behind `message_id`.
+async fn finish_delivered(message_id: u64) {
+    let _guard = REMINDERS_LOCK.lock().await;
+    let mut reminders = load_reminders().await;
+    reminders.retain(|r| r.delivered_message != Some(message_id));
+    save_reminders(&reminders).await;
+}
+
+/// Replaces the delivered reminder's buttons with a status line.
+fn resolved_response(status: String) -> CreateInteractionResponse {
+    CreateInteractionResponse::UpdateMessage(
+        CreateInteractionResponseMessage::new()
+            .content(status)
+            .components(vec![]),
+    )
+}
+
+/// Handles the snooze and done buttons of delivered reminders.
+/// Interactions that don't belong to reminders are ignored.
+pub async fn handle_reminder_interaction(
+    ctx: &serenity::all::Context,
+    interaction: &Interaction,
+) -> Result<(), Error> {
+    match interaction {
+        Interaction::Component(component)
+            if component.data.custom_id.starts_with(COMPONENT_PREFIX) =>
+        {
+            handle_reminder_button(ctx, component).await
+        }
+        Interaction::Modal(modal) if modal.data.custom_id == CUSTOM_SNOOZE_MODAL_ID => {
+            handle_custom_snooze(ctx, modal).await
+        }
+        _ => Ok(()),
+    }
+}
+
+async fn handle_reminder_button(
+    ctx: &serenity::all::Context,
+    component: &ComponentInteraction,
+) -> Result<(), Error> {
+    let message_id = component.message.id.get();
+    let custom_id = component.data.custom_id.as_str();
+
+    let response = if custom_id == DONE_ID {
+        finish_delivered(message_id).await;
+        resolved_response("✅ Done.".to_string())
+    } else if custom_id == CUSTOM_SNOOZE_ID {
+        let input =
+            CreateInputText::new(InputTextStyle::Short, "Snooze for", CUSTOM_SNOOZE_INPUT_ID)
+                .placeholder("e.g. 30m, 2h, 1d")
+                .required(true);
+        CreateInteractionResponse::Modal(
+            CreateModal::new(CUSTOM_SNOOZE_MODAL_ID, "Snooze reminder")
+                .components(vec![CreateActionRow::InputText(input)]),
+        )
+    } else if let Some(secs) = custom_id
+        .strip_prefix(SNOOZE_PREFIX)
+        .and_then(|s| s.parse::<u64>().ok())
+    {
+        let duration = Duration::from_secs(secs);
+        if snooze_delivered(message_id, duration).await {
+            resolved_response(format!(
+                "💤 Snoozed for {}.",
+                humantime::format_duration(duration)
+            ))
+        } else {
+            resolved_response("This reminder no longer exists.".to_string())
+        }
+    } else {
+        return Ok(());
+    };
+
+    component.create_response(&ctx.http, response).await?;
+    Ok(())
+}
+
+async fn handle_custom_snooze(
+    ctx: &serenity::all::Context,
+    modal: &ModalInteraction,
+) -> Result<(), Error> {
+    let Some(message_id) = modal.message.as_ref().map(|m| m.id.get()) else {
+        return Ok(());
+    };
+
+    let value = modal
+        .data
+        .components
+        .iter()
+        .flat_map(|row| &row.components)
+        .find_map(|c| match c {
+            ActionRowComponent::InputText(input) if input.custom_id == CUSTOM_SNOOZE_INPUT_ID => {
+                input.value.clone()
+            }
+            _ => None,
+        })
+        .unwrap_or_default();
+
+    let response = match humantime::parse_duration(value.trim()) {
+        Ok(duration) => {
+            if snooze_delivered(message_id, duration).await {
+                resolved_response(format!(
+                    "💤 Snoozed for {}.",
+                    humantime::format_duration(duration)
+                ))
+            } else {
+                resolved_response("This reminder no longer exists.".to_string())
+            }
+        }
+        Err(_) => CreateInteractionResponse::Message(
+            CreateInteractionResponseMessage::new()
+                .content("Invalid time format. Use formats like 10m, 1h30m, 1d or 1w.")
+                .ephemeral(true),
+        ),
+    };
+
+    modal.create_response(&ctx.http, response).await?;
+    Ok(())
+}
+
 /// Starts the background task that checks for due reminders every second.
 pub async fn start_reminder_loop(ctx: serenity::all::Context) {
     tokio::spawn(async move {
         loop {
-            let reminders = load_reminders().await;
             let now = SystemTime::now();
+            let due: Vec<Reminder> = {
+                let _guard = REMINDERS_LOCK.lock().await;
+                let mut reminders = load_reminders().await;
+                let before = reminders.len();
+
+                // Drop delivered reminders nobody snoozed within the retention window
+                reminders.retain(|r| {
+                    r.delivered_message.is_none()
+                        || now
+                            .duration_since(r.time)
+                            .is_ok_and(|age| age < DELIVERED_RETENTION)
+                });
+                if reminders.len() != before {
+                    save_reminders(&reminders).await;
+                }
+
+                reminders
+                    .into_iter()
+                    .filter(|r| r.delivered_message.is_none() && r.time <= now)
+                    .collect()
+            };
 
-            // Partition reminders into due and future
-            let (due, future): (Vec<_>, Vec<_>) =
-                reminders.into_iter().partition(|r| r.time <= now);
-
-            for reminder in due {
-                if let Ok(user) = ctx.http.get_user(UserId::new(reminder.user_id)).await {
-                    let _ = user
-                        .dm(
-                            &ctx.http,
-                            CreateMessage::default().content(reminder.message.clone()),
-                        )
+            if !due.is_empty() {
+                // Send without holding the lock, then record the outcome by id
+                let mut delivered = Vec::new();
+                for reminder in &due {
+                    let result = UserId::new(reminder.user_id)
+                        .direct_message(&ctx.http, reminder_message(reminder))
                         .await;
+                    if let Err(e) = &result {
+                        tracing::warn!("Failed to deliver reminder: {}", e);
+                    }
+                    delivered.push((reminder.id.as_str(), result.ok().map(|msg| msg.id.get())));
                 }
-            }
 
-            save_reminders(&future).await;
+                let _guard = REMINDERS_LOCK.lock().await;
+                let mut reminders = load_reminders().await;
+                for (id, message) in delivered {
+                    match message {
+                        Some(message) => {
+                            if let Some(reminder) = reminders
+                                .iter_mut()
+                                .find(|r| r.id == id && r.delivered_message.is_none())
+                            {
+                                reminder.time = now;
+                                reminder.delivered_message = Some(message);
+                            }
+                        }
+                        None => reminders.retain(|r| r.id != id),
+                    }
+                }
+                save_reminders(&reminders).await;
+            }
 
             // Sleep for one second before checking again
             tokio::time::sleep(Duration::from_secs(1)).await;
@@ -0,0 +1,360 @@
+use chrono::Utc;
+use once_cell::sync::Lazy;
+use poise::CreateReply;
+use regex::{Captures, Regex};
+use serenity::{
+    all::{ChannelId, CreateEmbed, GetMessages, GuildId, Message, MessageId},
+    futures::StreamExt,
+};
+
+use super::{
+    ai_usage::{check_budget, record_usage},
+    deepseek::{autocomplete_model, chat_stream},
+};
+use crate::{
+    Context, Data, Error,
+    utils::{
+        bot::{self, error_text, is_deepseek},
+        llm::{self, ChatEvent, ChatMessage, LlmError, TokenUsage},
+        stream_output::{ReplySink, StreamOutput},
+    },
+};
+
+const DEFAULT_MESSAGES: usize = 100;
+const MAX_MESSAGES: usize = 500;
+/// Messages are summarized in parts of at most this many estimated tokens.
+const CHUNK_TOKENS: u64 = 6000;
+/// Longer messages are cut before they are summarized.
+const MAX_MESSAGE_LEN: usize = 600;
+
+const SUMMARY_PROMPT: &str = "You summarize Discord conversations. Every message is prefixed \
+with its number in square brackets. Write a short summary of the topics, decisions and open \
+questions as a bullet list. Cite the key messages by their number, e.g. [12].";
+const COMBINE_PROMPT: &str = "You combine partial summaries of one Discord conversation into a \
+single short bullet list of topics, decisions and open questions. Keep the message citations \
+like [12] of the key points.";
+
+/// Matches a citation like `[12]` that is not already a link.
+static CITATION: Lazy<Regex> = Lazy::new(|| Regex::new(r"\[(\d+)\](\()?").expect("Invalid regex"));
+
+/// The messages to summarize, oldest first, within `count` and `since`.
+async fn fetch_history(
+    ctx: Context<'_>,
+    channel_id: ChannelId,
+    count: usize,
+    since: Option<i64>,
+) -> Result<Vec<Message>, serenity::Error> {
+    let mut messages: Vec<Message> = Vec::new();
+    let mut before: Option<MessageId> = None;
+
+    while messages.len() < count {
+        let mut request = GetMessages::new().limit((count - messages.len()).min(100) as u8);
+        if let Some(before) = before {
+            request = request.before(before);
+        }
+        let page = channel_id.messages(ctx.http(), request).await?;
+        let Some(last) = page.last() else {
+            break;
+        };
+        before = Some(last.id);
+
+        let page_len = page.len();
+        let mut reached_since = false;
+        for message in page {
+            if since.is_some_and(|since| message.timestamp.unix_timestamp() < since) {
+                reached_since = true;
+                break;
+            }
+            messages.push(message);
+        }
+        if reached_since || page_len < 100 {
+            break;
+        }
+    }
+
+    messages.reverse();
+    Ok(messages)
+}
+
+/// Formats the messages as numbered lines, skipping messages without text or files.
+fn format_history(messages: &[Message]) -> Vec<String> {
+    messages
+        .iter()
+        .enumerate()
+        .filter_map(|(i, message)| {
+            let mut content: String = message.content.chars().take(MAX_MESSAGE_LEN).collect();
+            for attachment in &message.attachments {
+                content.push_str(&format!(" (file: {})", attachment.filename));
+            }
+            let content = content.trim().replace('\n', " ");
+            (!content.is_empty()).then(|| {
+                format!(
+                    "[{}] {} {}: {}",
+                    i + 1,
+                    message.timestamp.format("%Y-%m-%d %H:%M"),
+                    message.author.display_name(),
+                    content
+                )
+            })
+        })
+        .collect()
+}
+
+/// Splits the lines into parts that fit the model context.
+fn chunk_lines(lines: Vec<String>) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    for line in lines {
+        if !current.is_empty()
+            && llm::estimate_text_tokens(&current) + llm::estimate_text_tokens(&line) > CHUNK_TOKENS
+        {
+            chunks.push(std::mem::take(&mut current));
+        }
+        current.push_str(&line);
+        current.push('\n');
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+/// Turns citations like `[12]` into links to the cited message.
+fn link_citations(
+    text: &str,
+    messages: &[Message],
+    channel_id: ChannelId,
+    guild_id: Option<GuildId>,
+) -> String {
+    CITATION
+        .replace_all(text, |caps: &Captures| {
+            let message = caps[1]
+                .parse::<usize>()
+                .ok()
+                .and_then(|n| n.checked_sub(1))
+                .and_then(|i| messages.get(i));
+            match message {
+                Some(message) if caps.get(2).is_none() => format!(
+                    "[#{}](<{}>)",
+                    &caps[1],
+                    message.id.link(channel_id, guild_id)
+                ),
+                _ => caps[0].to_string(),
+            }
+        })
+        .into_owned()
+}
+
+/// Requests a complete answer without showing it.
+async fn complete(
+    data: &Data,
+    model: &str,
+    messages: Vec<ChatMessage>,
+) -> Result<(String, Option<TokenUsage>), LlmError> {
+    let mut deltas = chat_stream(data, model, None, messages, Vec::new()).await?;
+    let mut text = String::new();
+    let mut usage = None;
+    while let Some(event) = deltas.next().await {
+        match event? {
+            ChatEvent::Delta(content) => text.push_str(&content),
+            ChatEvent::Usage(reported) => usage = Some(reported),
+            ChatEvent::ToolCalls(_) => {}
+        }
+    }
+    Ok((text, usage))
+}
+
+#[poise::command(slash_command, guild_only)]
+/// Summarizes the recent messages of this channel or thread.
+pub async fn summarize(
+    ctx: Context<'_>,
+    #[description = "Number of messages to summarize (default 100)"]
+    #[min = 1]
+    #[max = 500]
+    count: Option<usize>,
+    #[description = "Only messages from this long ago, like `2h` or `1day`"] since: Option<String>,
+    #[description = "Model to use"]
+    #[autocomplete = "autocomplete_model"]
+    model: Option<String>,
+    #[description = "Send the response directly to you?"] ephemeral: Option<bool>,
+) -> Result<(), Error> {
+    let ephemeral = bot::defer_based_on_ephemeral(ctx, ephemeral).await?;
+
+    if !is_deepseek(ctx).await? {
+        error_text(&ctx, ephemeral, "You are not allowed to use deepseek!").await;
+        return Ok(());
+    }
+
+    // Only summarize what the user could read themselves
+    let can_read = ctx.author_member().await.is_some_and(|member| {
+        member.permissions.is_some_and(|permissions| {
+            permissions.view_channel() && permissions.read_message_history()
+        })
+    });
+    if !can_read {
+        error_text(
+            &ctx,
+            ephemeral,
+            "You need permission to read this channel's history",
+        )
+        .await;
+        return Ok(());
+    }
+
+    let since = match since
+        .as_deref()
+        .map(|s| humantime::parse_duration(s.trim()))
+    {
+        Some(Ok(duration)) => Some(Utc::now().timestamp() - duration.as_secs() as i64),
+        Some(Err(_)) => {
+            error_text(
+                &ctx,
+                ephemeral,
+                "Invalid duration, use something like `2h` or `1day`",
+            )
+            .await;
+            return Ok(());
+        }
+        None => None,
+    };
+    let count = count
+        .unwrap_or(if since.is_some() {
+            MAX_MESSAGES
+        } else {
+            DEFAULT_MESSAGES
+        })
+        .min(MAX_MESSAGES);
+
+    let user_id = ctx.author().id.get();
+    let guild_id = ctx.guild_id();
+    let model = {
+        let config = ctx.data().config.read().await;
+        if let Err(reason) = check_budget(&config, user_id, guild_id.map(|g| g.get())).await {
+            error_text(&ctx, ephemeral, &reason).await;
+            return Ok(());
+        }
+        model.unwrap_or_else(|| config.default_model.clone())
+    };
+
+    let channel_id = ctx.channel_id();
+    let messages = match fetch_history(ctx, channel_id, count, since).await {
+        Ok(messages) => messages,
+        Err(e) => {
+            error_text(&ctx, ephemeral, &format!("Failed to read messages: {}", e)).await;
+            return Ok(());
+        }
+    };
+    let chunks = chunk_lines(format_history(&messages));
+    if chunks.is_empty() {
+        error_text(&ctx, ephemeral, "No messages to summarize").await;
+        return Ok(());
+    }
+
+    let header = CreateEmbed::new().title(format!(
+        "{} summary of the last {} messages",
+        model,
+        messages.len()
+    ));
+    let reply = ctx
+        .send(
+            CreateReply::default()
+                .content("Please wait...")
+                .embed(header.clone())
+                .ephemeral(ephemeral),
+        )
+        .await?;
+    let mut output = StreamOutput::new(ReplySink::new(ctx, ephemeral, reply, Some(header)));
+
+    // Long histories are summarized in parts first, then combined
+    let final_request = if chunks.len() == 1 {
+        vec![
+            ChatMessage::system(SUMMARY_PROMPT),
+            ChatMessage::user(chunks[0].clone()),
+        ]
+    } else {
+        let mut summaries = Vec::new();
+        for (i, chunk) in chunks.iter().enumerate() {
+            output
+                .update(&format!("Summarizing part {} of {}", i + 1, chunks.len()))
+                .await?;
+            let request = vec![
+                ChatMessage::system(SUMMARY_PROMPT),
+                ChatMessage::user(chunk.clone()),
+            ];
+            let prompt_estimate = llm::estimate_tokens(&request);
+            match complete(ctx.data(), &model, request).await {
+                Ok((summary, usage)) => {
+                    record_usage(
+                        user_id,
+                        guild_id.map(|g| g.get()),
+                        &model,
+                        usage,
+                        prompt_estimate,
+                        &summary,
+                    )
+                    .await;
+                    summaries.push(summary);
+                }
+                Err(e) => {
+                    output
+                        .finish(&format!("Failed to summarize: {}", e))
+                        .await?;
+                    return Ok(());
+                }
+            }
+        }
+        vec![
+            ChatMessage::system(COMBINE_PROMPT),
+            ChatMessage::user(summaries.join("\n\n")),
+        ]
+    };
+
+    let prompt_estimate = llm::estimate_tokens(&final_request);
+    let mut deltas = match chat_stream(ctx.data(), &model, None, final_request, Vec::new()).await {
+        Ok(deltas) => deltas,
+        Err(e) => {
+            output
+                .finish(&format!("Failed to summarize: {}", e))
+                .await?;
+            return Ok(());
+        }
+    };
+
+    let mut summary = String::new();
+    let mut usage = None;
+    let mut error = None;
+    while let Some(event) = deltas.next().await {
+        match event {
+            Ok(ChatEvent::Delta(content)) => {
+                summary.push_str(&content);
+                output
+                    .update(&link_citations(&summary, &messages, channel_id, guild_id))
+                    .await?;
+            }
+            Ok(ChatEvent::Usage(reported)) => usage = Some(reported),
+            Ok(ChatEvent::ToolCalls(_)) => {}
+            Err(e) => {
+                error = Some(e);
+                break;
+            }
+        }
+    }
+
+    record_usage(
+        user_id,
+        guild_id.map(|g| g.get()),
+        &model,
+        usage,
+        prompt_estimate,
+        &summary,
+    )
+    .await;
+    output
+        .finish(&link_citations(&summary, &messages, channel_id, guild_id))
+        .await?;
+    if let Some(e) = error {
+        error_text(&ctx, ephemeral, &format!("Stream error: {}", e)).await;
+    }
+
+    Ok(())
+}
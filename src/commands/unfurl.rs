@@ -0,0 +1,622 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use once_cell::sync::Lazy;
+use poise::CreateReply;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serenity::all::{
+    ButtonStyle, ComponentInteraction, CreateActionRow, CreateAllowedMentions, CreateButton,
+    CreateEmbed, CreateInteractionResponse, CreateInteractionResponseMessage, CreateMessage,
+    GuildChannel, Interaction, Message,
+};
+use tokio::{fs, sync::RwLock};
+
+use super::{
+    github::{fetch_github, issue_embed, lookup_embed},
+    mc_server::{DEFAULT_PORT, DEFAULT_PROTOCOL_VERSION, create_server_embed},
+    youtube::video_embed,
+};
+use crate::{
+    Context, Data, Error,
+    utils::{
+        bot::{self, can_manage_guild, error_text, user_in_whitelist},
+        server, youtube,
+    },
+};
+
+const UNFURL_PATH: &str = "unfurl.json";
+const DISMISS_PREFIX: &str = "unfurl_dismiss:";
+/// Links previewed per message.
+const MAX_PREVIEWS: usize = 3;
+/// Minimum time between two previews in a channel.
+const CHANNEL_COOLDOWN: Duration = Duration::from_secs(20);
+/// A link previewed in a channel is not previewed there again within this time.
+const REPEAT_WINDOW: Duration = Duration::from_secs(10 * 60);
+
+static URL_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(<)?(https?://[^\s<>|]+)").expect("Invalid regex"));
+static MC_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?:^|\s)mc:([A-Za-z0-9][A-Za-z0-9.-]*\.[A-Za-z0-9]+)(?::(\d{1,5}))?")
+        .expect("Invalid regex")
+});
+/// Code blocks and inline code, whose links are not previewed.
+static CODE_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?s)```.*?```|`[^`]*`").expect("Invalid regex"));
+
+/// First path segments of github.com that are not users.
+const GITHUB_RESERVED: [&str; 12] = [
+    "about",
+    "apps",
+    "collections",
+    "enterprise",
+    "explore",
+    "features",
+    "marketplace",
+    "notifications",
+    "orgs",
+    "settings",
+    "sponsors",
+    "topics",
+];
+
+/// Where link previews are turned on.
+#[derive(Serialize, Deserialize, Default)]
+struct UnfurlSettings {
+    /// Guilds previewing links in every channel.
+    guilds: HashSet<u64>,
+    /// Channel settings, overriding their guild's.
+    channels: HashMap<u64, bool>,
+}
+
+impl UnfurlSettings {
+    fn enabled(&self, guild_id: u64, channel_id: u64) -> bool {
+        self.channels
+            .get(&channel_id)
+            .copied()
+            .unwrap_or_else(|| self.guilds.contains(&guild_id))
+    }
+}
+
+static SETTINGS: Lazy<RwLock<UnfurlSettings>> =
+    Lazy::new(|| RwLock::new(UnfurlSettings::default()));
+
+/// Recent previews of a channel.
+#[derive(Default)]
+struct ChannelPreviews {
+    last: Option<Instant>,
+    /// When each link was last previewed.
+    links: HashMap<String, Instant>,
+}
+
+static PREVIEWS: Lazy<RwLock<HashMap<u64, ChannelPreviews>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// Load the link preview settings from disk into memory at startup.
+pub async fn load_unfurl_settings_from_file() -> Result<(), std::io::Error> {
+    if Path::new(UNFURL_PATH).exists() {
+        let data = fs::read_to_string(UNFURL_PATH).await?;
+        *SETTINGS.write().await = serde_json::from_str(&data)?;
+    }
+    Ok(())
+}
+
+/// Saves the link preview settings to disk as pretty JSON.
+async fn save_unfurl_settings_to_file() {
+    let json = {
+        let settings = SETTINGS.read().await;
+        serde_json::to_string_pretty(&*settings)
+    };
+    match json {
+        Ok(json) => {
+            if let Err(e) = fs::write(UNFURL_PATH, json).await {
+                tracing::warn!("Failed to save unfurl settings: {}", e);
+            }
+        }
+        Err(e) => tracing::warn!("Failed to serialize unfurl settings: {}", e),
+    }
+}
+
+/// Something in a message worth a preview.
+enum Target {
+    GitHub(String),
+    GitHubIssue {
+        repo: String,
+        number: u64,
+    },
+    YouTube {
+        video_id: String,
+        start: Option<Duration>,
+    },
+    Minecraft {
+        host: String,
+        port: u16,
+    },
+}
+
+impl Target {
+    /// Identifies the target when looking for repeats.
+    fn key(&self) -> String {
+        match self {
+            Target::GitHub(query) => format!("gh:{}", query.to_lowercase()),
+            Target::GitHubIssue { repo, number } => {
+                format!("gh:{}#{}", repo.to_lowercase(), number)
+            }
+            Target::YouTube { video_id, .. } => format!("yt:{}", video_id),
+            Target::Minecraft { host, port } => format!("mc:{}:{}", host.to_lowercase(), port),
+        }
+    }
+}
+
+/// Reads a github.com link to a user, repository, issue or pull request.
+fn github_target(url: &str) -> Option<Target> {
+    let rest = url.split_once("://")?.1;
+    let (host, path) = rest.split_once('/')?;
+    if !matches!(
+        host.to_lowercase().as_str(),
+        "github.com" | "www.github.com"
+    ) {
+        return None;
+    }
+    let path = path.split(['?', '#']).next().unwrap_or_default();
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    let owner = *segments.first()?;
+    if GITHUB_RESERVED.contains(&owner.to_lowercase().as_str()) {
+        return None;
+    }
+
+    match segments.as_slice() {
+        [owner] => Some(Target::GitHub(owner.to_string())),
+        [owner, repo] => Some(Target::GitHub(format!(
+            "{}/{}",
+            owner,
+            repo.trim_end_matches(".git")
+        ))),
+        [owner, repo, "issues" | "pull", number, ..] => Some(Target::GitHubIssue {
+            repo: format!("{}/{}", owner, repo),
+            number: number.parse().ok()?,
+        }),
+        _ => None,
+    }
+}
+
+/// Finds the previewable links and `mc:host` mentions of a message, without repeats.
+/// Links in code or wrapped in `<>`, which Discord does not embed either, are skipped.
+fn find_targets(content: &str) -> Vec<Target> {
+    let content = CODE_REGEX.replace_all(content, " ");
+    let mut targets: Vec<Target> = Vec::new();
+
+    for caps in URL_REGEX.captures_iter(&content) {
+        if caps.get(1).is_some() {
+            continue;
+        }
+        let url = caps[2].trim_end_matches(['.', ',', ';', ':', '!', '?', ')', '\'', '"']);
+        let target = youtube::parse_url(url)
+            .and_then(|link| {
+                Some(Target::YouTube {
+                    video_id: link.video_id?,
+                    start: link.start,
+                })
+            })
+            .or_else(|| github_target(url));
+        if let Some(target) = target
+            && !targets.iter().any(|t| t.key() == target.key())
+        {
+            targets.push(target);
+        }
+    }
+
+    for caps in MC_REGEX.captures_iter(&content) {
+        let port = match caps.get(2) {
+            Some(port) => match port.as_str().parse() {
+                Ok(port) => port,
+                Err(_) => continue,
+            },
+            None => DEFAULT_PORT,
+        };
+        let target = Target::Minecraft {
+            host: caps[1].to_string(),
+            port,
+        };
+        if !targets.iter().any(|t| t.key() == target.key()) {
+            targets.push(target);
+        }
+    }
+
+    targets.truncate(MAX_PREVIEWS);
+    targets
+}
+
+/// Builds the same embed the matching slash command shows. Returns None
+/// if there is nothing to show, failures are only logged.
+async fn target_embed(
+    target: &Target,
+    github_token: Option<&str>,
+    youtube_token: Option<&str>,
+    youtube_public_url: &str,
+) -> Option<CreateEmbed> {
+    let result = match target {
+        Target::GitHub(query) => fetch_github(github_token, query, false)
+            .await
+            .map(|lookup| lookup.as_ref().map(lookup_embed))
+            .map_err(|e| e.to_string()),
+        Target::GitHubIssue { repo, number } => issue_embed(github_token, repo, *number, false)
+            .await
+            .map_err(|e| e.to_string()),
+        Target::YouTube { video_id, start } => {
+            video_embed(youtube_token, youtube_public_url, video_id, *start, false)
+                .await
+                .map_err(|e| e.to_string())
+        }
+        Target::Minecraft { host, port } => {
+            server::ping::ping(host, *port, DEFAULT_PROTOCOL_VERSION)
+                .await
+                .map(|status| Some(create_server_embed(&status)))
+                .map_err(|e| e.to_string())
+        }
+    };
+    result
+        .inspect_err(|e| tracing::debug!("No preview for {}: {}", target.key(), e))
+        .ok()
+        .flatten()
+}
+
+/// Takes the targets not previewed in the channel lately.
+/// Returns nothing while the channel cools down.
+async fn fresh_targets(channel_id: u64, targets: Vec<Target>) -> Vec<Target> {
+    let previews = PREVIEWS.read().await;
+    let Some(channel) = previews.get(&channel_id) else {
+        return targets;
+    };
+    if channel
+        .last
+        .is_some_and(|last| last.elapsed() < CHANNEL_COOLDOWN)
+    {
+        return Vec::new();
+    }
+    targets
+        .into_iter()
+        .filter(|target| {
+            channel
+                .links
+                .get(&target.key())
+                .is_none_or(|previewed| previewed.elapsed() >= REPEAT_WINDOW)
+        })
+        .collect()
+}
+
+/// Records the links of a preview about to be sent and starts the channel's cooldown.
+/// Returns false if another preview was sent in the meantime.
+async fn record_preview(channel_id: u64, keys: Vec<String>) -> bool {
+    let mut previews = PREVIEWS.write().await;
+    let channel = previews.entry(channel_id).or_default();
+    if channel
+        .last
+        .is_some_and(|last| last.elapsed() < CHANNEL_COOLDOWN)
+    {
+        return false;
+    }
+
+    channel
+        .links
+        .retain(|_, previewed| previewed.elapsed() < REPEAT_WINDOW);
+    let now = Instant::now();
+    channel.last = Some(now);
+    for key in keys {
+        channel.links.insert(key, now);
+    }
+    true
+}
+
+/// Replies to messages in channels with previews turned on with embeds of their links.
+pub async fn handle_unfurl_message(
+    ctx: &serenity::all::Context,
+    data: &Data,
+    msg: &Message,
+) -> Result<(), Error> {
+    if msg.author.bot {
+        return Ok(());
+    }
+    let Some(guild_id) = msg.guild_id else {
+        return Ok(());
+    };
+    if !SETTINGS
+        .read()
+        .await
+        .enabled(guild_id.get(), msg.channel_id.get())
+    {
+        return Ok(());
+    }
+
+    let (github_token, youtube_token, youtube_public_url, youtube_allowed, ping_allowed) = {
+        let config = data.config.read().await;
+        let user_id = msg.author.id.get();
+        (
+            config.github_token.clone(),
+            config.youtube_token.clone(),
+            config.youtube_public_url.clone(),
+            user_in_whitelist(
+                config.youtube_whitelist_active,
+                &config.youtube_whitelist,
+                user_id,
+            ),
+            user_in_whitelist(
+                config.ping_whitelist_active,
+                &config.ping_whitelist,
+                user_id,
+            ),
+        )
+    };
+    // The whitelists of the slash commands apply to the author
+    let targets: Vec<Target> = find_targets(&msg.content)
+        .into_iter()
+        .filter(|target| match target {
+            Target::YouTube { .. } => youtube_allowed,
+            Target::Minecraft { .. } => ping_allowed,
+            Target::GitHub(_) | Target::GitHubIssue { .. } => true,
+        })
+        .collect();
+    if targets.is_empty() {
+        return Ok(());
+    }
+    let targets = fresh_targets(msg.channel_id.get(), targets).await;
+
+    // Only links that got a preview count as previewed
+    let mut embeds = Vec::new();
+    let mut keys = Vec::new();
+    for target in &targets {
+        if let Some(embed) = target_embed(
+            target,
+            github_token.as_deref(),
+            youtube_token.as_deref(),
+            &youtube_public_url,
+        )
+        .await
+        {
+            embeds.push(embed);
+            keys.push(target.key());
+        }
+    }
+    if embeds.is_empty() || !record_preview(msg.channel_id.get(), keys).await {
+        return Ok(());
+    }
+
+    let dismiss = CreateButton::new(format!("{}{}", DISMISS_PREFIX, msg.author.id))
+        .label("Dismiss")
+        .style(ButtonStyle::Secondary);
+    msg.channel_id
+        .send_message(
+            &ctx.http,
+            CreateMessage::new()
+                .embeds(embeds)
+                .reference_message(msg)
+                .allowed_mentions(CreateAllowedMentions::new())
+                .components(vec![CreateActionRow::Buttons(vec![dismiss])]),
+        )
+        .await?;
+
+    Ok(())
+}
+
+/// Deletes a preview when its Dismiss button is used by the linking user or a moderator.
+pub async fn handle_unfurl_interaction(
+    ctx: &serenity::all::Context,
+    interaction: &Interaction,
+) -> Result<(), Error> {
+    let Interaction::Component(component) = interaction else {
+        return Ok(());
+    };
+    let Some(author_id) = component
+        .data
+        .custom_id
+        .strip_prefix(DISMISS_PREFIX)
+        .and_then(|id| id.parse::<u64>().ok())
+    else {
+        return Ok(());
+    };
+
+    if !may_dismiss(component, author_id) {
+        component
+            .create_response(
+                &ctx.http,
+                CreateInteractionResponse::Message(
+                    CreateInteractionResponseMessage::new()
+                        .content("Only the author of the linked message can dismiss this preview.")
+                        .ephemeral(true),
+                ),
+            )
+            .await?;
+        return Ok(());
+    }
+
+    component
+        .create_response(&ctx.http, CreateInteractionResponse::Acknowledge)
+        .await?;
+    component.message.delete(&ctx.http).await?;
+    Ok(())
+}
+
+fn may_dismiss(component: &ComponentInteraction, author_id: u64) -> bool {
+    component.user.id.get() == author_id
+        || component
+            .member
+            .as_ref()
+            .and_then(|member| member.permissions)
+            .is_some_and(|permissions| permissions.manage_messages())
+}
+
+#[poise::command(slash_command, guild_only)]
+/// Turns link previews for GitHub, YouTube and `mc:host` mentions on or off.
+pub async fn unfurl(
+    ctx: Context<'_>,
+    #[description = "Reply to links with previews?"] enabled: bool,
+    #[description = "Only change this channel instead of the whole server"]
+    #[channel_types("Text", "News")]
+    channel: Option<GuildChannel>,
+    #[description = "Send the response directly to you?"] ephemeral: Option<bool>,
+) -> Result<(), Error> {
+    let ephemeral = bot::defer_based_on_ephemeral(ctx, ephemeral).await?;
+
+    if !can_manage_guild(ctx).await? {
+        error_text(
+            &ctx,
+            ephemeral,
+            "You need the Manage Server permission to change link previews",
+        )
+        .await;
+        return Ok(());
+    }
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+    if channel.as_ref().is_some_and(|c| c.guild_id != guild_id) {
+        error_text(&ctx, ephemeral, "Please pick a channel of this server").await;
+        return Ok(());
+    }
+
+    let scope = {
+        let mut settings = SETTINGS.write().await;
+        match &channel {
+            Some(channel) => {
+                settings.channels.insert(channel.id.get(), enabled);
+                format!("in <#{}>", channel.id)
+            }
+            None => {
+                if enabled {
+                    settings.guilds.insert(guild_id.get());
+                } else {
+                    settings.guilds.remove(&guild_id.get());
+                }
+                "in this server, except channels set on their own".to_string()
+            }
+        }
+    };
+    save_unfurl_settings_to_file().await;
+
+    ctx.send(
+        CreateReply::default()
+            .content(format!(
+                "Link previews are now {} {}",
+                if enabled { "on" } else { "off" },
+                scope
+            ))
+            .ephemeral(ephemeral),
+    )
+    .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(content: &str) -> Vec<String> {
+        find_targets(content).iter().map(Target::key).collect()
+    }
+
+    #[test]
+    fn reads_github_links() {
+        let key = |url: &str| github_target(url).map(|target| target.key());
+        assert_eq!(key("https://github.com/Octocat"), Some("gh:octocat".into()));
+        assert_eq!(
+            key("https://www.github.com/serenity-rs/poise.git"),
+            Some("gh:serenity-rs/poise".into())
+        );
+        assert_eq!(
+            key("https://github.com/serenity-rs/poise/?tab=readme#usage"),
+            Some("gh:serenity-rs/poise".into())
+        );
+        assert_eq!(
+            key("https://github.com/serenity-rs/poise/pull/42/files"),
+            Some("gh:serenity-rs/poise#42".into())
+        );
+        assert_eq!(
+            key("https://github.com/serenity-rs/poise/issues/7"),
+            Some("gh:serenity-rs/poise#7".into())
+        );
+        for url in [
+            "https://github.com/",
+            "https://github.com/settings/profile",
+            "https://github.com/Topics",
+            "https://github.com/orgs/serenity-rs",
+            "https://github.com/serenity-rs/poise/issues/new",
+            "https://github.com/serenity-rs/poise/blob/main/README.md",
+            "https://gist.github.com/octocat",
+            "https://github.community/octocat",
+        ] {
+            assert!(github_target(url).is_none(), "{}", url);
+        }
+    }
+
+    #[test]
+    fn finds_links_discord_would_embed() {
+        assert_eq!(
+            keys(
+                "see https://github.com/serenity-rs/poise/issues/7, and (https://github.com/octocat)."
+            ),
+            ["gh:serenity-rs/poise#7", "gh:octocat"]
+        );
+        assert_eq!(keys("https://github.com/octocat?!'\""), ["gh:octocat"]);
+        assert!(keys("<https://github.com/octocat>").is_empty());
+        assert!(keys("`https://github.com/octocat`").is_empty());
+        assert!(keys("```\nhttps://github.com/octocat\n```").is_empty());
+        assert_eq!(
+            keys("`code` https://github.com/serenity-rs/poise `more`"),
+            ["gh:serenity-rs/poise"]
+        );
+        assert_eq!(
+            keys("https://github.com/octocat https://github.com/OctoCat"),
+            ["gh:octocat"]
+        );
+    }
+
+    #[test]
+    fn finds_minecraft_servers() {
+        assert_eq!(
+            keys("join mc:Play.Example.com or mc:example.net:25566"),
+            [
+                format!("mc:play.example.com:{}", DEFAULT_PORT),
+                "mc:example.net:25566".to_string()
+            ]
+        );
+        assert!(keys("mc:example.com:99999").is_empty());
+        assert!(keys("mc:localhost xmc:example.com").is_empty());
+        assert!(keys("`mc:example.com`").is_empty());
+    }
+
+    #[test]
+    fn caps_previews_per_message() {
+        let users: Vec<String> = (0..MAX_PREVIEWS + 2)
+            .map(|i| format!("user{}", i))
+            .collect();
+        let content = users
+            .iter()
+            .map(|user| format!("https://github.com/{}", user))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let expected: Vec<String> = users[..MAX_PREVIEWS]
+            .iter()
+            .map(|user| format!("gh:{}", user))
+            .collect();
+        assert_eq!(keys(&content), expected);
+    }
+
+    #[tokio::test]
+    async fn only_sent_previews_count() {
+        const CHANNEL: u64 = 1;
+        let targets = || find_targets("https://github.com/a https://github.com/b");
+        assert_eq!(fresh_targets(CHANNEL, targets()).await.len(), 2);
+
+        assert!(record_preview(CHANNEL, vec!["gh:a".into()]).await);
+        // The channel cools down, after which only the previewed link is a repeat
+        assert!(fresh_targets(CHANNEL, targets()).await.is_empty());
+        assert!(!record_preview(CHANNEL, vec!["gh:b".into()]).await);
+        PREVIEWS.write().await.get_mut(&CHANNEL).unwrap().last = None;
+        let fresh = fresh_targets(CHANNEL, targets()).await;
+        assert_eq!(fresh.iter().map(Target::key).collect::<Vec<_>>(), ["gh:b"]);
+    }
+}
@@ -1,17 +1,97 @@
+use std::time::Duration;
+
 use once_cell::sync::Lazy;
 use poise::CreateReply;
 use regex::Regex;
-use serde::Deserialize;
-use serenity::all::{Color, CreateEmbed};
+use serenity::all::{
+    ButtonStyle, Color, ComponentInteractionCollector, CreateActionRow, CreateButton, CreateEmbed,
+    CreateEmbedFooter, CreateInteractionResponse, CreateInteractionResponseMessage,
+};
 
 use crate::{
     Context, Error,
-    utils::bot::{self, error_and_return, error_text, is_youtube},
+    utils::{
+        bot::{self, error_and_return, error_text, is_youtube},
+        markdown::truncate,
+        youtube::{
+            self, Channel, ChannelRef, ListResponse, MAX_PAGE_SIZE, SearchResult, YouTubeClient,
+            YouTubeError, YouTubeItem,
+            oembed::{PublicClient, PublicVideo},
+            unescape_html,
+        },
+    },
 };
 
 // Compile regex once
-static YT_ID_REGEX: Lazy<Regex> =
-    Lazy::new(|| Regex::new(r"(?:v=|\/)([0-9A-Za-z_-]{11})").expect("Invalid regex"));
+static CHANNEL_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?:/channel/|^)(UC[0-9A-Za-z_-]{22})(?:[/?#]|$)").expect("Invalid regex")
+});
+static HANDLE_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?:/|^)(@[0-9A-Za-z_.-]+)").expect("Invalid regex"));
+static USERNAME_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"/user/([0-9A-Za-z_-]+)").expect("Invalid regex"));
+
+/// Playlist videos whose durations are summed up, to bound the quota used.
+const MAX_DURATION_ITEMS: usize = 500;
+const SEARCH_PAGE_SIZE: u32 = 5;
+const PAGINATION_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// Recognizes channel ids, `@handles` and channel URLs.
+fn channel_ref(input: &str) -> Option<ChannelRef<'_>> {
+    let input = input.trim();
+    let capture = |regex: &Regex| {
+        regex
+            .captures(input)
+            .and_then(|caps| caps.get(1))
+            .map(|m| m.as_str())
+    };
+    capture(&CHANNEL_REGEX)
+        .map(ChannelRef::Id)
+        .or_else(|| capture(&HANDLE_REGEX).map(ChannelRef::Handle))
+        .or_else(|| capture(&USERNAME_REGEX).map(ChannelRef::Username))
+}
+
+/// Looks up a channel by URL, `@handle` or id. Anything else is searched for by name.
+pub(super) async fn resolve_channel(
+    client: &YouTubeClient,
+    input: &str,
+) -> Result<Option<Channel>, YouTubeError> {
+    if let Some(reference) = channel_ref(input) {
+        return client.channel(reference).await;
+    }
+
+    let results = client.search(input, Some("channel"), None, 1).await?;
+    match results.items.into_iter().find_map(|r| r.id.channel_id) {
+        Some(id) => client.channel(ChannelRef::Id(&id)).await,
+        None => Ok(None),
+    }
+}
+
+/// Checks the whitelist and the API key, reporting problems to the user.
+pub(super) async fn youtube_client(
+    ctx: Context<'_>,
+    ephemeral: bool,
+) -> Result<Option<YouTubeClient>, Error> {
+    if !is_youtube(ctx).await? {
+        error_text(
+            &ctx,
+            ephemeral,
+            "You are not allowed to use the YouTube API!",
+        )
+        .await;
+        return Ok(None);
+    }
+
+    let config = ctx.data().config.read().await;
+    match config.youtube_token.as_deref() {
+        Some(key) if !key.is_empty() => Ok(Some(YouTubeClient::new(key))),
+        _ => {
+            drop(config);
+            error_text(&ctx, ephemeral, "No YouTube API key configured").await;
+            Ok(None)
+        }
+    }
+}
 
 #[poise::command(slash_command)]
 pub async fn yt_vid(
@@ -32,54 +112,70 @@ pub async fn yt_vid(
         return Ok(());
     }
 
-    let video_id = match YT_ID_REGEX.captures(&url).and_then(|caps| caps.get(1)) {
-        Some(m) => m.as_str(),
-        None => {
-            error_text(&ctx, ephemeral, "Invalid YouTube URL provided").await;
-            return Ok(());
-        }
+    let Some((video_id, start)) =
+        youtube::parse_url(&url).and_then(|link| Some((link.video_id?, link.start)))
+    else {
+        error_text(&ctx, ephemeral, "Invalid YouTube URL provided").await;
+        return Ok(());
     };
 
-    let config = ctx.data().config.read().await;
-
-    let api_key = match config.youtube_token.as_deref() {
-        Some(key) if !key.is_empty() => key.to_owned(), // Clone key to own the string
-        _ => {
-            error_text(&ctx, ephemeral, "No YouTube API key configured").await;
-            return Ok(());
-        }
+    let (key, public_url) = {
+        let config = ctx.data().config.read().await;
+        (
+            config.youtube_token.clone(),
+            config.youtube_public_url.clone(),
+        )
     };
-
-    let api_url = format!(
-        "https://www.googleapis.com/youtube/v3/videos?part=snippet,statistics&id={video_id}&key={api_key}"
-    );
-
-    let response = match reqwest::get(&api_url).await {
-        Ok(resp) => resp,
-        Err(e) => {
-            error_and_return(&ctx, ephemeral, e).await?;
-            return Ok(());
+    match video_embed(
+        key.as_deref(),
+        &public_url,
+        &video_id,
+        start,
+        show_description.unwrap_or(false),
+    )
+    .await
+    {
+        Ok(Some(embed)) => {
+            ctx.send(CreateReply::default().ephemeral(ephemeral).embed(embed))
+                .await?;
         }
-    };
+        Ok(None) => error_text(&ctx, ephemeral, "Video not found").await,
+        Err(e) => return error_and_return(&ctx, ephemeral, e).await,
+    }
 
-    let yt_response: YouTubeResponse = match response.json().await {
-        Ok(json) => json,
-        Err(e) => {
-            error_and_return(&ctx, ephemeral, e).await?;
-            return Ok(());
-        }
-    };
+    Ok(())
+}
 
-    let video = match yt_response.items.first() {
-        Some(video) => video,
-        None => {
-            error_text(&ctx, ephemeral, "Video not found").await;
-            return Ok(());
-        }
-    };
+/// Builds the `yt_vid` embed of a video. Returns None if the video does not exist.
+/// Without an API key only the public metadata from `public_url` is available.
+pub(super) async fn video_embed(
+    key: Option<&str>,
+    public_url: &str,
+    video_id: &str,
+    start: Option<Duration>,
+    show_description: bool,
+) -> Result<Option<CreateEmbed>, YouTubeError> {
+    let mut link = youtube::video_url(video_id);
+    if let Some(start) = start {
+        link.push_str(&format!("?t={}", start.as_secs()));
+    }
 
-    let link = format!("https://youtu.be/{}", video.id);
+    let Some(key) = key.filter(|key| !key.is_empty()) else {
+        let video = PublicClient::with_base_url(public_url)
+            .video(video_id, show_description)
+            .await?;
+        return Ok(video.map(|video| public_video_embed(&video, &link, start, show_description)));
+    };
+    let video = YouTubeClient::new(key).video(video_id).await?;
+    Ok(video.map(|video| api_video_embed(&video, &link, start, show_description)))
+}
 
+fn api_video_embed(
+    video: &YouTubeItem,
+    link: &str,
+    start: Option<Duration>,
+    show_description: bool,
+) -> CreateEmbed {
     let views = video.statistics.view_count.parse::<f64>().unwrap_or(0.0);
     let likes = video
         .statistics
@@ -95,10 +191,13 @@ pub async fn yt_vid(
 
     let mut embed = CreateEmbed::default()
         .title(&video.snippet.title)
-        .url(&link)
-        .thumbnail(&video.snippet.thumbnails.high.url)
+        .url(link)
         .field("Channel", &video.snippet.channel_title, true)
-        .field("Published", &video.snippet.published_at[..10], true)
+        .field(
+            "Published",
+            video.snippet.published_at.get(..10).unwrap_or("N/A"),
+            true,
+        )
         .field("Views", &video.statistics.view_count, true)
         .field(
             "Likes",
@@ -113,8 +212,170 @@ pub async fn yt_vid(
         .field("Like View Ratio", format!("{:.2}%", like_ratio), true)
         .color(Color::RED);
 
-    if show_description.unwrap_or(false) {
-        embed = embed.description(&video.snippet.description);
+    if let Some(details) = &video.content_details {
+        if let Some(duration) = youtube::parse_duration(&details.duration) {
+            embed = embed.field("Duration", youtube::format_duration(duration), true);
+        }
+        embed = embed
+            .field("Definition", details.definition.to_uppercase(), true)
+            .field(
+                "Captions",
+                if details.caption == "true" {
+                    "Available"
+                } else {
+                    "None"
+                },
+                true,
+            );
+    }
+    if let Some(start) = start {
+        embed = embed.field("Starts at", youtube::format_duration(start), true);
+    }
+
+    if let Some(thumbnail) = video.snippet.thumbnails.best() {
+        embed = embed.thumbnail(thumbnail);
+    }
+    if show_description {
+        embed = embed.description(truncate(&video.snippet.description, 4096));
+    }
+    embed
+}
+
+/// Shows what oEmbed and the watch page tell about a video, for when no API key is set.
+fn public_video_embed(
+    video: &PublicVideo,
+    link: &str,
+    start: Option<Duration>,
+    show_description: bool,
+) -> CreateEmbed {
+    let channel = match (&video.channel_title, &video.channel_url) {
+        (Some(title), Some(url)) => format!("[{}]({})", title, url),
+        (Some(title), None) => title.clone(),
+        _ => "Unknown".to_string(),
+    };
+
+    let mut embed = CreateEmbed::default()
+        .title(&video.title)
+        .url(link)
+        .field("Channel", channel, true)
+        .field(
+            "Statistics",
+            "Unavailable: no YouTube API key configured",
+            true,
+        )
+        .footer(CreateEmbedFooter::new(
+            "Limited info from public metadata; views, likes and duration need an API key",
+        ))
+        .color(Color::RED);
+
+    if let Some(start) = start {
+        embed = embed.field("Starts at", youtube::format_duration(start), true);
+    }
+    if let Some(thumbnail) = &video.thumbnail {
+        embed = embed.thumbnail(thumbnail);
+    }
+    if show_description && let Some(description) = &video.description {
+        embed = embed.description(description);
+    }
+    embed
+}
+
+#[poise::command(slash_command)]
+/// Shows a playlist with its total duration and first videos.
+pub async fn yt_playlist(
+    ctx: Context<'_>,
+    #[description = "Playlist URL or id"] playlist: String,
+    #[description = "Number of videos to list (default 10)"]
+    #[min = 1]
+    #[max = 25]
+    count: Option<usize>,
+    #[description = "Send the response directly to you?"] ephemeral: Option<bool>,
+) -> Result<(), Error> {
+    let ephemeral = bot::defer_based_on_ephemeral(ctx, ephemeral).await?;
+
+    let Some(client) = youtube_client(ctx, ephemeral).await? else {
+        return Ok(());
+    };
+    let Some(playlist_id) = youtube::parse_url(&playlist).and_then(|link| link.playlist_id) else {
+        error_text(&ctx, ephemeral, "Invalid playlist URL provided").await;
+        return Ok(());
+    };
+
+    let playlist = match client.playlist(&playlist_id).await {
+        Ok(Some(playlist)) => playlist,
+        Ok(None) => {
+            error_text(&ctx, ephemeral, "Playlist not found").await;
+            return Ok(());
+        }
+        Err(e) => return error_and_return(&ctx, ephemeral, e).await,
+    };
+
+    // Collect the videos page by page, summing up their durations
+    let count = count.unwrap_or(10);
+    let mut listed = Vec::new();
+    let mut total = Duration::ZERO;
+    let mut summed = 0;
+    let mut page_token: Option<String> = None;
+    while summed < MAX_DURATION_ITEMS {
+        let page = match client
+            .playlist_items(&playlist.id, page_token.as_deref(), MAX_PAGE_SIZE)
+            .await
+        {
+            Ok(page) => page,
+            Err(e) => return error_and_return(&ctx, ephemeral, e).await,
+        };
+        let ids: Vec<&str> = page.items.iter().filter_map(|i| i.video_id()).collect();
+        if !ids.is_empty() {
+            match client.total_duration(&ids).await {
+                Ok(duration) => total += duration,
+                Err(e) => return error_and_return(&ctx, ephemeral, e).await,
+            }
+        }
+        summed += page.items.len();
+
+        for item in &page.items {
+            if listed.len() < count
+                && let Some(id) = item.video_id()
+            {
+                listed.push(format!(
+                    "{}. [{}]({})",
+                    listed.len() + 1,
+                    truncate(&item.snippet.title, 80),
+                    youtube::video_url(id)
+                ));
+            }
+        }
+
+        page_token = page.next_page_token;
+        if page_token.is_none() {
+            break;
+        }
+    }
+
+    let duration = if page_token.is_some() {
+        format!(
+            "{}+ (first {} videos)",
+            youtube::format_duration(total),
+            summed
+        )
+    } else {
+        youtube::format_duration(total)
+    };
+
+    let mut embed = CreateEmbed::default()
+        .title(&playlist.snippet.title)
+        .url(youtube::playlist_url(&playlist.id))
+        .field("Channel", &playlist.snippet.channel_title, true)
+        .field(
+            "Videos",
+            playlist.content_details.item_count.to_string(),
+            true,
+        )
+        .field("Total duration", duration, true)
+        .description(listed.join("\n"))
+        .color(Color::RED);
+    if let Some(thumbnail) = playlist.snippet.thumbnails.best() {
+        embed = embed.thumbnail(thumbnail);
     }
 
     ctx.send(CreateReply::default().ephemeral(ephemeral).embed(embed))
@@ -123,46 +384,282 @@ pub async fn yt_vid(
     Ok(())
 }
 
-#[derive(Deserialize)]
-struct YouTubeResponse {
-    items: Vec<YouTubeItem>,
-}
+#[poise::command(slash_command)]
+/// Shows a channel's statistics and latest uploads.
+pub async fn yt_channel(
+    ctx: Context<'_>,
+    #[description = "Channel URL, @handle, id or name"] channel: String,
+    #[description = "Number of uploads to list (default 5)"]
+    #[min = 1]
+    #[max = 10]
+    count: Option<u32>,
+    #[description = "Send the response directly to you?"] ephemeral: Option<bool>,
+) -> Result<(), Error> {
+    let ephemeral = bot::defer_based_on_ephemeral(ctx, ephemeral).await?;
+
+    let Some(client) = youtube_client(ctx, ephemeral).await? else {
+        return Ok(());
+    };
+
+    let channel = match resolve_channel(&client, &channel).await {
+        Ok(Some(channel)) => channel,
+        Ok(None) => {
+            error_text(&ctx, ephemeral, "Channel not found").await;
+            return Ok(());
+        }
+        Err(e) => return error_and_return(&ctx, ephemeral, e).await,
+    };
+
+    let uploads = match client
+        .playlist_items(
+            &channel.content_details.related_playlists.uploads,
+            None,
+            count.unwrap_or(5),
+        )
+        .await
+    {
+        Ok(page) => page.items,
+        // Channels without uploads have no uploads playlist
+        Err(YouTubeError::Api { status: 404, .. }) => Vec::new(),
+        Err(e) => return error_and_return(&ctx, ephemeral, e).await,
+    };
 
-#[derive(Deserialize)]
-struct YouTubeItem {
-    id: String,
-    snippet: Snippet,
-    statistics: Statistics,
+    let mut description = truncate(&channel.snippet.description, 300);
+    if !uploads.is_empty() {
+        description.push_str("\n\n**Latest uploads**\n");
+        for item in &uploads {
+            if let Some(id) = item.video_id() {
+                description.push_str(&format!(
+                    "{} · [{}]({})\n",
+                    item.snippet.published_at.get(..10).unwrap_or_default(),
+                    truncate(&item.snippet.title, 80),
+                    youtube::video_url(id)
+                ));
+            }
+        }
+    }
+
+    let subscribers = if channel.statistics.hidden_subscriber_count {
+        "Hidden"
+    } else {
+        channel
+            .statistics
+            .subscriber_count
+            .as_deref()
+            .unwrap_or("N/A")
+    };
+    let mut embed = CreateEmbed::default()
+        .title(&channel.snippet.title)
+        .url(youtube::channel_url(&channel.id))
+        .description(description)
+        .field("Subscribers", subscribers, true)
+        .field("Videos", &channel.statistics.video_count, true)
+        .field("Views", &channel.statistics.view_count, true)
+        .field(
+            "Created",
+            channel.snippet.published_at.get(..10).unwrap_or_default(),
+            true,
+        )
+        .color(Color::RED);
+    if let Some(handle) = &channel.snippet.custom_url {
+        embed = embed.field("Handle", handle, true);
+    }
+    if let Some(thumbnail) = channel.snippet.thumbnails.best() {
+        embed = embed.thumbnail(thumbnail);
+    }
+
+    ctx.send(CreateReply::default().ephemeral(ephemeral).embed(embed))
+        .await?;
+
+    Ok(())
 }
 
-#[derive(Deserialize)]
-struct Snippet {
-    title: String,
-    #[serde(rename = "channelTitle")]
-    channel_title: String,
-    #[serde(rename = "publishedAt")]
-    published_at: String,
-    description: String,
-    thumbnails: Thumbnails,
+/// What `/yt_search` looks for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, poise::ChoiceParameter)]
+pub enum SearchKind {
+    #[name = "videos"]
+    Video,
+    #[name = "channels"]
+    Channel,
+    #[name = "playlists"]
+    Playlist,
 }
 
-#[derive(Deserialize)]
-struct Thumbnails {
-    #[serde(rename = "high")]
-    high: Thumbnail,
+impl SearchKind {
+    fn api_name(self) -> &'static str {
+        match self {
+            SearchKind::Video => "video",
+            SearchKind::Channel => "channel",
+            SearchKind::Playlist => "playlist",
+        }
+    }
 }
 
-#[derive(Deserialize)]
-struct Thumbnail {
-    url: String,
+/// Builds one page of `/yt_search` results with its navigation buttons.
+fn search_page(
+    query: &str,
+    page: &ListResponse<SearchResult>,
+    index: usize,
+    ctx_id: u64,
+    ephemeral: bool,
+) -> CreateReply {
+    let mut embed = CreateEmbed::default()
+        .title(format!("YouTube results for \"{}\"", truncate(query, 200)))
+        .color(Color::RED);
+    for result in &page.items {
+        let mut value = format!(
+            "{} · {}",
+            unescape_html(&result.snippet.channel_title),
+            result.snippet.published_at.get(..10).unwrap_or_default()
+        );
+        if result.snippet.live_broadcast_content.as_deref() == Some("live") {
+            value.push_str(" · 🔴 live");
+        }
+        if let Some(url) = result.url() {
+            value.push_str(&format!("\n{}", url));
+        }
+        embed = embed.field(
+            truncate(&unescape_html(&result.snippet.title), 256),
+            value,
+            false,
+        );
+    }
+    if page.items.is_empty() {
+        embed = embed.description("No results");
+    }
+
+    let mut footer = format!("Page {}", index + 1);
+    if let Some(info) = &page.page_info {
+        footer.push_str(&format!(" · about {} results", info.total_results));
+    }
+    embed = embed.footer(CreateEmbedFooter::new(footer));
+
+    let buttons = CreateActionRow::Buttons(vec![
+        CreateButton::new(format!("{}prev", ctx_id))
+            .emoji('◀')
+            .style(ButtonStyle::Secondary)
+            .disabled(index == 0),
+        CreateButton::new(format!("{}next", ctx_id))
+            .emoji('▶')
+            .style(ButtonStyle::Secondary)
+            .disabled(page.next_page_token.is_none()),
+    ]);
+
+    CreateReply::default()
+        .embed(embed)
+        .components(vec![buttons])
+        .ephemeral(ephemeral)
 }
 
-#[derive(Deserialize)]
-struct Statistics {
-    #[serde(rename = "viewCount")]
-    view_count: String,
-    #[serde(rename = "likeCount")]
-    like_count: Option<String>,
-    #[serde(rename = "commentCount")]
-    comment_count: Option<String>,
+#[poise::command(slash_command)]
+/// Searches YouTube.
+pub async fn yt_search(
+    ctx: Context<'_>,
+    #[description = "Search query"] query: String,
+    #[description = "Only search for these"] kind: Option<SearchKind>,
+    #[description = "Send the response directly to you?"] ephemeral: Option<bool>,
+) -> Result<(), Error> {
+    let ephemeral = bot::defer_based_on_ephemeral(ctx, ephemeral).await?;
+
+    let Some(client) = youtube_client(ctx, ephemeral).await? else {
+        return Ok(());
+    };
+    let kind = kind.map(SearchKind::api_name);
+    let ctx_id = ctx.id();
+
+    let first = match client.search(&query, kind, None, SEARCH_PAGE_SIZE).await {
+        Ok(page) => page,
+        Err(e) => return error_and_return(&ctx, ephemeral, e).await,
+    };
+    // Pages already fetched, as search requests are expensive in quota
+    let mut pages = vec![first];
+    let mut index = 0;
+    let reply = ctx
+        .send(search_page(&query, &pages[0], index, ctx_id, ephemeral))
+        .await?;
+
+    let prefix_len = ctx_id.to_string().len();
+    while let Some(press) = ComponentInteractionCollector::new(ctx)
+        .filter(move |press| press.data.custom_id.starts_with(&ctx_id.to_string()))
+        .timeout(PAGINATION_TIMEOUT)
+        .await
+    {
+        // Answer every press, otherwise Discord reports the interaction as failed
+        if press.user.id != ctx.author().id {
+            press
+                .create_response(
+                    ctx.serenity_context(),
+                    CreateInteractionResponse::Message(
+                        CreateInteractionResponseMessage::new()
+                            .content("These buttons belong to someone else's search.")
+                            .ephemeral(true),
+                    ),
+                )
+                .await?;
+            continue;
+        }
+
+        let moved = match &press.data.custom_id[prefix_len..] {
+            "prev" => {
+                index = index.saturating_sub(1);
+                true
+            }
+            "next" if index + 1 < pages.len() => {
+                index += 1;
+                true
+            }
+            "next" => match pages[index].next_page_token.clone() {
+                Some(token) => match client
+                    .search(&query, kind, Some(&token), SEARCH_PAGE_SIZE)
+                    .await
+                {
+                    Ok(page) => {
+                        pages.push(page);
+                        index += 1;
+                        true
+                    }
+                    Err(e) => {
+                        tracing::error!("YouTube search failed: {}", e);
+                        false
+                    }
+                },
+                None => false,
+            },
+            _ => false,
+        };
+        if !moved {
+            press
+                .create_response(
+                    ctx.serenity_context(),
+                    CreateInteractionResponse::Acknowledge,
+                )
+                .await?;
+            continue;
+        }
+
+        let CreateReply {
+            embeds, components, ..
+        } = search_page(&query, &pages[index], index, ctx_id, ephemeral);
+        press
+            .create_response(
+                ctx.serenity_context(),
+                CreateInteractionResponse::UpdateMessage(
+                    CreateInteractionResponseMessage::new()
+                        .embeds(embeds)
+                        .components(components.unwrap_or_default()),
+                ),
+            )
+            .await?;
+    }
+
+    // Navigation timed out, remove the now dead buttons
+    reply
+        .edit(
+            ctx,
+            search_page(&query, &pages[index], index, ctx_id, ephemeral).components(vec![]),
+        )
+        .await
+        .ok();
+
+    Ok(())
 }
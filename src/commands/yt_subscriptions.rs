@@ -0,0 +1,618 @@
+use std::{
+    collections::HashMap,
+    path::Path,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use chrono::Utc;
+use chrono_tz::America::Los_Angeles;
+use once_cell::sync::Lazy;
+use poise::CreateReply;
+use serde::{Deserialize, Serialize};
+use serenity::all::{
+    AutocompleteChoice, ChannelId, Color, CreateEmbed, CreateEmbedFooter, CreateMessage,
+    GuildChannel, Http,
+};
+use tokio::{fs, sync::RwLock};
+
+use super::youtube::{resolve_channel, youtube_client};
+use crate::{
+    Context, Error,
+    config::Config,
+    utils::{
+        bot::{self, can_manage_guild, error_and_return, error_text},
+        youtube::{self, YouTubeClient},
+    },
+};
+
+const SUBSCRIPTIONS_PATH: &str = "yt_subscriptions.json";
+const MAX_GUILD_SUBSCRIPTIONS: usize = 25;
+/// Video ids remembered per channel, more than a feed lists.
+const SEEN_LIMIT: usize = 50;
+/// How often the poller looks for channels that are due.
+const TICK: Duration = Duration::from_secs(60);
+/// API units one check of a channel may cost: the uploads fallback and the video details.
+const UNITS_PER_CHECK: u64 = 2;
+
+/// A guild's subscription to a YouTube channel.
+#[derive(Serialize, Deserialize, Clone)]
+struct Subscription {
+    guild_id: u64,
+    discord_channel_id: u64,
+    youtube_channel_id: String,
+    title: String,
+}
+
+/// What the poller knows about a subscribed YouTube channel.
+#[derive(Serialize, Deserialize, Default)]
+struct ChannelState {
+    /// Latest video ids already announced or skipped, newest last.
+    seen: Vec<String>,
+    /// False until the current videos were recorded, so they are not announced.
+    initialized: bool,
+    /// Scheduled streams and premieres to announce once they are live.
+    upcoming: Vec<String>,
+    last_polled: Option<SystemTime>,
+}
+
+/// API units used on one quota day, which starts at midnight Pacific time.
+#[derive(Serialize, Deserialize, Default)]
+struct QuotaUsage {
+    day: String,
+    used: u64,
+}
+
+impl QuotaUsage {
+    /// Spends `units` if they fit into `limit` for today.
+    fn spend(&mut self, units: u64, limit: u64) -> bool {
+        let today = Utc::now()
+            .with_timezone(&Los_Angeles)
+            .date_naive()
+            .to_string();
+        if self.day != today {
+            self.day = today;
+            self.used = 0;
+        }
+        if self.used + units > limit {
+            return false;
+        }
+        self.used += units;
+        true
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct YtSubscriptions {
+    subscriptions: Vec<Subscription>,
+    /// Poller state by YouTube channel id.
+    channels: HashMap<String, ChannelState>,
+    quota: QuotaUsage,
+}
+
+static SUBSCRIPTIONS: Lazy<RwLock<YtSubscriptions>> =
+    Lazy::new(|| RwLock::new(YtSubscriptions::default()));
+
+/// Load YouTube subscriptions from disk into memory at startup.
+pub async fn load_yt_subscriptions_from_file() -> Result<(), std::io::Error> {
+    if Path::new(SUBSCRIPTIONS_PATH).exists() {
+        let data = fs::read_to_string(SUBSCRIPTIONS_PATH).await?;
+        *SUBSCRIPTIONS.write().await = serde_json::from_str(&data)?;
+    }
+    Ok(())
+}
+
+/// Saves the subscriptions and poller state to disk as pretty JSON.
+async fn save_yt_subscriptions_to_file() {
+    let json = {
+        let subscriptions = SUBSCRIPTIONS.read().await;
+        serde_json::to_string_pretty(&*subscriptions)
+    };
+    match json {
+        Ok(json) => {
+            if let Err(e) = fs::write(SUBSCRIPTIONS_PATH, json).await {
+                tracing::warn!("Failed to save YouTube subscriptions: {}", e);
+            }
+        }
+        Err(e) => tracing::warn!("Failed to serialize YouTube subscriptions: {}", e),
+    }
+}
+
+/// Time between two checks of a channel. With many channels the interval
+/// grows so a full day of checks stays within the daily quota.
+fn poll_interval(minutes: u64, quota: u64, channels: usize) -> Duration {
+    let configured = Duration::from_secs(minutes.max(1) * 60);
+    let checks_per_day = quota / (channels as u64 * UNITS_PER_CHECK).max(1);
+    let quota_bound = Duration::from_secs(24 * 60 * 60 / checks_per_day.max(1));
+    configured.max(quota_bound)
+}
+
+/// A video to announce.
+struct Announcement {
+    video_id: String,
+    title: String,
+    live: bool,
+}
+
+fn announcement_message(channel_title: &str, announcement: &Announcement) -> CreateMessage {
+    let description = if announcement.live {
+        format!("🔴 **{}** is live now", channel_title)
+    } else {
+        format!("New upload from **{}**", channel_title)
+    };
+    let embed = CreateEmbed::default()
+        .title(&announcement.title)
+        .url(youtube::video_url(&announcement.video_id))
+        .description(description)
+        .image(format!(
+            "https://i.ytimg.com/vi/{}/hqdefault.jpg",
+            announcement.video_id
+        ))
+        .color(Color::RED);
+    CreateMessage::new().embed(embed)
+}
+
+/// The latest videos of a channel, oldest first, as (id, title).
+/// Uses the free feed, falling back to the uploads playlist.
+async fn latest_videos(
+    channel_id: &str,
+    client: Option<&YouTubeClient>,
+    quota_limit: u64,
+) -> Option<Vec<(String, String)>> {
+    match youtube::fetch_feed(channel_id).await {
+        Ok(entries) => {
+            return Some(
+                entries
+                    .into_iter()
+                    .rev()
+                    .map(|entry| (entry.video_id, entry.title))
+                    .collect(),
+            );
+        }
+        Err(e) => tracing::warn!("Failed to fetch feed of {}: {}", channel_id, e),
+    }
+
+    let client = client?;
+    let playlist = youtube::uploads_playlist_id(channel_id)?;
+    if !SUBSCRIPTIONS.write().await.quota.spend(1, quota_limit) {
+        return None;
+    }
+    match client.playlist_items(&playlist, None, 15).await {
+        Ok(page) => Some(
+            page.items
+                .iter()
+                .rev()
+                .filter_map(|item| {
+                    item.video_id()
+                        .map(|id| (id.to_string(), item.snippet.title.clone()))
+                })
+                .collect(),
+        ),
+        Err(e) => {
+            tracing::warn!("Failed to fetch uploads of {}: {}", channel_id, e);
+            None
+        }
+    }
+}
+
+/// Checks one channel and returns what to announce.
+async fn check_channel(
+    channel_id: &str,
+    client: Option<&YouTubeClient>,
+    quota_limit: u64,
+) -> Vec<Announcement> {
+    // Failed checks wait for the next interval too, so they do not eat the quota
+    SUBSCRIPTIONS
+        .write()
+        .await
+        .channels
+        .entry(channel_id.to_string())
+        .or_default()
+        .last_polled = Some(SystemTime::now());
+    let Some(videos) = latest_videos(channel_id, client, quota_limit).await else {
+        return Vec::new();
+    };
+
+    let (new, upcoming, initialized) = {
+        let subscriptions = SUBSCRIPTIONS.read().await;
+        let Some(state) = subscriptions.channels.get(channel_id) else {
+            return Vec::new();
+        };
+        let new: Vec<(String, String)> = videos
+            .into_iter()
+            .filter(|(id, _)| !state.seen.contains(id))
+            .collect();
+        (new, state.upcoming.clone(), state.initialized)
+    };
+    if new.is_empty() && upcoming.is_empty() {
+        if !initialized
+            && let Some(state) = SUBSCRIPTIONS.write().await.channels.get_mut(channel_id)
+        {
+            state.initialized = true;
+        }
+        return Vec::new();
+    }
+
+    let has_quota = match client {
+        Some(_) => SUBSCRIPTIONS.write().await.quota.spend(1, quota_limit),
+        None => false,
+    };
+    let (announcements, still_upcoming) = match client.filter(|_| has_quota) {
+        Some(client) => {
+            let ids: Vec<&str> = new
+                .iter()
+                .map(|(id, _)| id.as_str())
+                .chain(upcoming.iter().map(String::as_str))
+                .take(youtube::MAX_PAGE_SIZE as usize)
+                .collect();
+            let details = match client.videos(&ids).await {
+                Ok(details) => details,
+                Err(e) => {
+                    // Nothing is marked as seen, so the next check tries again
+                    tracing::warn!("Failed to fetch videos of {}: {}", channel_id, e);
+                    return Vec::new();
+                }
+            };
+
+            let mut announcements = Vec::new();
+            let mut still_upcoming = Vec::new();
+            for video in details {
+                match video.snippet.live_broadcast_content.as_deref() {
+                    Some("upcoming") => still_upcoming.push(video.id),
+                    status => announcements.push(Announcement {
+                        live: status == Some("live"),
+                        video_id: video.id,
+                        title: video.snippet.title,
+                    }),
+                }
+            }
+            (announcements, still_upcoming)
+        }
+        // Without quota every new video is announced as an upload
+        None => (
+            new.iter()
+                .map(|(video_id, title)| Announcement {
+                    video_id: video_id.clone(),
+                    title: title.clone(),
+                    live: false,
+                })
+                .collect(),
+            upcoming,
+        ),
+    };
+
+    let mut subscriptions = SUBSCRIPTIONS.write().await;
+    let Some(state) = subscriptions.channels.get_mut(channel_id) else {
+        return Vec::new();
+    };
+    state.seen.extend(new.into_iter().map(|(id, _)| id));
+    let excess = state.seen.len().saturating_sub(SEEN_LIMIT);
+    state.seen.drain(..excess);
+    state.upcoming = still_upcoming;
+    // The videos present when subscribing are not announced, but their scheduled
+    // streams are kept to be announced once live
+    if !state.initialized {
+        state.initialized = true;
+        return Vec::new();
+    }
+    announcements
+}
+
+/// Sends the announcements to every channel subscribed to `channel_id`.
+async fn announce(http: &Http, channel_id: &str, announcements: &[Announcement]) {
+    let targets: Vec<Subscription> = SUBSCRIPTIONS
+        .read()
+        .await
+        .subscriptions
+        .iter()
+        .filter(|s| s.youtube_channel_id == channel_id)
+        .cloned()
+        .collect();
+
+    for subscription in targets {
+        for announcement in announcements {
+            if let Err(e) = ChannelId::new(subscription.discord_channel_id)
+                .send_message(
+                    http,
+                    announcement_message(&subscription.title, announcement),
+                )
+                .await
+            {
+                tracing::warn!(
+                    "Failed to announce {} in {}: {}",
+                    announcement.video_id,
+                    subscription.discord_channel_id,
+                    e
+                );
+            }
+        }
+    }
+}
+
+/// Periodically checks the subscribed channels for uploads and live streams.
+pub async fn start_youtube_poller(ctx: serenity::all::Context, config: Arc<RwLock<Config>>) {
+    tokio::spawn(async move {
+        loop {
+            let (client, minutes, quota_limit) = {
+                let config = config.read().await;
+                (
+                    config
+                        .youtube_token
+                        .as_deref()
+                        .filter(|key| !key.is_empty())
+                        .map(YouTubeClient::new),
+                    config.youtube_poll_minutes,
+                    config.youtube_poll_quota,
+                )
+            };
+
+            let due: Vec<String> = {
+                let subscriptions = SUBSCRIPTIONS.read().await;
+                let mut channels: Vec<&String> = subscriptions
+                    .subscriptions
+                    .iter()
+                    .map(|s| &s.youtube_channel_id)
+                    .collect();
+                channels.sort();
+                channels.dedup();
+
+                let interval = poll_interval(minutes, quota_limit, channels.len());
+                channels
+                    .into_iter()
+                    .filter(|id| {
+                        subscriptions
+                            .channels
+                            .get(*id)
+                            .and_then(|state| state.last_polled)
+                            .is_none_or(|last| {
+                                last.elapsed().is_ok_and(|elapsed| elapsed >= interval)
+                            })
+                    })
+                    .cloned()
+                    .collect()
+            };
+
+            for channel_id in &due {
+                let announcements = check_channel(channel_id, client.as_ref(), quota_limit).await;
+                announce(&ctx.http, channel_id, &announcements).await;
+            }
+            if !due.is_empty() {
+                save_yt_subscriptions_to_file().await;
+            }
+
+            tokio::time::sleep(TICK).await;
+        }
+    });
+}
+
+/// Suggests the current server's subscriptions.
+async fn autocomplete_subscription(ctx: Context<'_>, partial: &str) -> Vec<AutocompleteChoice> {
+    let Some(guild_id) = ctx.guild_id() else {
+        return Vec::new();
+    };
+    let partial = partial.to_lowercase();
+    let subscriptions = SUBSCRIPTIONS.read().await;
+    let mut matching: Vec<&Subscription> = subscriptions
+        .subscriptions
+        .iter()
+        .filter(|s| s.guild_id == guild_id.get())
+        .filter(|s| s.title.to_lowercase().contains(&partial))
+        .collect();
+    // A channel posted in several places is suggested once
+    matching.sort_by(|a, b| a.youtube_channel_id.cmp(&b.youtube_channel_id));
+    matching.dedup_by(|a, b| a.youtube_channel_id == b.youtube_channel_id);
+    let mut choices: Vec<AutocompleteChoice> = matching
+        .into_iter()
+        .map(|s| AutocompleteChoice::new(s.title.clone(), s.youtube_channel_id.clone()))
+        .collect();
+    choices.truncate(25);
+    choices
+}
+
+#[poise::command(slash_command, guild_only)]
+/// Posts a YouTube channel's uploads and live streams in a channel of this server.
+pub async fn yt_subscribe(
+    ctx: Context<'_>,
+    #[description = "YouTube channel URL, @handle, id or name"] channel: String,
+    #[description = "Where to post new videos"]
+    #[channel_types("Text", "News")]
+    discord_channel: GuildChannel,
+    #[description = "Send the response directly to you?"] ephemeral: Option<bool>,
+) -> Result<(), Error> {
+    let ephemeral = bot::defer_based_on_ephemeral(ctx, ephemeral).await?;
+
+    if !can_manage_guild(ctx).await? {
+        error_text(
+            &ctx,
+            ephemeral,
+            "You need the Manage Server permission to subscribe to channels",
+        )
+        .await;
+        return Ok(());
+    }
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+    if discord_channel.guild_id != guild_id {
+        error_text(&ctx, ephemeral, "Please pick a channel of this server").await;
+        return Ok(());
+    }
+
+    let Some(client) = youtube_client(ctx, ephemeral).await? else {
+        return Ok(());
+    };
+    let channel = match resolve_channel(&client, &channel).await {
+        Ok(Some(channel)) => channel,
+        Ok(None) => {
+            error_text(&ctx, ephemeral, "YouTube channel not found").await;
+            return Ok(());
+        }
+        Err(e) => return error_and_return(&ctx, ephemeral, e).await,
+    };
+
+    {
+        let mut subscriptions = SUBSCRIPTIONS.write().await;
+        let guild_subscriptions: Vec<&Subscription> = subscriptions
+            .subscriptions
+            .iter()
+            .filter(|s| s.guild_id == guild_id.get())
+            .collect();
+        let error = if guild_subscriptions.iter().any(|s| {
+            s.youtube_channel_id == channel.id && s.discord_channel_id == discord_channel.id.get()
+        }) {
+            Some(format!(
+                "{} is already posted in <#{}>",
+                channel.snippet.title, discord_channel.id
+            ))
+        } else if guild_subscriptions.len() >= MAX_GUILD_SUBSCRIPTIONS {
+            Some(format!(
+                "This server already has {} subscriptions",
+                MAX_GUILD_SUBSCRIPTIONS
+            ))
+        } else {
+            None
+        };
+        if let Some(error) = error {
+            drop(subscriptions);
+            error_text(&ctx, ephemeral, &error).await;
+            return Ok(());
+        }
+
+        subscriptions.subscriptions.push(Subscription {
+            guild_id: guild_id.get(),
+            discord_channel_id: discord_channel.id.get(),
+            youtube_channel_id: channel.id.clone(),
+            title: channel.snippet.title.clone(),
+        });
+        subscriptions
+            .channels
+            .entry(channel.id.clone())
+            .or_default();
+    }
+    save_yt_subscriptions_to_file().await;
+
+    let mut embed = CreateEmbed::default()
+        .title(format!("Subscribed to {}", channel.snippet.title))
+        .url(youtube::channel_url(&channel.id))
+        .description(format!(
+            "New uploads and live streams will be posted in <#{}>",
+            discord_channel.id
+        ))
+        .footer(CreateEmbedFooter::new(
+            "Videos already published are not posted",
+        ))
+        .color(Color::RED);
+    if let Some(thumbnail) = channel.snippet.thumbnails.best() {
+        embed = embed.thumbnail(thumbnail);
+    }
+    ctx.send(CreateReply::default().embed(embed).ephemeral(ephemeral))
+        .await?;
+
+    Ok(())
+}
+
+#[poise::command(slash_command, guild_only)]
+/// Stops posting a YouTube channel's videos in this server.
+pub async fn yt_unsubscribe(
+    ctx: Context<'_>,
+    #[description = "Subscribed YouTube channel"]
+    #[autocomplete = "autocomplete_subscription"]
+    channel: String,
+    #[description = "Only stop posting in this channel"]
+    #[channel_types("Text", "News")]
+    discord_channel: Option<GuildChannel>,
+    #[description = "Send the response directly to you?"] ephemeral: Option<bool>,
+) -> Result<(), Error> {
+    let ephemeral = bot::defer_based_on_ephemeral(ctx, ephemeral).await?;
+
+    if !can_manage_guild(ctx).await? {
+        error_text(
+            &ctx,
+            ephemeral,
+            "You need the Manage Server permission to unsubscribe from channels",
+        )
+        .await;
+        return Ok(());
+    }
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+
+    let removed = {
+        let mut subscriptions = SUBSCRIPTIONS.write().await;
+        let before = subscriptions.subscriptions.len();
+        subscriptions.subscriptions.retain(|s| {
+            !(s.guild_id == guild_id.get()
+                && s.youtube_channel_id == channel
+                && discord_channel
+                    .as_ref()
+                    .is_none_or(|c| c.id.get() == s.discord_channel_id))
+        });
+        let removed = before - subscriptions.subscriptions.len();
+
+        // Forget the poller state of channels nobody subscribes to anymore
+        if !subscriptions
+            .subscriptions
+            .iter()
+            .any(|s| s.youtube_channel_id == channel)
+        {
+            subscriptions.channels.remove(&channel);
+        }
+        removed
+    };
+    if removed == 0 {
+        error_text(&ctx, ephemeral, "No such subscription").await;
+        return Ok(());
+    }
+    save_yt_subscriptions_to_file().await;
+
+    ctx.send(
+        CreateReply::default()
+            .content(format!("Removed {} subscription(s)", removed))
+            .ephemeral(ephemeral),
+    )
+    .await?;
+
+    Ok(())
+}
+
+#[poise::command(slash_command, guild_only)]
+/// Lists this server's YouTube subscriptions.
+pub async fn yt_subscriptions(
+    ctx: Context<'_>,
+    #[description = "Send the response directly to you?"] ephemeral: Option<bool>,
+) -> Result<(), Error> {
+    let ephemeral = bot::defer_based_on_ephemeral(ctx, ephemeral).await?;
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+
+    let lines: Vec<String> = SUBSCRIPTIONS
+        .read()
+        .await
+        .subscriptions
+        .iter()
+        .filter(|s| s.guild_id == guild_id.get())
+        .map(|s| {
+            format!(
+                "[{}]({}) → <#{}>",
+                s.title,
+                youtube::channel_url(&s.youtube_channel_id),
+                s.discord_channel_id
+            )
+        })
+        .collect();
+    if lines.is_empty() {
+        error_text(&ctx, ephemeral, "This server has no YouTube subscriptions").await;
+        return Ok(());
+    }
+
+    let embed = CreateEmbed::default()
+        .title("YouTube subscriptions")
+        .description(lines.join("\n"))
+        .color(Color::RED);
+    ctx.send(CreateReply::default().embed(embed).ephemeral(ephemeral))
+        .await?;
+
+    Ok(())
+}
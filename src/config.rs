@@ -1,4 +1,8 @@
-use std::{io::Write, path::Path};
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::Write,
+    path::Path,
+};
 
 use serde::{Deserialize, Serialize};
 use tokio::io::{AsyncBufReadExt, BufReader};
@@ -11,11 +15,103 @@ pub struct Config {
     pub youtube_token: Option<String>,
     pub youtube_whitelist_active: bool,
     pub youtube_whitelist: Vec<String>,
+    /// Minutes between two checks of a subscribed YouTube channel.
+    pub youtube_poll_minutes: u64,
+    /// YouTube API units per day the upload poller may use. Polling slows down to stay within it.
+    pub youtube_poll_quota: u64,
+    /// Site root for video lookups without an API key, e.g. a proxy or Invidious style mirror.
+    pub youtube_public_url: String,
+    /// Personal access token for the GitHub API, raising the rate limit to 5000 requests per hour.
+    pub github_token: Option<String>,
+    /// Seconds between two checks of a watched GitHub repository.
+    pub github_poll_seconds: u64,
+    /// Address like `0.0.0.0:8080` to receive GitHub webhooks on at `/github`. Repositories delivering webhooks are not polled.
+    pub github_webhook_addr: Option<String>,
+    /// Secret the GitHub webhooks are signed with, required by the receiver.
+    pub github_webhook_secret: Option<String>,
     pub admin_list: Vec<String>,
     pub deepseek_whitelist_active: bool,
     pub deepseek_whitelist: Vec<String>,
     pub ping_whitelist_active: bool,
     pub ping_whitelist: Vec<String>,
+    /// Role ids per guild id that may create, edit or delete the guild's shared aliases.
+    pub guild_alias_roles: HashMap<String, Vec<String>>,
+    /// Approximate token budget of the history sent with each deepseek prompt.
+    pub deepseek_history_tokens: usize,
+    /// LLM providers by name. Models are addressed as `provider/model`.
+    pub llm_providers: BTreeMap<String, LlmProvider>,
+    /// Model used by `/ai` when none is given, as `provider/model`.
+    pub default_model: String,
+    /// Token budgets enforced before AI requests. Admins are exempt.
+    pub ai_budgets: AiBudgets,
+    /// Named personas selectable on the AI commands.
+    pub personas: BTreeMap<String, Persona>,
+    /// Default persona name per guild id.
+    pub guild_personas: HashMap<String, String>,
+}
+
+/// A system prompt with generation settings for the AI commands.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Persona {
+    pub system_prompt: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    /// Model as `provider/model`, overriding the command's default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+}
+
+/// Token limits per UTC day and calendar month. None means unlimited.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct AiBudgets {
+    pub user_daily: Option<u64>,
+    pub user_monthly: Option<u64>,
+    pub guild_daily: Option<u64>,
+    pub guild_monthly: Option<u64>,
+}
+
+/// Price of a model in USD per million tokens.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default)]
+pub struct ModelPrice {
+    pub prompt: f64,
+    pub completion: f64,
+}
+
+/// The API flavour spoken by an LLM provider.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ProviderKind {
+    /// Any OpenAI compatible chat completions API.
+    OpenAi,
+    /// A local or remote Ollama server.
+    Ollama,
+    /// DeepSeek, falls back to `deepseek_token` when no key is set.
+    DeepSeek,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LlmProvider {
+    pub kind: ProviderKind,
+    /// Overrides the provider's default API base URL.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
+    /// Models that may be selected for this provider.
+    #[serde(default)]
+    pub models: Vec<String>,
+    /// Models that do not support tool calling.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub models_without_tools: Vec<String>,
+    /// Models that accept images.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub vision_models: Vec<String>,
+    /// Prices per model name, used for cost estimates.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub prices: BTreeMap<String, ModelPrice>,
 }
 
 impl Default for Config {
@@ -29,8 +125,48 @@ impl Default for Config {
             ping_whitelist_active: false,
             admin_list: vec!["921066050009833572".into()],
             youtube_whitelist: vec!["921066050009833572".into()],
+            youtube_poll_minutes: 10,
+            youtube_poll_quota: 2000,
+            youtube_public_url: "https://www.youtube.com".into(),
+            github_token: None,
+            github_poll_seconds: 60,
+            github_webhook_addr: None,
+            github_webhook_secret: None,
             deepseek_whitelist: vec!["921066050009833572".into()],
             ping_whitelist: vec!["921066050009833572".into()],
+            guild_alias_roles: HashMap::new(),
+            deepseek_history_tokens: 4000,
+            llm_providers: BTreeMap::from([(
+                "deepseek".into(),
+                LlmProvider {
+                    kind: ProviderKind::DeepSeek,
+                    base_url: None,
+                    api_key: None,
+                    models: vec!["deepseek-chat".into(), "deepseek-reasoner".into()],
+                    models_without_tools: vec!["deepseek-reasoner".into()],
+                    vision_models: Vec::new(),
+                    prices: BTreeMap::from([
+                        (
+                            "deepseek-chat".into(),
+                            ModelPrice {
+                                prompt: 0.27,
+                                completion: 1.10,
+                            },
+                        ),
+                        (
+                            "deepseek-reasoner".into(),
+                            ModelPrice {
+                                prompt: 0.55,
+                                completion: 2.19,
+                            },
+                        ),
+                    ]),
+                },
+            )]),
+            default_model: "deepseek/deepseek-chat".into(),
+            ai_budgets: AiBudgets::default(),
+            personas: BTreeMap::new(),
+            guild_personas: HashMap::new(),
         }
     }
 }
@@ -5,7 +5,7 @@ mod utils;
 use std::{sync::Arc, vec};
 
 use poise::FrameworkError;
-use serenity::all::{CacheHttp, ClientBuilder, GatewayIntents, UserId};
+use serenity::all::{CacheHttp, ClientBuilder, FullEvent, GatewayIntents, UserId};
 use tokio::sync::RwLock;
 use tracing::{error, info, warn};
 
@@ -65,8 +65,24 @@ async fn main() -> Result<(), Error> {
             commands::morse(),
             commands::time(),
             commands::deepseek(),
+            commands::deepseek_reset(),
+            commands::ai(),
+            commands::ask_ai_message(),
+            commands::ai_usage(),
+            commands::ai_usage_report(),
+            commands::persona_set(),
+            commands::persona_delete(),
+            commands::persona_default(),
+            commands::personas(),
+            commands::summarize(),
             commands::reload_settings(),
             commands::yt_vid(),
+            commands::yt_playlist(),
+            commands::yt_channel(),
+            commands::yt_search(),
+            commands::yt_subscribe(),
+            commands::yt_unsubscribe(),
+            commands::yt_subscriptions(),
             commands::ping(),
             commands::dump_ping(),
             commands::cat(),
@@ -75,11 +91,20 @@ async fn main() -> Result<(), Error> {
             commands::reminder(),
             commands::reminders(),
             commands::delete_reminder(),
+            commands::reminder_edit(),
             commands::github(),
+            commands::github_watch(),
+            commands::github_unwatch(),
+            commands::github_watches(),
+            commands::unfurl(),
             commands::translate(),
             commands::print(),
             commands::list_alias(),
             commands::delete_alias(),
+            commands::alias_export(),
+            commands::alias_import(),
+            commands::alias_history(),
+            commands::alias_restore(),
         ],
         prefix_options: poise::PrefixFrameworkOptions {
             prefix: None,
@@ -106,9 +131,37 @@ async fn main() -> Result<(), Error> {
                 );
             })
         },
-        event_handler: |_ctx, event, _framework, _data| {
+        event_handler: |ctx, event, _framework, data| {
             Box::pin(async move {
                 info!("Event: {:?}", event.snake_case_name());
+                match event {
+                    // The handlers don't depend on each other, one failing must not stop the other
+                    FullEvent::InteractionCreate { interaction } => {
+                        let (reminder, unfurl) = tokio::join!(
+                            commands::handle_reminder_interaction(ctx, interaction),
+                            commands::handle_unfurl_interaction(ctx, interaction),
+                        );
+                        if let Err(e) = reminder {
+                            error!("Reminder interaction failed: {}", e);
+                        }
+                        if let Err(e) = unfurl {
+                            error!("Unfurl interaction failed: {}", e);
+                        }
+                    }
+                    FullEvent::Message { new_message } => {
+                        let (thread, unfurl) = tokio::join!(
+                            commands::handle_deepseek_thread_message(ctx, data, new_message),
+                            commands::handle_unfurl_message(ctx, data, new_message),
+                        );
+                        if let Err(e) = thread {
+                            error!("AI thread message failed: {}", e);
+                        }
+                        if let Err(e) = unfurl {
+                            error!("Unfurling message failed: {}", e);
+                        }
+                    }
+                    _ => {}
+                }
                 Ok(())
             })
         },
@@ -128,12 +181,12 @@ async fn main() -> Result<(), Error> {
 
                 // Notify admins of startup
                 for id_str in &cfg_lock.read().await.admin_list {
-                    if let Ok(id) = id_str.parse::<u64>() {
-                        if let Ok(dm) = UserId::new(id).create_dm_channel(ctx.http()).await {
-                            let _ = dm
-                                .say(ctx.http(), format!("Bot started: {}", git_hash))
-                                .await;
-                        }
+                    if let Ok(id) = id_str.parse::<u64>()
+                        && let Ok(dm) = UserId::new(id).create_dm_channel(ctx.http()).await
+                    {
+                        let _ = dm
+                            .say(ctx.http(), format!("Bot started: {}", git_hash))
+                            .await;
                     }
                 }
 
@@ -142,14 +195,36 @@ async fn main() -> Result<(), Error> {
                     error!("Failed to load saved messages: {:?}", e);
                 }
 
+                if let Err(e) = crate::commands::load_sessions_from_file().await {
+                    error!("Failed to load deepseek sessions: {:?}", e);
+                }
+                if let Err(e) = crate::commands::load_usage_from_file().await {
+                    error!("Failed to load AI usage: {:?}", e);
+                }
+
+                if let Err(e) = crate::commands::load_yt_subscriptions_from_file().await {
+                    error!("Failed to load YouTube subscriptions: {:?}", e);
+                }
+                if let Err(e) = crate::commands::load_github_watches_from_file().await {
+                    error!("Failed to load GitHub watches: {:?}", e);
+                }
+                if let Err(e) = crate::commands::load_unfurl_settings_from_file().await {
+                    error!("Failed to load unfurl settings: {:?}", e);
+                }
+
                 commands::start_reminder_loop(ctx.clone()).await;
+                commands::start_alias_usage_flush().await;
+                commands::start_youtube_poller(ctx.clone(), cfg_lock.clone()).await;
+                commands::start_github_webhook_receiver(ctx.clone(), cfg_lock.clone()).await;
+                commands::start_github_poller(ctx.clone(), cfg_lock.clone()).await;
                 Ok(Data { config: cfg_lock })
             })
         })
         .build();
 
     // Launch client
-    let intents = GatewayIntents::non_privileged();
+    // Message content is needed to summarize channels and continue conversation threads
+    let intents = GatewayIntents::non_privileged() | GatewayIntents::MESSAGE_CONTENT;
     ClientBuilder::new(token, intents)
         .framework(framework)
         .await?
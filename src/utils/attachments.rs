@@ -0,0 +1,136 @@
+use base64::{Engine, prelude::BASE64_STANDARD};
+use serenity::all::Attachment;
+
+use crate::utils::llm::ImageInput;
+
+/// Images larger than this are rejected.
+const MAX_IMAGE_BYTES: u32 = 5 * 1024 * 1024;
+const MAX_IMAGES: usize = 4;
+/// Text files larger than this are rejected.
+const MAX_TEXT_BYTES: u32 = 256 * 1024;
+/// Inlined text is cut to this many characters over all files.
+const MAX_TEXT_CHARS: usize = 20_000;
+
+const IMAGE_TYPES: [&str; 4] = ["image/png", "image/jpeg", "image/gif", "image/webp"];
+/// Extensions of files that are inlined as text, besides `text/*` content types.
+const TEXT_EXTENSIONS: [&str; 32] = [
+    "txt", "md", "log", "csv", "json", "toml", "yaml", "yml", "xml", "html", "css", "js", "ts",
+    "jsx", "tsx", "rs", "py", "java", "kt", "c", "h", "cpp", "hpp", "cs", "go", "rb", "php", "sh",
+    "sql", "lua", "ini", "cfg",
+];
+
+/// Attachments converted into prompt input.
+#[derive(Default)]
+pub struct PromptAttachments {
+    /// Text files as code blocks, to be appended to the prompt.
+    pub text: String,
+    pub images: Vec<ImageInput>,
+    /// Why attachments were left out, one note per file.
+    pub skipped: Vec<String>,
+}
+
+impl PromptAttachments {
+    /// True if nothing of the attachments made it into the prompt.
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty() && self.images.is_empty()
+    }
+}
+
+fn extension(filename: &str) -> Option<String> {
+    filename
+        .rsplit_once('.')
+        .map(|(_, extension)| extension.to_lowercase())
+}
+
+fn is_text(attachment: &Attachment) -> bool {
+    attachment
+        .content_type
+        .as_deref()
+        .is_some_and(|t| t.starts_with("text/") || t.starts_with("application/json"))
+        || extension(&attachment.filename).is_some_and(|e| TEXT_EXTENSIONS.contains(&e.as_str()))
+}
+
+fn image_type(attachment: &Attachment) -> Option<&'static str> {
+    let content_type = attachment.content_type.as_deref()?;
+    IMAGE_TYPES
+        .into_iter()
+        .find(|t| content_type.starts_with(t))
+}
+
+/// Downloads the attachments, inlining text files and encoding images.
+/// Attachments that are unsupported or too big are skipped with a note,
+/// failing downloads are returned as an error message.
+pub async fn read_attachments(attachments: &[Attachment]) -> Result<PromptAttachments, String> {
+    let mut input = PromptAttachments::default();
+    let mut text_chars = 0;
+
+    for attachment in attachments {
+        if let Some(mime_type) = image_type(attachment) {
+            if input.images.len() >= MAX_IMAGES {
+                input.skipped.push(format!(
+                    "`{}` was skipped, at most {} images are supported",
+                    attachment.filename, MAX_IMAGES
+                ));
+                continue;
+            }
+            if attachment.size > MAX_IMAGE_BYTES {
+                input.skipped.push(format!(
+                    "`{}` is too big, images may have at most {} MB",
+                    attachment.filename,
+                    MAX_IMAGE_BYTES / 1024 / 1024
+                ));
+                continue;
+            }
+            let bytes = attachment
+                .download()
+                .await
+                .map_err(|e| format!("Failed to download `{}`: {}", attachment.filename, e))?;
+            input.images.push(ImageInput {
+                mime_type: mime_type.to_string(),
+                data: BASE64_STANDARD.encode(bytes),
+            });
+        } else if is_text(attachment) {
+            if attachment.size > MAX_TEXT_BYTES {
+                input.skipped.push(format!(
+                    "`{}` is too big, text files may have at most {} KB",
+                    attachment.filename,
+                    MAX_TEXT_BYTES / 1024
+                ));
+                continue;
+            }
+            let remaining = MAX_TEXT_CHARS.saturating_sub(text_chars);
+            if remaining == 0 {
+                input.skipped.push(format!(
+                    "`{}` was skipped, text files may have at most {} characters together",
+                    attachment.filename, MAX_TEXT_CHARS
+                ));
+                continue;
+            }
+            let bytes = attachment
+                .download()
+                .await
+                .map_err(|e| format!("Failed to download `{}`: {}", attachment.filename, e))?;
+            let content = String::from_utf8_lossy(&bytes);
+
+            let truncated = content.chars().count() > remaining;
+            let mut content: String = content.chars().take(remaining).collect();
+            text_chars += content.chars().count();
+            if truncated {
+                content.push_str("\n[truncated]");
+            }
+            input.text.push_str(&format!(
+                "\n\nFile `{}`:\n```{}\n{}\n```",
+                attachment.filename,
+                extension(&attachment.filename).unwrap_or_default(),
+                content.trim_end()
+            ));
+        } else {
+            input.skipped.push(format!(
+                "`{}` was skipped, only images and text files are supported",
+                attachment.filename
+            ));
+        }
+    }
+
+    Ok(input)
+}
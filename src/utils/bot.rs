@@ -21,6 +21,12 @@ pub async fn defer_based_on_ephemeral(
     Ok(ephemeral)
 }
 
+/// Returns true if the whitelist is inactive or contains the user.
+/// Usable outside of commands, e.g. from event handlers.
+pub fn user_in_whitelist(is_active: bool, list: &[String], user_id: u64) -> bool {
+    !is_active || list.contains(&user_id.to_string())
+}
+
 /// Generic whitelist checker: returns true if feature disabled or user in whitelist.
 pub async fn check_whitelist<F, L>(ctx: Context<'_>, is_active: F, list: L) -> Result<bool, Error>
 where
@@ -29,17 +35,29 @@ where
 {
     let data = ctx.data();
     let cfg = data.config.read().await;
-    if !is_active(&cfg) {
-        return Ok(true);
-    }
-    let user = ctx.author().id.to_string();
-    Ok(list(&cfg).contains(&user))
+    Ok(user_in_whitelist(
+        is_active(&cfg),
+        list(&cfg),
+        ctx.author().id.get(),
+    ))
 }
 
 pub async fn is_admin(ctx: Context<'_>) -> Result<bool, Error> {
     check_whitelist(ctx, |_| true, |c| &c.admin_list).await
 }
 
+/// Bot admins and members allowed to manage the server may change its settings.
+pub async fn can_manage_guild(ctx: Context<'_>) -> Result<bool, Error> {
+    if is_admin(ctx).await? {
+        return Ok(true);
+    }
+    Ok(ctx.author_member().await.is_some_and(|member| {
+        member
+            .permissions
+            .is_some_and(|permissions| permissions.manage_guild())
+    }))
+}
+
 pub async fn is_deepseek(ctx: Context<'_>) -> Result<bool, Error> {
     check_whitelist(
         ctx,
@@ -82,10 +100,6 @@ where
     Err(Box::new(e))
 }
 
-pub async fn error(ctx: &Context<'_>, ephemeral: bool) {
-    send_error(ctx, ephemeral, "AN ERROR OCCURRED", None).await;
-}
-
 pub async fn error_and_return_text<E>(
     ctx: &Context<'_>,
     ephemeral: bool,
@@ -0,0 +1,243 @@
+use std::io::Write;
+
+use flate2::{Compression, write::ZlibEncoder};
+
+/// Discord's dark embed background, so charts blend in.
+const BACKGROUND: [u8; 3] = [0x2b, 0x2d, 0x31];
+const GRID: [u8; 3] = [0x4e, 0x50, 0x58];
+const PADDING: u32 = 12;
+/// Horizontal grid lines, not counting the baseline.
+const GRID_LINES: u32 = 4;
+
+/// One set of values drawn in the same color.
+pub struct Series<'a> {
+    pub values: &'a [f64],
+    pub color: [u8; 3],
+}
+
+/// An RGB image drawn in memory.
+struct Canvas {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    fn new(width: u32, height: u32, color: [u8; 3]) -> Self {
+        Self {
+            width,
+            height,
+            pixels: color.repeat((width * height) as usize),
+        }
+    }
+
+    /// Fills a rectangle, clipped to the canvas.
+    fn fill(&mut self, x: u32, y: u32, width: u32, height: u32, color: [u8; 3]) {
+        for row in y..(y + height).min(self.height) {
+            for column in x..(x + width).min(self.width) {
+                let i = ((row * self.width + column) * 3) as usize;
+                self.pixels[i..i + 3].copy_from_slice(&color);
+            }
+        }
+    }
+
+    fn into_png(self) -> Vec<u8> {
+        encode_png(self.width, self.height, &self.pixels)
+    }
+}
+
+/// Draws grouped bars, one group per slot with a bar for every series,
+/// scaled so the largest value reaches the top grid line.
+pub fn bar_chart(series: &[Series], width: u32, height: u32) -> Vec<u8> {
+    let mut canvas = Canvas::new(width, height, BACKGROUND);
+    let plot_width = width.saturating_sub(2 * PADDING);
+    let plot_height = height.saturating_sub(2 * PADDING);
+    let baseline = PADDING + plot_height;
+
+    for line in 0..=GRID_LINES {
+        let y = baseline - plot_height * line / GRID_LINES;
+        canvas.fill(PADDING, y, plot_width, 1, GRID);
+    }
+
+    let slots = series.iter().map(|s| s.values.len()).max().unwrap_or(0) as u32;
+    let max = series
+        .iter()
+        .flat_map(|s| s.values.iter().copied())
+        .fold(0.0, f64::max);
+    if slots == 0 || series.is_empty() || max <= 0.0 {
+        return canvas.into_png();
+    }
+
+    let slot_width = plot_width / slots;
+    // Leave a gap between groups once they are wide enough to show one
+    let gap = if slot_width >= 4 { slot_width / 4 } else { 0 };
+    let bar_width = ((slot_width - gap) / series.len() as u32).max(1);
+    for (index, s) in series.iter().enumerate() {
+        for (slot, &value) in s.values.iter().enumerate() {
+            let bar_height = (value.max(0.0) / max * plot_height as f64).round() as u32;
+            if bar_height == 0 {
+                continue;
+            }
+            let x = PADDING + slot as u32 * slot_width + gap / 2 + index as u32 * bar_width;
+            canvas.fill(x, baseline - bar_height, bar_width, bar_height, s.color);
+        }
+    }
+    canvas.into_png()
+}
+
+fn write_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = out.len();
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+    let crc = crc32fast::hash(&out[start..]);
+    out.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// Encodes 8-bit RGB pixels, row by row, as a PNG file.
+pub fn encode_png(width: u32, height: u32, rgb: &[u8]) -> Vec<u8> {
+    let stride = width as usize * 3;
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    for row in rgb.chunks(stride).take(height as usize) {
+        // Every scanline starts with its filter type, 0 meaning unfiltered
+        let _ = encoder.write_all(&[0]);
+        let _ = encoder.write_all(row);
+    }
+    let data = encoder.finish().unwrap_or_default();
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    // Bit depth 8, truecolor, default compression, filtering and no interlacing
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+    write_chunk(&mut png, b"IHDR", &header);
+    write_chunk(&mut png, b"IDAT", &data);
+    write_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use flate2::read::ZlibDecoder;
+
+    use super::*;
+
+    /// Splits a PNG into its chunks, checking the signature and every CRC,
+    /// and returns the IHDR size with the decompressed pixel data.
+    fn decode(png: &[u8]) -> (u32, u32, Vec<u8>) {
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        let mut rest = &png[8..];
+        let mut kinds = Vec::new();
+        let (mut size, mut data) = ((0, 0), Vec::new());
+        while !rest.is_empty() {
+            let length = u32::from_be_bytes(rest[..4].try_into().unwrap()) as usize;
+            let (kind, body) = (&rest[4..8], &rest[8..8 + length]);
+            let crc = u32::from_be_bytes(rest[8 + length..12 + length].try_into().unwrap());
+            assert_eq!(
+                crc,
+                crc32fast::hash(&rest[4..8 + length]),
+                "CRC of {:?}",
+                kind
+            );
+            match kind {
+                b"IHDR" => {
+                    size = (
+                        u32::from_be_bytes(body[..4].try_into().unwrap()),
+                        u32::from_be_bytes(body[4..8].try_into().unwrap()),
+                    );
+                    assert_eq!(&body[8..], [8, 2, 0, 0, 0]);
+                }
+                b"IDAT" => data.extend_from_slice(body),
+                _ => {}
+            }
+            kinds.push(kind.to_vec());
+            rest = &rest[12 + length..];
+        }
+        assert_eq!(
+            kinds,
+            [b"IHDR".to_vec(), b"IDAT".to_vec(), b"IEND".to_vec()]
+        );
+
+        let mut pixels = Vec::new();
+        ZlibDecoder::new(&data[..])
+            .read_to_end(&mut pixels)
+            .unwrap();
+        (size.0, size.1, pixels)
+    }
+
+    /// The color at `(x, y)` in decompressed scanlines.
+    fn pixel(pixels: &[u8], width: u32, x: u32, y: u32) -> [u8; 3] {
+        let i = (y * (width * 3 + 1) + 1 + x * 3) as usize;
+        pixels[i..i + 3].try_into().unwrap()
+    }
+
+    #[test]
+    fn encodes_valid_png() {
+        let rgb: Vec<u8> = (0..2 * 3 * 3).map(|i| i as u8).collect();
+        let (width, height, pixels) = decode(&encode_png(3, 2, &rgb));
+        assert_eq!((width, height), (3, 2));
+        assert_eq!(pixels.len(), 2 * (1 + 3 * 3));
+        assert_eq!(pixel(&pixels, 3, 0, 0), [0, 1, 2]);
+        assert_eq!(pixel(&pixels, 3, 2, 1), [15, 16, 17]);
+    }
+
+    #[test]
+    fn draws_bars_in_their_colors() {
+        let values = [0.0, 4.0];
+        let png = bar_chart(
+            &[Series {
+                values: &values,
+                color: [255, 0, 0],
+            }],
+            64,
+            48,
+        );
+        let (width, height, pixels) = decode(&png);
+        assert_eq!((width, height), (64, 48));
+        // The largest bar reaches the top grid line, the zero one is not drawn
+        let right = PADDING + (64 - 2 * PADDING) * 3 / 4;
+        let left = PADDING + (64 - 2 * PADDING) / 4;
+        assert_eq!(pixel(&pixels, 64, right, PADDING + 1), [255, 0, 0]);
+        assert_eq!(pixel(&pixels, 64, left, PADDING + 1), BACKGROUND);
+    }
+
+    #[test]
+    fn empty_and_zero_series_give_blank_charts() {
+        let zeros = [0.0; 12];
+        for series in [
+            vec![],
+            vec![Series {
+                values: &[],
+                color: [255, 0, 0],
+            }],
+            vec![Series {
+                values: &zeros,
+                color: [255, 0, 0],
+            }],
+        ] {
+            let (width, height, pixels) = decode(&bar_chart(&series, 40, 30));
+            assert_eq!((width, height), (40, 30));
+            assert_eq!(pixels.len(), 30 * (1 + 40 * 3));
+            assert!(
+                pixels
+                    .chunks(40 * 3 + 1)
+                    .all(|row| row[1..].chunks(3).all(|p| p == BACKGROUND || p == GRID))
+            );
+        }
+        // Too small for the padding, and more slots than pixels
+        decode(&bar_chart(&[], 10, 10));
+        let many = [1.0; 100];
+        decode(&bar_chart(
+            &[Series {
+                values: &many,
+                color: [255, 0, 0],
+            }],
+            30,
+            30,
+        ));
+    }
+}
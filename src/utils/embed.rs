@@ -0,0 +1,329 @@
+use serde::{Deserialize, Serialize};
+use serenity::all::{Colour, CreateEmbed, CreateEmbedAuthor, CreateEmbedFooter, Timestamp};
+
+use crate::utils::{
+    markdown::truncate,
+    template::{self, TemplateVars},
+};
+
+const MAX_EMBEDS: usize = 10;
+const MAX_TITLE: usize = 256;
+const MAX_DESCRIPTION: usize = 4096;
+const MAX_FIELDS: usize = 25;
+const MAX_FIELD_NAME: usize = 256;
+const MAX_FIELD_VALUE: usize = 1024;
+const MAX_FOOTER: usize = 2048;
+const MAX_AUTHOR: usize = 256;
+const MAX_TOTAL: usize = 6000;
+
+/// A serializable embed definition following Discord's embed JSON schema,
+/// so definitions authored in external embed builders can be imported as is.
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+#[serde(default)]
+pub struct EmbedDefinition {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<u32>,
+    /// ISO 8601 timestamp shown next to the footer.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author: Option<AuthorDefinition>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub footer: Option<FooterDefinition>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<ImageDefinition>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thumbnail: Option<ImageDefinition>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldDefinition>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AuthorDefinition {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon_url: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct FooterDefinition {
+    pub text: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon_url: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ImageDefinition {
+    pub url: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct FieldDefinition {
+    pub name: String,
+    pub value: String,
+    #[serde(default)]
+    pub inline: bool,
+}
+
+impl EmbedDefinition {
+    /// Builds the embed, rendering template placeholders in all of its text.
+    /// Rendered text may outgrow the template, e.g. through `{args}`, so it is
+    /// cut to Discord's limits, taking from a `budget` shared by all embeds of a message.
+    /// Text that does not fit at all is left out.
+    fn build(&self, vars: &TemplateVars, budget: &mut usize) -> CreateEmbed {
+        let mut render = |text: &str, limit: usize| {
+            if *budget == 0 {
+                return None;
+            }
+            let rendered = truncate(&template::render(text, vars), limit.min(*budget));
+            *budget = budget.saturating_sub(rendered.chars().count());
+            Some(rendered)
+        };
+        let mut embed = CreateEmbed::default();
+
+        if let Some(title) = self.title.as_deref().and_then(|t| render(t, MAX_TITLE)) {
+            embed = embed.title(title);
+        }
+        if let Some(description) = self
+            .description
+            .as_deref()
+            .and_then(|d| render(d, MAX_DESCRIPTION))
+        {
+            embed = embed.description(description);
+        }
+        if let Some(url) = &self.url {
+            embed = embed.url(url);
+        }
+        if let Some(color) = self.color {
+            embed = embed.color(Colour(color));
+        }
+        if let Some(timestamp) = self
+            .timestamp
+            .as_deref()
+            .and_then(|t| Timestamp::parse(t).ok())
+        {
+            embed = embed.timestamp(timestamp);
+        }
+        if let Some(author) = &self.author
+            && let Some(name) = render(&author.name, MAX_AUTHOR)
+        {
+            let mut builder = CreateEmbedAuthor::new(name);
+            if let Some(url) = &author.url {
+                builder = builder.url(url);
+            }
+            if let Some(icon_url) = &author.icon_url {
+                builder = builder.icon_url(icon_url);
+            }
+            embed = embed.author(builder);
+        }
+        if let Some(footer) = &self.footer
+            && let Some(text) = render(&footer.text, MAX_FOOTER)
+        {
+            let mut builder = CreateEmbedFooter::new(text);
+            if let Some(icon_url) = &footer.icon_url {
+                builder = builder.icon_url(icon_url);
+            }
+            embed = embed.footer(builder);
+        }
+        if let Some(image) = &self.image {
+            embed = embed.image(&image.url);
+        }
+        if let Some(thumbnail) = &self.thumbnail {
+            embed = embed.thumbnail(&thumbnail.url);
+        }
+        for field in &self.fields {
+            if let Some(name) = render(&field.name, MAX_FIELD_NAME)
+                && let Some(value) = render(&field.value, MAX_FIELD_VALUE)
+            {
+                embed = embed.field(name, value, field.inline);
+            }
+        }
+
+        embed
+    }
+
+    /// Counts the characters Discord adds up towards the per-message limit.
+    fn text_len(&self) -> usize {
+        let len = |s: &Option<String>| s.as_deref().map_or(0, |s| s.chars().count());
+        len(&self.title)
+            + len(&self.description)
+            + self.author.as_ref().map_or(0, |a| a.name.chars().count())
+            + self.footer.as_ref().map_or(0, |f| f.text.chars().count())
+            + self
+                .fields
+                .iter()
+                .map(|f| f.name.chars().count() + f.value.chars().count())
+                .sum::<usize>()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.title.as_deref().is_none_or(str::is_empty)
+            && self.description.as_deref().is_none_or(str::is_empty)
+            && self.fields.is_empty()
+            && self.image.is_none()
+            && self.thumbnail.is_none()
+            && self.author.is_none()
+            && self.footer.is_none()
+    }
+}
+
+/// Builds all embeds of a message, keeping their rendered text within the message limit.
+pub fn build_all(embeds: &[EmbedDefinition], vars: &TemplateVars) -> Vec<CreateEmbed> {
+    let mut budget = MAX_TOTAL;
+    embeds
+        .iter()
+        .map(|embed| embed.build(vars, &mut budget))
+        .collect()
+}
+
+/// Checks a single message worth of embeds against Discord's embed limits.
+/// Returns a human readable description of the first violation.
+pub fn validate(embeds: &[EmbedDefinition]) -> Result<(), String> {
+    if embeds.is_empty() {
+        return Err("at least one embed is required".to_string());
+    }
+    if embeds.len() > MAX_EMBEDS {
+        return Err(format!("at most {} embeds are allowed", MAX_EMBEDS));
+    }
+
+    let check = |what: &str, value: Option<&str>, max: usize| match value {
+        Some(v) if v.chars().count() > max => Err(format!("{} is longer than {}", what, max)),
+        _ => Ok(()),
+    };
+
+    for (i, embed) in embeds.iter().enumerate() {
+        let n = i + 1;
+        if embed.is_empty() {
+            return Err(format!("embed {} is empty", n));
+        }
+        check(
+            &format!("embed {} title", n),
+            embed.title.as_deref(),
+            MAX_TITLE,
+        )?;
+        check(
+            &format!("embed {} description", n),
+            embed.description.as_deref(),
+            MAX_DESCRIPTION,
+        )?;
+        check(
+            &format!("embed {} author name", n),
+            embed.author.as_ref().map(|a| a.name.as_str()),
+            MAX_AUTHOR,
+        )?;
+        check(
+            &format!("embed {} footer", n),
+            embed.footer.as_ref().map(|f| f.text.as_str()),
+            MAX_FOOTER,
+        )?;
+
+        if let Some(timestamp) = &embed.timestamp
+            && Timestamp::parse(timestamp).is_err()
+        {
+            return Err(format!("embed {} has an invalid timestamp", n));
+        }
+
+        if embed.fields.len() > MAX_FIELDS {
+            return Err(format!("embed {} has more than {} fields", n, MAX_FIELDS));
+        }
+        for field in &embed.fields {
+            if field.name.is_empty() || field.value.is_empty() {
+                return Err(format!("embed {} has a field without name or value", n));
+            }
+            check(
+                &format!("embed {} field name", n),
+                Some(&field.name),
+                MAX_FIELD_NAME,
+            )?;
+            check(
+                &format!("embed {} field value", n),
+                Some(&field.value),
+                MAX_FIELD_VALUE,
+            )?;
+        }
+    }
+
+    let total: usize = embeds.iter().map(EmbedDefinition::text_len).sum();
+    if total > MAX_TOTAL {
+        return Err(format!(
+            "embeds contain {} characters, at most {} are allowed",
+            total, MAX_TOTAL
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::*;
+
+    fn vars(args: &str) -> TemplateVars {
+        TemplateVars {
+            user: String::new(),
+            username: String::new(),
+            channel: String::new(),
+            server: String::new(),
+            args: vec![args.to_string()],
+            now: Utc::now(),
+        }
+    }
+
+    fn chars(json: &serde_json::Value) -> usize {
+        json.as_str().unwrap().chars().count()
+    }
+
+    #[test]
+    fn rendered_text_is_cut_to_the_limits() {
+        let embed = EmbedDefinition {
+            title: Some("Title {args}".into()),
+            description: Some("{args}".into()),
+            footer: Some(FooterDefinition {
+                text: "{args}".into(),
+                icon_url: None,
+            }),
+            fields: vec![FieldDefinition {
+                name: "{args}".into(),
+                value: "{args}".into(),
+                inline: false,
+            }],
+            ..Default::default()
+        };
+        assert!(validate(std::slice::from_ref(&embed)).is_ok());
+
+        let built = build_all(&[embed], &vars(&"é".repeat(5000)));
+        let json = serde_json::to_value(&built[0]).unwrap();
+        assert_eq!(chars(&json["title"]), MAX_TITLE);
+        assert_eq!(chars(&json["description"]), MAX_DESCRIPTION);
+        assert!(json["title"].as_str().unwrap().ends_with('…'));
+        // The footer gets what is left of the message limit, the field nothing
+        assert_eq!(
+            chars(&json["footer"]["text"]),
+            MAX_TOTAL - MAX_TITLE - MAX_DESCRIPTION
+        );
+        assert!(json["fields"].as_array().is_none_or(Vec::is_empty));
+    }
+
+    #[test]
+    fn short_text_is_kept() {
+        let embed = EmbedDefinition {
+            title: Some("Hi {args}".into()),
+            ..Default::default()
+        };
+        let built = build_all(&[embed.clone(), embed], &vars("there"));
+        for embed in built {
+            let json = serde_json::to_value(&embed).unwrap();
+            assert_eq!(json["title"], "Hi there");
+        }
+    }
+}
@@ -0,0 +1,337 @@
+use std::{
+    collections::HashMap,
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+use once_cell::sync::Lazy;
+use reqwest::{Response, StatusCode, header};
+use serde::{Deserialize, de::DeserializeOwned};
+use thiserror::Error;
+use tokio::sync::RwLock;
+
+pub const API_URL: &str = "https://api.github.com";
+/// Responses kept for conditional requests.
+const MAX_CACHED: usize = 500;
+
+#[derive(Error, Debug)]
+pub enum GitHubError {
+    #[error("GitHub request failed: {0}")]
+    Request(#[from] reqwest::Error),
+    #[error("Not found on GitHub")]
+    NotFound,
+    /// An empty answer, e.g. the contributors of an empty repository.
+    #[error("GitHub has nothing to show for this")]
+    NoContent,
+    #[error("GitHub rate limit reached, it resets <t:{0}:R>")]
+    RateLimited(u64),
+    #[error("GitHub rejected the configured token")]
+    Unauthorized,
+    /// Statistics are computed in the background and answered with 202 until ready.
+    #[error("GitHub is still computing this, try again in a moment")]
+    Pending,
+    #[error("GitHub API error {status}: {message}")]
+    Api { status: u16, message: String },
+}
+
+/// The rate limit of one bucket as GitHub reported it with the last response.
+#[derive(Clone, Copy)]
+struct RateLimit {
+    remaining: u64,
+    /// Unix time the limit resets at.
+    reset: u64,
+}
+
+impl RateLimit {
+    fn from_response(response: &Response) -> Option<Self> {
+        let value = |name: &str| -> Option<u64> {
+            response.headers().get(name)?.to_str().ok()?.parse().ok()
+        };
+        Some(Self {
+            remaining: value("x-ratelimit-remaining")?,
+            reset: value("x-ratelimit-reset")?,
+        })
+    }
+
+    /// The bucket a response counted against, like `core` or `search`.
+    fn resource(response: &Response) -> Option<String> {
+        Some(
+            response
+                .headers()
+                .get("x-ratelimit-resource")?
+                .to_str()
+                .ok()?
+                .to_string(),
+        )
+    }
+
+    /// The reset time if no requests are left until then.
+    fn exhausted_until(&self) -> Option<u64> {
+        (self.remaining == 0 && self.reset > unix_now()).then_some(self.reset)
+    }
+}
+
+/// The rate limit bucket requests to `path` count against. Searches have
+/// their own, much smaller limits.
+fn resource(path: &str) -> &'static str {
+    if path.starts_with("search/code") {
+        "code_search"
+    } else if path.starts_with("search/") {
+        "search"
+    } else {
+        "core"
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// A response that differs from the given ETag.
+pub struct Fresh<T> {
+    pub value: T,
+    pub etag: Option<String>,
+    /// Minimum seconds between polls GitHub asks for, sent with events.
+    pub poll_interval: Option<u64>,
+}
+
+/// The answer to a conditional request.
+pub enum Conditional<T> {
+    NotModified,
+    Modified(Fresh<T>),
+}
+
+struct Cached {
+    etag: String,
+    body: String,
+    stored: Instant,
+}
+
+#[derive(Deserialize)]
+struct ErrorBody {
+    message: String,
+}
+
+/// The GitHub API client shared by all commands. It remembers ETags so
+/// unchanged responses are served from memory without using the rate limit.
+pub struct GitHubClient {
+    http: reqwest::Client,
+    cache: RwLock<HashMap<String, Cached>>,
+    /// Limits by bucket, from the `x-ratelimit-resource` header.
+    rate_limits: RwLock<HashMap<String, RateLimit>>,
+}
+
+static CLIENT: Lazy<GitHubClient> = Lazy::new(|| GitHubClient {
+    http: reqwest::Client::new(),
+    cache: RwLock::new(HashMap::new()),
+    rate_limits: RwLock::new(HashMap::new()),
+});
+
+/// The shared GitHub client.
+pub fn client() -> &'static GitHubClient {
+    &CLIENT
+}
+
+impl GitHubClient {
+    /// Unix time requests to `path` are possible again, if their rate limit is used up.
+    pub async fn limited_until(&self, path: &str) -> Option<u64> {
+        self.rate_limits
+            .read()
+            .await
+            .get(resource(path))
+            .and_then(|limit| limit.exhausted_until())
+    }
+
+    /// Sends a GET request for `path`, like `repos/owner/repo`, and sorts out error statuses.
+    async fn send(
+        &self,
+        token: Option<&str>,
+        path: &str,
+        etag: Option<&str>,
+    ) -> Result<Response, GitHubError> {
+        if let Some(reset) = self.limited_until(path).await {
+            return Err(GitHubError::RateLimited(reset));
+        }
+
+        let mut request = self
+            .http
+            .get(format!("{}/{}", API_URL, path))
+            .header(header::USER_AGENT, "poise-bot")
+            .header(header::ACCEPT, "application/vnd.github+json");
+        if let Some(token) = token.filter(|token| !token.is_empty()) {
+            request = request.bearer_auth(token);
+        }
+        if let Some(etag) = etag {
+            request = request.header(header::IF_NONE_MATCH, etag);
+        }
+        let response = request.send().await.map_err(|e| {
+            tracing::error!("Request failed: {}", e);
+            e
+        })?;
+
+        let rate_limit = RateLimit::from_response(&response);
+        if let Some(limit) = rate_limit {
+            let bucket =
+                RateLimit::resource(&response).unwrap_or_else(|| resource(path).to_string());
+            self.rate_limits.write().await.insert(bucket, limit);
+        }
+
+        let status = response.status();
+        if status == StatusCode::ACCEPTED {
+            return Err(GitHubError::Pending);
+        }
+        if status.is_success() || status == StatusCode::NOT_MODIFIED {
+            return Ok(response);
+        }
+        match status {
+            StatusCode::NOT_FOUND => Err(GitHubError::NotFound),
+            StatusCode::UNAUTHORIZED => Err(GitHubError::Unauthorized),
+            StatusCode::FORBIDDEN | StatusCode::TOO_MANY_REQUESTS => {
+                // Secondary rate limits send Retry-After instead of an empty quota
+                let retry_after = response
+                    .headers()
+                    .get(header::RETRY_AFTER)
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(|value| value.parse::<u64>().ok());
+                if let Some(reset) = rate_limit.and_then(|limit| limit.exhausted_until()) {
+                    Err(GitHubError::RateLimited(reset))
+                } else if let Some(seconds) = retry_after {
+                    Err(GitHubError::RateLimited(unix_now() + seconds))
+                } else {
+                    Err(api_error(response).await)
+                }
+            }
+            _ => Err(api_error(response).await),
+        }
+    }
+
+    /// Fetches and parses `path`, reusing the cached response if it did not change.
+    pub async fn get<T: DeserializeOwned>(
+        &self,
+        token: Option<&str>,
+        path: &str,
+    ) -> Result<T, GitHubError> {
+        // Private data depends on the token, so authenticated responses are cached apart
+        let key = format!("{}:{}", token.is_some_and(|t| !t.is_empty()), path);
+        let etag = self.cache.read().await.get(&key).map(|c| c.etag.clone());
+
+        let mut response = self.send(token, path, etag.as_deref()).await?;
+        if response.status() == StatusCode::NOT_MODIFIED {
+            if let Some(cached) = self.cache.read().await.get(&key) {
+                return serde_json::from_str(&cached.body).map_err(|e| GitHubError::Api {
+                    status: 304,
+                    message: e.to_string(),
+                });
+            }
+            // The entry was evicted in the meantime, so the body has to be fetched again
+            response = self.send(token, path, None).await?;
+        }
+
+        let etag = response
+            .headers()
+            .get(header::ETAG)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        let body = response.text().await?;
+        if body.trim().is_empty() {
+            return Err(GitHubError::NoContent);
+        }
+        let value = serde_json::from_str(&body).map_err(|e| GitHubError::Api {
+            status: 200,
+            message: e.to_string(),
+        })?;
+
+        if let Some(etag) = etag {
+            let mut cache = self.cache.write().await;
+            if cache.len() >= MAX_CACHED
+                && let Some(oldest) = cache
+                    .iter()
+                    .min_by_key(|(_, cached)| cached.stored)
+                    .map(|(key, _)| key.clone())
+            {
+                cache.remove(&oldest);
+            }
+            cache.insert(
+                key,
+                Cached {
+                    etag,
+                    body,
+                    stored: Instant::now(),
+                },
+            );
+        }
+        Ok(value)
+    }
+
+    /// Fetches `path` unless it still matches `etag`, for callers that keep the ETag themselves.
+    pub async fn get_if_changed<T: DeserializeOwned>(
+        &self,
+        token: Option<&str>,
+        path: &str,
+        etag: Option<&str>,
+    ) -> Result<Conditional<T>, GitHubError> {
+        let response = self.send(token, path, etag).await?;
+        if response.status() == StatusCode::NOT_MODIFIED {
+            return Ok(Conditional::NotModified);
+        }
+
+        let header_value = |name: header::HeaderName| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        let etag = header_value(header::ETAG);
+        let poll_interval = header_value(header::HeaderName::from_static("x-poll-interval"))
+            .and_then(|value| value.parse().ok());
+        Ok(Conditional::Modified(Fresh {
+            value: response.json().await?,
+            etag,
+            poll_interval,
+        }))
+    }
+}
+
+async fn api_error(response: Response) -> GitHubError {
+    let status = response.status().as_u16();
+    let message = response
+        .json::<ErrorBody>()
+        .await
+        .map_or_else(|_| "unknown error".to_string(), |body| body.message);
+    GitHubError::Api { status, message }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn searches_have_their_own_buckets() {
+        assert_eq!(resource("repos/rust-lang/rust/events"), "core");
+        assert_eq!(resource("users/octocat"), "core");
+        assert_eq!(resource("search/issues?q=repo:a/b"), "search");
+        assert_eq!(resource("search/code?q=x"), "code_search");
+    }
+
+    #[tokio::test]
+    async fn exhausted_search_does_not_limit_core() {
+        let client = GitHubClient {
+            http: reqwest::Client::new(),
+            cache: RwLock::new(HashMap::new()),
+            rate_limits: RwLock::new(HashMap::new()),
+        };
+        let reset = unix_now() + 60;
+        client.rate_limits.write().await.insert(
+            "search".into(),
+            RateLimit {
+                remaining: 0,
+                reset,
+            },
+        );
+
+        assert_eq!(client.limited_until("search/issues?q=x").await, Some(reset));
+        assert_eq!(client.limited_until("repos/a/b").await, None);
+    }
+}
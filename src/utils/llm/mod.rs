@@ -0,0 +1,312 @@
+pub mod ollama;
+pub mod openai;
+
+use std::pin::Pin;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serenity::futures::Stream;
+use thiserror::Error;
+
+use crate::config::{Config, LlmProvider, ProviderKind};
+
+pub use ollama::OllamaClient;
+pub use openai::OpenAiClient;
+
+const DEEPSEEK_BASE_URL: &str = "https://api.deepseek.com/v1";
+const OPENAI_BASE_URL: &str = "https://api.openai.com/v1";
+const OLLAMA_BASE_URL: &str = "http://localhost:11434";
+
+/// Represents an error while talking to an LLM provider.
+#[derive(Debug, Error)]
+pub enum LlmError {
+    #[error("Request failed: {0}")]
+    Request(#[from] reqwest::Error),
+
+    #[error("API error ({status}): {body}")]
+    Api { status: u16, body: String },
+
+    #[error("Unknown model: {0}")]
+    UnknownModel(String),
+
+    #[error("No API key configured for {0}")]
+    MissingKey(String),
+
+    #[error("Invalid response: {0}")]
+    Decode(String),
+}
+
+/// A single message of a chat conversation.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ChatMessage {
+    pub role: String,
+    pub content: String,
+    /// Tools the assistant asked to call.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tool_calls: Vec<ToolCall>,
+    /// The call a `tool` message answers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+    /// Images sent along with a user message. They are not persisted.
+    #[serde(skip)]
+    pub images: Vec<ImageInput>,
+}
+
+/// An image in a prompt, base64 encoded.
+#[derive(Clone, Debug)]
+pub struct ImageInput {
+    pub mime_type: String,
+    pub data: String,
+}
+
+impl ImageInput {
+    pub fn data_url(&self) -> String {
+        format!("data:{};base64,{}", self.mime_type, self.data)
+    }
+}
+
+impl ChatMessage {
+    fn new(role: &str, content: impl Into<String>) -> Self {
+        Self {
+            role: role.into(),
+            content: content.into(),
+            tool_calls: Vec::new(),
+            tool_call_id: None,
+            images: Vec::new(),
+        }
+    }
+
+    pub fn system(content: impl Into<String>) -> Self {
+        Self::new("system", content)
+    }
+
+    pub fn user(content: impl Into<String>) -> Self {
+        Self::new("user", content)
+    }
+
+    pub fn assistant(content: impl Into<String>) -> Self {
+        Self::new("assistant", content)
+    }
+
+    /// The assistant's turn that requested tool calls.
+    pub fn tool_request(content: impl Into<String>, calls: Vec<ToolCall>) -> Self {
+        Self {
+            tool_calls: calls,
+            ..Self::new("assistant", content)
+        }
+    }
+
+    /// The result of a tool call.
+    pub fn tool_result(call_id: impl Into<String>, content: impl Into<String>) -> Self {
+        Self {
+            tool_call_id: Some(call_id.into()),
+            ..Self::new("tool", content)
+        }
+    }
+}
+
+/// A function call requested by the model, in the OpenAI wire format.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ToolCall {
+    pub id: String,
+    #[serde(rename = "type", default = "function_type")]
+    pub kind: String,
+    pub function: FunctionCall,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct FunctionCall {
+    pub name: String,
+    /// Arguments as a JSON encoded object.
+    pub arguments: String,
+}
+
+fn function_type() -> String {
+    "function".into()
+}
+
+impl ToolCall {
+    pub fn new(
+        id: impl Into<String>,
+        name: impl Into<String>,
+        arguments: impl Into<String>,
+    ) -> Self {
+        Self {
+            id: id.into(),
+            kind: function_type(),
+            function: FunctionCall {
+                name: name.into(),
+                arguments: arguments.into(),
+            },
+        }
+    }
+}
+
+/// A tool offered to the model.
+#[derive(Clone, Debug)]
+pub struct ToolSpec {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// JSON schema of the arguments object.
+    pub parameters: serde_json::Value,
+}
+
+impl ToolSpec {
+    /// The tool definition as OpenAI compatible APIs and Ollama expect it.
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "function",
+            "function": {
+                "name": self.name,
+                "description": self.description,
+                "parameters": self.parameters
+            }
+        })
+    }
+}
+
+/// Rough characters per token, for providers that do not report usage.
+const CHARS_PER_TOKEN: u64 = 4;
+/// Per-message overhead (role, separators) added to the estimate.
+const TOKENS_PER_MESSAGE: u64 = 4;
+/// Rough cost of an image; the real cost depends on its size and the model.
+const TOKENS_PER_IMAGE: u64 = 800;
+
+/// Roughly estimates the tokens a text costs.
+pub fn estimate_text_tokens(text: &str) -> u64 {
+    text.chars().count() as u64 / CHARS_PER_TOKEN
+}
+
+/// Roughly estimates the prompt tokens of a conversation.
+pub fn estimate_tokens(messages: &[ChatMessage]) -> u64 {
+    messages
+        .iter()
+        .map(|m| {
+            estimate_text_tokens(&m.content)
+                + TOKENS_PER_MESSAGE
+                + TOKENS_PER_IMAGE * m.images.len() as u64
+        })
+        .sum()
+}
+
+/// A chat completion request, independent of the provider.
+#[derive(Clone, Debug)]
+pub struct ChatRequest {
+    /// Model name as the provider knows it, without the provider prefix.
+    pub model: String,
+    pub messages: Vec<ChatMessage>,
+    pub temperature: Option<f32>,
+    /// Upper limit for the answer's tokens.
+    pub max_tokens: Option<u32>,
+    /// Tools the model may call. Empty to disable tool calling.
+    pub tools: Vec<ToolSpec>,
+}
+
+/// Tokens consumed by a request as reported by the provider.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TokenUsage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+}
+
+/// An item of a streamed answer.
+#[derive(Clone, Debug)]
+pub enum ChatEvent {
+    /// The next piece of the answer's content.
+    Delta(String),
+    /// Token usage, usually sent once at the end of the stream.
+    Usage(TokenUsage),
+    /// The model wants these tools called before it continues.
+    ToolCalls(Vec<ToolCall>),
+}
+
+/// The answer, streamed in pieces as the provider produces it.
+pub type DeltaStream = Pin<Box<dyn Stream<Item = Result<ChatEvent, LlmError>> + Send>>;
+
+/// A chat model backend.
+#[async_trait]
+pub trait LlmClient: Send + Sync {
+    /// Starts a chat completion and streams the answer.
+    async fn chat_stream(&self, request: &ChatRequest) -> Result<DeltaStream, LlmError>;
+}
+
+/// Creates the client for a configured provider.
+/// `name` is only used for error messages, `fallback_key` for DeepSeek providers without a key.
+pub fn client_for(
+    name: &str,
+    provider: &LlmProvider,
+    fallback_key: Option<&str>,
+) -> Result<Box<dyn LlmClient>, LlmError> {
+    let base_url = |default: &str| provider.base_url.as_deref().unwrap_or(default).to_string();
+
+    Ok(match provider.kind {
+        ProviderKind::OpenAi => Box::new(OpenAiClient::new(
+            base_url(OPENAI_BASE_URL),
+            provider.api_key.clone(),
+        )),
+        ProviderKind::DeepSeek => {
+            let key = provider
+                .api_key
+                .as_deref()
+                .or(fallback_key)
+                .ok_or_else(|| LlmError::MissingKey(name.to_string()))?;
+            Box::new(OpenAiClient::new(
+                base_url(DEEPSEEK_BASE_URL),
+                Some(key.to_string()),
+            ))
+        }
+        ProviderKind::Ollama => Box::new(OllamaClient::new(base_url(OLLAMA_BASE_URL))),
+    })
+}
+
+/// Resolves a `provider/model` id to its client and the provider's model name.
+pub fn resolve_model(
+    config: &Config,
+    model: &str,
+) -> Result<(Box<dyn LlmClient>, String), LlmError> {
+    let unknown = || LlmError::UnknownModel(model.to_string());
+    let (name, model_name) = model.split_once('/').ok_or_else(unknown)?;
+    let provider = config.llm_providers.get(name).ok_or_else(unknown)?;
+    if !provider.models.iter().any(|m| m == model_name) {
+        return Err(unknown());
+    }
+
+    let client = client_for(name, provider, config.deepseek_token.as_deref())?;
+    Ok((client, model_name.to_string()))
+}
+
+/// Whether tools may be offered to a `provider/model`.
+pub fn supports_tools(config: &Config, model: &str) -> bool {
+    model
+        .split_once('/')
+        .and_then(|(name, model)| {
+            let provider = config.llm_providers.get(name)?;
+            Some(!provider.models_without_tools.iter().any(|m| m == model))
+        })
+        .unwrap_or(false)
+}
+
+/// Whether a `provider/model` accepts images.
+pub fn supports_vision(config: &Config, model: &str) -> bool {
+    model
+        .split_once('/')
+        .and_then(|(name, model)| {
+            let provider = config.llm_providers.get(name)?;
+            Some(provider.vision_models.iter().any(|m| m == model))
+        })
+        .unwrap_or(false)
+}
+
+/// Lists all configured models as `provider/model`.
+pub fn available_models(config: &Config) -> Vec<String> {
+    config
+        .llm_providers
+        .iter()
+        .flat_map(|(name, provider)| {
+            provider
+                .models
+                .iter()
+                .map(move |model| format!("{}/{}", name, model))
+        })
+        .collect()
+}
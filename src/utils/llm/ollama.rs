@@ -0,0 +1,316 @@
+use async_trait::async_trait;
+use serde::Deserialize;
+use serenity::futures::{StreamExt, future, stream};
+
+use super::{
+    ChatEvent, ChatMessage, ChatRequest, DeltaStream, LlmClient, LlmError, TokenUsage, ToolCall,
+    ToolSpec,
+};
+
+/// Client for Ollama's native chat API, which streams newline delimited JSON.
+pub struct OllamaClient {
+    http: reqwest::Client,
+    base_url: String,
+}
+
+#[derive(Deserialize)]
+struct ChatChunk {
+    #[serde(default)]
+    message: Option<ChunkMessage>,
+    #[serde(default)]
+    done: bool,
+    #[serde(default)]
+    error: Option<String>,
+    /// Prompt tokens, sent with the final chunk.
+    #[serde(default)]
+    prompt_eval_count: Option<u64>,
+    /// Completion tokens, sent with the final chunk.
+    #[serde(default)]
+    eval_count: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct ChunkMessage {
+    #[serde(default)]
+    content: String,
+    #[serde(default)]
+    tool_calls: Vec<OllamaToolCall>,
+}
+
+/// Ollama's tool call format, which has no ids and passes arguments as an object.
+#[derive(Deserialize)]
+struct OllamaToolCall {
+    function: OllamaFunction,
+}
+
+#[derive(Deserialize)]
+struct OllamaFunction {
+    name: String,
+    #[serde(default)]
+    arguments: serde_json::Value,
+}
+
+/// Converts a message into Ollama's format.
+fn wire_message(message: &ChatMessage) -> serde_json::Value {
+    let mut json = serde_json::json!({
+        "role": message.role,
+        "content": message.content
+    });
+    if !message.images.is_empty() {
+        json["images"] = message
+            .images
+            .iter()
+            .map(|image| image.data.clone())
+            .collect();
+    }
+    if !message.tool_calls.is_empty() {
+        json["tool_calls"] = message
+            .tool_calls
+            .iter()
+            .map(|call| {
+                let arguments: serde_json::Value =
+                    serde_json::from_str(&call.function.arguments).unwrap_or_default();
+                serde_json::json!({
+                    "function": { "name": call.function.name, "arguments": arguments }
+                })
+            })
+            .collect();
+    }
+    json
+}
+
+impl OllamaClient {
+    /// `base_url` is the server root, e.g. `http://localhost:11434`.
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+        }
+    }
+}
+
+/// Parses one NDJSON line. Returns None for blank lines.
+fn parse_line(line: &[u8]) -> Option<Result<ChatChunk, LlmError>> {
+    let line = String::from_utf8_lossy(line);
+    let line = line.trim();
+    if line.is_empty() {
+        return None;
+    }
+    Some(serde_json::from_str(line).map_err(|e| LlmError::Decode(e.to_string())))
+}
+
+/// Turns the NDJSON body into chat events. Lines may be split across chunks,
+/// so they are buffered until complete.
+#[derive(Default)]
+struct NdjsonDecoder {
+    buffer: Vec<u8>,
+    /// The final chunk was seen, anything after it is ignored.
+    done: bool,
+    call_count: usize,
+}
+
+impl NdjsonDecoder {
+    /// Feeds the next chunk of bytes and returns the events of all lines completed by it.
+    fn feed(&mut self, chunk: &[u8]) -> Vec<Result<ChatEvent, LlmError>> {
+        self.buffer.extend_from_slice(chunk);
+        let mut events = Vec::new();
+        while let Some(pos) = self.buffer.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=pos).collect();
+            self.process_line(&line, &mut events);
+        }
+        events
+    }
+
+    /// Ends the stream, handling a last line without a trailing newline.
+    fn finish(&mut self) -> Vec<Result<ChatEvent, LlmError>> {
+        let line = std::mem::take(&mut self.buffer);
+        let mut events = Vec::new();
+        self.process_line(&line, &mut events);
+        events
+    }
+
+    fn process_line(&mut self, line: &[u8], events: &mut Vec<Result<ChatEvent, LlmError>>) {
+        if self.done {
+            return;
+        }
+        let chunk = match parse_line(line) {
+            Some(Ok(chunk)) => chunk,
+            Some(Err(e)) => {
+                events.push(Err(e));
+                return;
+            }
+            None => return,
+        };
+
+        if let Some(error) = chunk.error {
+            events.push(Err(LlmError::Decode(error)));
+        }
+        if let Some(message) = chunk.message {
+            if !message.content.is_empty() {
+                events.push(Ok(ChatEvent::Delta(message.content)));
+            }
+            if !message.tool_calls.is_empty() {
+                let calls = message
+                    .tool_calls
+                    .into_iter()
+                    .map(|call| {
+                        self.call_count += 1;
+                        ToolCall::new(
+                            format!("call_{}", self.call_count),
+                            call.function.name,
+                            call.function.arguments.to_string(),
+                        )
+                    })
+                    .collect();
+                events.push(Ok(ChatEvent::ToolCalls(calls)));
+            }
+        }
+        if let (Some(prompt), Some(completion)) = (chunk.prompt_eval_count, chunk.eval_count) {
+            events.push(Ok(ChatEvent::Usage(TokenUsage {
+                prompt_tokens: prompt,
+                completion_tokens: completion,
+            })));
+        }
+        self.done = chunk.done;
+    }
+}
+
+#[async_trait]
+impl LlmClient for OllamaClient {
+    async fn chat_stream(&self, request: &ChatRequest) -> Result<DeltaStream, LlmError> {
+        let mut options = serde_json::Map::new();
+        if let Some(temperature) = request.temperature {
+            options.insert("temperature".into(), temperature.into());
+        }
+        if let Some(max_tokens) = request.max_tokens {
+            options.insert("num_predict".into(), max_tokens.into());
+        }
+
+        let mut body = serde_json::json!({
+            "model": request.model,
+            "messages": request.messages.iter().map(wire_message).collect::<Vec<_>>(),
+            "options": options,
+            "stream": true
+        });
+        if !request.tools.is_empty() {
+            body["tools"] = request.tools.iter().map(ToolSpec::to_json).collect();
+        }
+
+        let response = self
+            .http
+            .post(format!("{}/api/chat", self.base_url))
+            .json(&body)
+            .send()
+            .await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(LlmError::Api {
+                status: status.as_u16(),
+                body: response.text().await.unwrap_or_default(),
+            });
+        }
+
+        // A None marks the end of the body, where an unterminated last line is flushed
+        let mut decoder = NdjsonDecoder::default();
+        let deltas = response
+            .bytes_stream()
+            .map(Some)
+            .chain(stream::once(future::ready(None)))
+            .flat_map(move |item| {
+                stream::iter(match item {
+                    Some(Ok(chunk)) => decoder.feed(&chunk),
+                    Some(Err(e)) => vec![Err(e.into())],
+                    None => decoder.finish(),
+                })
+            });
+
+        Ok(Box::pin(deltas))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_server::{self, Route};
+
+    fn request() -> ChatRequest {
+        ChatRequest {
+            model: "llama3".into(),
+            messages: vec![ChatMessage::user("Hi")],
+            temperature: None,
+            max_tokens: None,
+            tools: Vec::new(),
+        }
+    }
+
+    async fn collect(chunks: Vec<&'static str>) -> Vec<Result<ChatEvent, LlmError>> {
+        let base_url =
+            test_server::serve(vec![Route::ok("/api/chat", "application/x-ndjson", chunks)]).await;
+        let client = OllamaClient::new(base_url);
+        client
+            .chat_stream(&request())
+            .await
+            .unwrap()
+            .collect()
+            .await
+    }
+
+    #[tokio::test]
+    async fn streams_split_lines_and_final_line_without_newline() {
+        let events = collect(vec![
+            "{\"message\":{\"content\":\"Hel\"}}\n{\"message\":",
+            "{\"content\":\"lo\"}}\n",
+            "{\"message\":{\"content\":\"\"},\"done\":true,",
+            "\"prompt_eval_count\":12,\"eval_count\":3}",
+        ])
+        .await;
+
+        let text: String = events
+            .iter()
+            .filter_map(|e| match e {
+                Ok(ChatEvent::Delta(text)) => Some(text.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(text, "Hello");
+        assert!(matches!(
+            events.last(),
+            Some(Ok(ChatEvent::Usage(TokenUsage {
+                prompt_tokens: 12,
+                completion_tokens: 3
+            })))
+        ));
+    }
+
+    #[tokio::test]
+    async fn numbers_tool_calls_and_ignores_lines_after_done() {
+        let events = collect(vec![
+            "{\"message\":{\"content\":\"\",\"tool_calls\":[{\"function\":",
+            "{\"name\":\"ping\",\"arguments\":{\"host\":\"a\"}}},",
+            "{\"function\":{\"name\":\"time\"}}]}}\n",
+            "{\"done\":true}\n{\"message\":{\"content\":\"late\"}}\n",
+        ])
+        .await;
+
+        assert_eq!(events.len(), 1);
+        let Ok(ChatEvent::ToolCalls(calls)) = &events[0] else {
+            panic!("expected tool calls");
+        };
+        assert_eq!(calls[0].id, "call_1");
+        assert_eq!(calls[0].function.name, "ping");
+        assert_eq!(calls[0].function.arguments, r#"{"host":"a"}"#);
+        assert_eq!(calls[1].id, "call_2");
+    }
+
+    #[tokio::test]
+    async fn reports_errors() {
+        let events = collect(vec!["{\"error\":\"model not found\"}\nnot json"]).await;
+        assert!(matches!(&events[0], Err(LlmError::Decode(e)) if e == "model not found"));
+        assert!(matches!(&events[1], Err(LlmError::Decode(_))));
+
+        let base_url = test_server::serve(Vec::new()).await;
+        let result = OllamaClient::new(base_url).chat_stream(&request()).await;
+        assert!(matches!(result, Err(LlmError::Api { status: 404, .. })));
+    }
+}
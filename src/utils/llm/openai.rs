@@ -0,0 +1,258 @@
+use async_trait::async_trait;
+use serenity::futures::{StreamExt, future, stream};
+
+use super::{
+    ChatEvent, ChatMessage, ChatRequest, DeltaStream, LlmClient, LlmError, TokenUsage, ToolCall,
+    ToolSpec,
+};
+use crate::utils::sse::{self, SseEvent};
+
+/// Client for OpenAI compatible chat completion APIs (OpenAI, DeepSeek, ...).
+pub struct OpenAiClient {
+    http: reqwest::Client,
+    base_url: String,
+    api_key: Option<String>,
+}
+
+impl OpenAiClient {
+    /// `base_url` is the API root, e.g. `https://api.openai.com/v1`.
+    pub fn new(base_url: impl Into<String>, api_key: Option<String>) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            api_key,
+        }
+    }
+}
+
+/// A tool call whose name and arguments are still being streamed.
+#[derive(Default)]
+struct PartialCall {
+    id: String,
+    name: String,
+    arguments: String,
+}
+
+/// Extracts the content delta, usage and tool calls of a completion chunk.
+/// Tool calls arrive in pieces and are collected in `calls` until the choice finishes.
+fn parse_event(
+    event: Result<SseEvent, reqwest::Error>,
+    calls: &mut Vec<PartialCall>,
+) -> Vec<Result<ChatEvent, LlmError>> {
+    let event = match event {
+        Ok(event) => event,
+        Err(e) => return vec![Err(e.into())],
+    };
+    let json: serde_json::Value = match serde_json::from_str(&event.data) {
+        Ok(json) => json,
+        Err(e) => return vec![Err(LlmError::Decode(e.to_string()))],
+    };
+    if let Some(error) = json.get("error") {
+        let message = error["message"].as_str().unwrap_or("unknown error");
+        return vec![Err(LlmError::Decode(message.to_string()))];
+    }
+
+    let mut events = Vec::new();
+    if let Some(content) = json["choices"][0]["delta"]["content"]
+        .as_str()
+        .filter(|s| !s.is_empty())
+    {
+        events.push(Ok(ChatEvent::Delta(content.to_string())));
+    }
+    if let Some(deltas) = json["choices"][0]["delta"]["tool_calls"].as_array() {
+        for (position, delta) in deltas.iter().enumerate() {
+            let index = delta["index"].as_u64().map_or(position, |i| i as usize);
+            if calls.len() <= index {
+                calls.resize_with(index + 1, PartialCall::default);
+            }
+            let call = &mut calls[index];
+            if let Some(id) = delta["id"].as_str() {
+                call.id = id.to_string();
+            }
+            if let Some(name) = delta["function"]["name"].as_str() {
+                call.name.push_str(name);
+            }
+            if let Some(arguments) = delta["function"]["arguments"].as_str() {
+                call.arguments.push_str(arguments);
+            }
+        }
+    }
+    if json["choices"][0]["finish_reason"].is_string() && !calls.is_empty() {
+        let finished = calls
+            .drain(..)
+            .enumerate()
+            .map(|(i, call)| {
+                let id = if call.id.is_empty() {
+                    format!("call_{}", i)
+                } else {
+                    call.id
+                };
+                ToolCall::new(id, call.name, call.arguments)
+            })
+            .collect();
+        events.push(Ok(ChatEvent::ToolCalls(finished)));
+    }
+    if let (Some(prompt), Some(completion)) = (
+        json["usage"]["prompt_tokens"].as_u64(),
+        json["usage"]["completion_tokens"].as_u64(),
+    ) {
+        events.push(Ok(ChatEvent::Usage(TokenUsage {
+            prompt_tokens: prompt,
+            completion_tokens: completion,
+        })));
+    }
+    events
+}
+
+/// Converts a message into the wire format, sending images as content parts.
+fn wire_message(message: &ChatMessage) -> serde_json::Value {
+    let mut json = serde_json::to_value(message).unwrap_or_default();
+    if !message.images.is_empty() {
+        let mut parts = vec![serde_json::json!({ "type": "text", "text": message.content })];
+        parts.extend(message.images.iter().map(|image| {
+            serde_json::json!({ "type": "image_url", "image_url": { "url": image.data_url() } })
+        }));
+        json["content"] = parts.into();
+    }
+    json
+}
+
+#[async_trait]
+impl LlmClient for OpenAiClient {
+    async fn chat_stream(&self, request: &ChatRequest) -> Result<DeltaStream, LlmError> {
+        let mut body = serde_json::json!({
+            "model": request.model,
+            "messages": request.messages.iter().map(wire_message).collect::<Vec<_>>(),
+            "stream": true,
+            "stream_options": { "include_usage": true }
+        });
+        if let Some(temperature) = request.temperature {
+            body["temperature"] = temperature.into();
+        }
+        if let Some(max_tokens) = request.max_tokens {
+            body["max_tokens"] = max_tokens.into();
+        }
+        if !request.tools.is_empty() {
+            body["tools"] = request.tools.iter().map(ToolSpec::to_json).collect();
+        }
+
+        let mut builder = self
+            .http
+            .post(format!("{}/chat/completions", self.base_url))
+            .header("Accept", "text/event-stream")
+            .json(&body);
+        if let Some(key) = &self.api_key {
+            builder = builder.bearer_auth(key);
+        }
+
+        let response = builder.send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(LlmError::Api {
+                status: status.as_u16(),
+                body: response.text().await.unwrap_or_default(),
+            });
+        }
+
+        let deltas = sse::decode(response.bytes_stream()).take_while(|event| {
+            future::ready(!matches!(event, Ok(event) if event.data.trim() == "[DONE]"))
+        });
+        let mut calls = Vec::new();
+        let deltas = deltas.flat_map(move |event| stream::iter(parse_event(event, &mut calls)));
+
+        Ok(Box::pin(deltas))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_server::{self, Route};
+
+    fn request() -> ChatRequest {
+        ChatRequest {
+            model: "gpt".into(),
+            messages: vec![ChatMessage::user("Hi")],
+            temperature: None,
+            max_tokens: None,
+            tools: Vec::new(),
+        }
+    }
+
+    async fn collect(chunks: Vec<&'static str>) -> Vec<Result<ChatEvent, LlmError>> {
+        let base_url = test_server::serve(vec![Route::ok(
+            "/v1/chat/completions",
+            "text/event-stream",
+            chunks,
+        )])
+        .await;
+        let client = OpenAiClient::new(format!("{}/v1", base_url), Some("key".into()));
+        client
+            .chat_stream(&request())
+            .await
+            .unwrap()
+            .collect()
+            .await
+    }
+
+    #[tokio::test]
+    async fn streams_deltas_and_usage_until_done() {
+        let events = collect(vec![
+            ": keep-alive\n\ndata: {\"choices\":[{\"delta\":{\"content\":\"Hel\"}}]}\r\n",
+            "\r\ndata: {\"choices\":[{\"delta\":{\"content\":\"lo\"}}]}\n\n",
+            "data: {\"choices\":[],\"usage\":{\"prompt_tokens\":7,\"completion_tokens\":2}}\n\n",
+            "data: [DONE]\n\n",
+            "data: {\"choices\":[{\"delta\":{\"content\":\"late\"}}]}\n\n",
+        ])
+        .await;
+
+        assert_eq!(events.len(), 3);
+        assert!(matches!(&events[0], Ok(ChatEvent::Delta(text)) if text == "Hel"));
+        assert!(matches!(&events[1], Ok(ChatEvent::Delta(text)) if text == "lo"));
+        assert!(matches!(
+            &events[2],
+            Ok(ChatEvent::Usage(TokenUsage {
+                prompt_tokens: 7,
+                completion_tokens: 2
+            }))
+        ));
+    }
+
+    #[tokio::test]
+    async fn assembles_tool_calls_from_pieces() {
+        let events = collect(vec![
+            "data: {\"choices\":[{\"delta\":{\"tool_calls\":[{\"index\":0,\"id\":\"call_a\",",
+            "\"function\":{\"name\":\"ping\",\"arguments\":\"{\\\"ho\"}}]}}]}\n\n",
+            "data: {\"choices\":[{\"delta\":{\"tool_calls\":[{\"index\":0,",
+            "\"function\":{\"arguments\":\"st\\\":1}\"}},{\"index\":1,\"function\":{\"name\":\"time\"}}]}}]}\n\n",
+            "data: {\"choices\":[{\"delta\":{},\"finish_reason\":\"tool_calls\"}]}",
+        ])
+        .await;
+
+        assert_eq!(events.len(), 1);
+        let Ok(ChatEvent::ToolCalls(calls)) = &events[0] else {
+            panic!("expected tool calls");
+        };
+        assert_eq!(calls[0].id, "call_a");
+        assert_eq!(calls[0].function.name, "ping");
+        assert_eq!(calls[0].function.arguments, r#"{"host":1}"#);
+        assert_eq!(calls[1].id, "call_1");
+        assert_eq!(calls[1].function.name, "time");
+    }
+
+    #[tokio::test]
+    async fn reports_errors() {
+        let events = collect(vec![
+            "data: {\"error\":{\"message\":\"overloaded\"}}\n\ndata: nope\n\n",
+        ])
+        .await;
+        assert!(matches!(&events[0], Err(LlmError::Decode(e)) if e == "overloaded"));
+        assert!(matches!(&events[1], Err(LlmError::Decode(_))));
+
+        let base_url = test_server::serve(Vec::new()).await;
+        let result = OpenAiClient::new(base_url, None)
+            .chat_stream(&request())
+            .await;
+        assert!(matches!(result, Err(LlmError::Api { status: 404, .. })));
+    }
+}
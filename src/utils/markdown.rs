@@ -0,0 +1,331 @@
+const FENCE: &str = "```";
+/// Room kept free on every page for reopening and closing a code fence.
+const FENCE_RESERVE: usize = 64;
+
+/// A code block taken out of a text to be sent as a file.
+pub struct CodeFile {
+    pub name: String,
+    pub content: String,
+}
+
+/// Splits `text` into pages of at most `limit` bytes without breaking Markdown.
+///
+/// Pages end at paragraph or code fence boundaries where possible. Code blocks
+/// that still have to be split are closed at the end of a page and reopened with
+/// the same language on the next one, and an unterminated block (e.g. while an
+/// answer is still streaming) is closed on the last page.
+pub fn split_message(text: &str, limit: usize) -> Vec<String> {
+    let limit = limit.max(FENCE_RESERVE * 2);
+    let mut pages = Vec::new();
+    let mut current = String::new();
+    // Positions in `current` outside of code blocks where a page may end
+    let mut breaks: Vec<usize> = Vec::new();
+    // Opening line of the code block `current` ends in
+    let mut fence: Option<String> = None;
+
+    for line in text.lines() {
+        for piece in hard_wrap(line, limit - FENCE_RESERVE) {
+            let is_fence = piece.trim_start().starts_with(FENCE);
+            let next_fence = match (&fence, is_fence) {
+                (Some(_), true) => None,
+                (None, true) => Some(piece.trim().to_string()),
+                (open, false) => open.clone(),
+            };
+
+            loop {
+                let closing = if next_fence.is_some() {
+                    FENCE.len() + 1
+                } else {
+                    0
+                };
+                let only_opener = fence.as_deref() == Some(current.as_str());
+                if current.is_empty()
+                    || only_opener
+                    || current.len() + 1 + piece.len() + closing <= limit
+                {
+                    break;
+                }
+
+                if let Some(at) = breaks.pop() {
+                    push_page(&mut pages, current[..at].to_string());
+                    current = current[at..].trim_start_matches('\n').to_string();
+                    breaks.clear();
+                } else if let Some(opener) = &fence {
+                    push_page(&mut pages, format!("{}\n{}", current, FENCE));
+                    current = opener.clone();
+                } else {
+                    push_page(&mut pages, std::mem::take(&mut current));
+                }
+            }
+
+            if fence.is_none() && !current.is_empty() && (is_fence || piece.trim().is_empty()) {
+                breaks.push(current.len());
+            }
+            if !current.is_empty() {
+                current.push('\n');
+            }
+            current.push_str(piece);
+            if fence.is_some() && next_fence.is_none() {
+                breaks.push(current.len());
+            }
+            fence = next_fence;
+        }
+    }
+
+    if fence.is_some() {
+        current.push('\n');
+        current.push_str(FENCE);
+    }
+    push_page(&mut pages, current);
+    pages
+}
+
+fn push_page(pages: &mut Vec<String>, page: String) {
+    let page = page.trim_end();
+    if !page.trim().is_empty() {
+        pages.push(page.to_string());
+    }
+}
+
+/// Splits a single overlong line, preferring whitespace, into pieces of at most `max` bytes.
+fn hard_wrap(line: &str, max: usize) -> Vec<&str> {
+    let mut pieces = Vec::new();
+    let mut rest = line;
+
+    while rest.len() > max {
+        let mut cut = max;
+        while !rest.is_char_boundary(cut) {
+            cut -= 1;
+        }
+        if let Some(space) = rest[..cut].rfind(char::is_whitespace).filter(|s| *s > 0) {
+            cut = space;
+        }
+        pieces.push(&rest[..cut]);
+        rest = rest[cut..].trim_start();
+    }
+
+    pieces.push(rest);
+    pieces
+}
+
+/// Moves fenced code blocks longer than `max_len` bytes out of `text`.
+/// Each block is replaced by a note naming the file it was moved to.
+pub fn extract_code_blocks(text: &str, max_len: usize) -> (String, Vec<CodeFile>) {
+    let mut out = String::with_capacity(text.len());
+    let mut files = Vec::new();
+    let mut lines = text.lines();
+
+    while let Some(line) = lines.next() {
+        let Some(info) = line.trim_start().strip_prefix(FENCE) else {
+            out.push_str(line);
+            out.push('\n');
+            continue;
+        };
+
+        let mut block = Vec::new();
+        let mut closed = false;
+        for inner in lines.by_ref() {
+            if inner.trim_start().starts_with(FENCE) {
+                closed = true;
+                break;
+            }
+            block.push(inner);
+        }
+        let content = block.join("\n");
+
+        if closed && content.len() > max_len {
+            let language = info.split_whitespace().next().unwrap_or_default();
+            let name = format!("snippet{}.{}", files.len() + 1, extension_for(language));
+            out.push_str(&format!("*(code attached as `{}`)*\n", name));
+            files.push(CodeFile { name, content });
+        } else {
+            out.push_str(line);
+            out.push('\n');
+            if !block.is_empty() {
+                out.push_str(&content);
+                out.push('\n');
+            }
+            if closed {
+                out.push_str(FENCE);
+                out.push('\n');
+            }
+        }
+    }
+
+    (out.trim_end().to_string(), files)
+}
+
+/// Maps a code fence language to a file extension.
+pub fn extension_for(language: &str) -> &'static str {
+    match language.to_lowercase().as_str() {
+        "rust" | "rs" => "rs",
+        "python" | "py" => "py",
+        "javascript" | "js" | "jsx" => "js",
+        "typescript" | "ts" | "tsx" => "ts",
+        "json" => "json",
+        "toml" => "toml",
+        "yaml" | "yml" => "yml",
+        "sh" | "bash" | "shell" | "zsh" => "sh",
+        "powershell" | "ps1" => "ps1",
+        "c" | "h" => "c",
+        "cpp" | "c++" | "cc" | "hpp" => "cpp",
+        "cs" | "csharp" | "c#" => "cs",
+        "java" => "java",
+        "kotlin" | "kt" => "kt",
+        "go" | "golang" => "go",
+        "html" => "html",
+        "css" => "css",
+        "sql" => "sql",
+        "lua" => "lua",
+        "ruby" | "rb" => "rb",
+        "php" => "php",
+        "swift" => "swift",
+        "xml" => "xml",
+        "markdown" | "md" => "md",
+        "diff" | "patch" => "diff",
+        "dockerfile" | "docker" => "dockerfile",
+        _ => "txt",
+    }
+}
+
+/// Shortens `text` to at most `max` characters, adding an ellipsis if cut.
+pub fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        text.to_string()
+    } else {
+        let mut cut: String = text.chars().take(max.saturating_sub(1)).collect();
+        cut.push('…');
+        cut
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIMIT: usize = 200;
+
+    fn fence_lines(page: &str) -> usize {
+        page.lines()
+            .filter(|line| line.trim_start().starts_with(FENCE))
+            .count()
+    }
+
+    /// Every page fits and closes the code blocks it opens.
+    fn assert_pages_valid(pages: &[String]) {
+        assert!(!pages.is_empty());
+        for page in pages {
+            assert!(page.len() <= LIMIT, "page of {} bytes", page.len());
+            assert!(
+                fence_lines(page).is_multiple_of(2),
+                "unbalanced fences in {:?}",
+                page
+            );
+        }
+    }
+
+    #[test]
+    fn short_text_is_one_page() {
+        assert_eq!(
+            split_message("hello\n\nworld", LIMIT),
+            vec!["hello\n\nworld"]
+        );
+    }
+
+    #[test]
+    fn prefers_paragraph_breaks() {
+        let first = "a ".repeat(60);
+        let second = "b ".repeat(60);
+        let text = format!("{}\n\n{}", first.trim(), second.trim());
+        assert_eq!(
+            split_message(&text, LIMIT),
+            vec![first.trim().to_string(), second.trim().to_string()]
+        );
+    }
+
+    #[test]
+    fn fence_crossing_a_page_boundary_is_reopened() {
+        let code: Vec<String> = (0..60).map(|i| format!("let x{} = {};", i, i)).collect();
+        let text = format!("Intro\n\n```rust\n{}\n```\nAfter", code.join("\n"));
+        let pages = split_message(&text, LIMIT);
+        assert_pages_valid(&pages);
+        assert!(pages.len() > 2);
+        for page in &pages[1..pages.len() - 1] {
+            assert!(page.starts_with("```rust\n"), "not reopened: {:?}", page);
+        }
+
+        // No line of code is lost or duplicated
+        let kept: Vec<&str> = pages
+            .iter()
+            .flat_map(|page| page.lines())
+            .filter(|line| line.starts_with("let "))
+            .collect();
+        assert_eq!(kept, code);
+        assert!(pages.last().unwrap().ends_with("After"));
+    }
+
+    #[test]
+    fn overlong_line_is_wrapped() {
+        let line = "x".repeat(LIMIT * 3);
+        let pages = split_message(&line, LIMIT);
+        assert_pages_valid(&pages);
+        // Wrapped pieces become separate lines, which may share a page
+        assert_eq!(pages.concat().replace('\n', ""), line);
+
+        let words = "word ".repeat(100);
+        let pages = split_message(words.trim(), LIMIT);
+        assert_pages_valid(&pages);
+        for page in &pages {
+            assert!(page.ends_with("word") && page.starts_with("word"));
+        }
+    }
+
+    #[test]
+    fn multibyte_characters_at_the_split_point() {
+        for text in [
+            "é".repeat(LIMIT),
+            "🦀".repeat(LIMIT),
+            format!("a{}", "é".repeat(LIMIT)),
+        ] {
+            let pages = split_message(&text, LIMIT);
+            assert_pages_valid(&pages);
+            assert_eq!(pages.concat().replace('\n', ""), text);
+        }
+    }
+
+    #[test]
+    fn unterminated_fence_is_closed() {
+        assert_eq!(
+            split_message("```py\nprint(1)", LIMIT),
+            vec!["```py\nprint(1)\n```"]
+        );
+
+        let code = "print(1)\n".repeat(60);
+        let pages = split_message(&format!("```py\n{}", code), LIMIT);
+        assert_pages_valid(&pages);
+        assert!(pages.iter().all(|page| page.starts_with("```py\n")));
+    }
+
+    #[test]
+    fn extracts_long_closed_blocks_only() {
+        let long = "fn main() {}\n".repeat(10);
+        let text = format!(
+            "Here:\n```rust\n{}```\nShort:\n```\nx\n```\nOpen:\n```js\n{}",
+            long, long
+        );
+        let (out, files) = extract_code_blocks(&text, 50);
+
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].name, "snippet1.rs");
+        assert_eq!(files[0].content, long.trim_end());
+        assert!(out.starts_with("Here:\n*(code attached as `snippet1.rs`)*\nShort:\n```\nx\n```"));
+        // The unterminated block is still streaming and stays in the text
+        assert!(out.ends_with(&format!("Open:\n```js\n{}", long.trim_end())));
+    }
+
+    #[test]
+    fn truncate_counts_characters() {
+        assert_eq!(truncate("héllo", 5), "héllo");
+        assert_eq!(truncate("héllo wörld", 6), "héllo…");
+    }
+}
@@ -1,3 +1,16 @@
+pub mod attachments;
 pub mod bot;
+pub mod chart;
+pub mod embed;
 pub mod git;
+pub mod github;
+pub mod llm;
+pub mod markdown;
 pub mod server;
+pub mod sse;
+pub mod stream_output;
+pub mod template;
+#[cfg(test)]
+pub mod test_server;
+pub mod webhook;
+pub mod youtube;
@@ -128,7 +128,7 @@ async fn connect(host: &str, port: u16) -> Result<TcpStream, PingError> {
         .map_err(PingError::HostResolutionFailed)?;
     let addr = lookup
         .next()
-        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::Other, "no addresses found"))?
+        .ok_or_else(|| std::io::Error::other("no addresses found"))?
         .ip();
 
     match timeout(CONNECT_TIMEOUT, TcpStream::connect((addr, port))).await {
@@ -0,0 +1,271 @@
+use std::collections::VecDeque;
+
+use serenity::futures::{Stream, StreamExt, stream};
+
+/// A dispatched Server-Sent Event.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SseEvent {
+    /// The `event:` field, None for the default "message" type.
+    pub event: Option<String>,
+    /// All `data:` lines of the event joined with `\n`.
+    pub data: String,
+    /// The last `id:` seen on the stream.
+    pub id: Option<String>,
+}
+
+/// Incremental Server-Sent Events decoder following the WHATWG event stream format.
+///
+/// Bytes can be fed in arbitrary chunks; lines split across chunks are buffered
+/// until they are complete. Handles `\n`, `\r\n` and `\r` line endings, comments,
+/// multi-line `data:` and the `event:`/`id:` fields.
+#[derive(Debug, Default)]
+pub struct SseDecoder {
+    buffer: Vec<u8>,
+    /// The previous chunk ended on `\r`, so a leading `\n` belongs to that line ending.
+    skip_lf: bool,
+    event: Option<String>,
+    data: String,
+    has_data: bool,
+    last_id: Option<String>,
+}
+
+impl SseDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feeds the next chunk of bytes and returns all events completed by it.
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        let mut events = Vec::new();
+        let mut chunk = chunk;
+
+        if self.skip_lf && !chunk.is_empty() {
+            if chunk[0] == b'\n' {
+                chunk = &chunk[1..];
+            }
+            self.skip_lf = false;
+        }
+
+        let mut start = 0;
+        let mut i = 0;
+        while i < chunk.len() {
+            match chunk[i] {
+                b'\n' | b'\r' => {
+                    self.buffer.extend_from_slice(&chunk[start..i]);
+                    let line = std::mem::take(&mut self.buffer);
+                    if let Some(event) = self.process_line(&line) {
+                        events.push(event);
+                    }
+
+                    if chunk[i] == b'\r' {
+                        match chunk.get(i + 1) {
+                            Some(b'\n') => i += 1,
+                            Some(_) => {}
+                            None => self.skip_lf = true,
+                        }
+                    }
+                    i += 1;
+                    start = i;
+                }
+                _ => i += 1,
+            }
+        }
+        self.buffer.extend_from_slice(&chunk[start..]);
+
+        events
+    }
+
+    /// Ends the stream, dispatching a pending event whose terminating blank line
+    /// is missing. Strictly it should be discarded, but some servers omit it.
+    pub fn finish(&mut self) -> Option<SseEvent> {
+        let line = std::mem::take(&mut self.buffer);
+        if !line.is_empty() {
+            self.process_line(&line);
+        }
+        self.dispatch()
+    }
+
+    fn process_line(&mut self, line: &[u8]) -> Option<SseEvent> {
+        if line.is_empty() {
+            return self.dispatch();
+        }
+        if line[0] == b':' {
+            // Comment, often used as keep-alive
+            return None;
+        }
+
+        let line = String::from_utf8_lossy(line);
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line.as_ref(), ""),
+        };
+
+        match field {
+            "event" => self.event = Some(value.to_string()),
+            "data" => {
+                if self.has_data {
+                    self.data.push('\n');
+                }
+                self.data.push_str(value);
+                self.has_data = true;
+            }
+            "id" if !value.contains('\0') => self.last_id = Some(value.to_string()),
+            // `retry` and unknown fields are ignored
+            _ => {}
+        }
+        None
+    }
+
+    fn dispatch(&mut self) -> Option<SseEvent> {
+        let event = self.event.take();
+        if !self.has_data {
+            return None;
+        }
+        self.has_data = false;
+        Some(SseEvent {
+            event,
+            data: std::mem::take(&mut self.data),
+            id: self.last_id.clone(),
+        })
+    }
+}
+
+/// Decodes a stream of byte chunks, e.g. `reqwest::Response::bytes_stream`, into events.
+pub fn decode<S, B, E>(chunks: S) -> impl Stream<Item = Result<SseEvent, E>> + Send
+where
+    S: Stream<Item = Result<B, E>> + Send + 'static,
+    B: AsRef<[u8]>,
+    E: Send,
+{
+    let state = (Box::pin(chunks), SseDecoder::new(), VecDeque::new(), false);
+
+    stream::unfold(
+        state,
+        |(mut chunks, mut decoder, mut pending, mut ended)| async move {
+            loop {
+                if let Some(event) = pending.pop_front() {
+                    return Some((Ok(event), (chunks, decoder, pending, ended)));
+                }
+                if ended {
+                    return None;
+                }
+                match chunks.next().await {
+                    Some(Ok(chunk)) => pending.extend(decoder.feed(chunk.as_ref())),
+                    Some(Err(e)) => return Some((Err(e), (chunks, decoder, pending, ended))),
+                    None => {
+                        ended = true;
+                        pending.extend(decoder.finish());
+                    }
+                }
+            }
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(event: Option<&str>, data: &str, id: Option<&str>) -> SseEvent {
+        SseEvent {
+            event: event.map(str::to_string),
+            data: data.to_string(),
+            id: id.map(str::to_string),
+        }
+    }
+
+    /// Decodes `input` split into two chunks at every byte offset, and byte by byte,
+    /// checking that every split yields `expected`.
+    fn assert_decodes(input: &str, expected: &[SseEvent]) {
+        let bytes = input.as_bytes();
+        for split in 0..=bytes.len() {
+            let mut decoder = SseDecoder::new();
+            let mut events = decoder.feed(&bytes[..split]);
+            events.extend(decoder.feed(&bytes[split..]));
+            events.extend(decoder.finish());
+            assert_eq!(events, expected, "split at {}", split);
+        }
+
+        let mut decoder = SseDecoder::new();
+        let mut events: Vec<_> = bytes.iter().flat_map(|b| decoder.feed(&[*b])).collect();
+        events.extend(decoder.finish());
+        assert_eq!(events, expected, "byte by byte");
+    }
+
+    #[test]
+    fn crlf_line_endings() {
+        assert_decodes(
+            "data: a\r\n\r\ndata: b\r\n\r\n",
+            &[event(None, "a", None), event(None, "b", None)],
+        );
+    }
+
+    #[test]
+    fn bare_cr_line_endings() {
+        assert_decodes(
+            "data: a\r\rdata: b\r\r",
+            &[event(None, "a", None), event(None, "b", None)],
+        );
+    }
+
+    #[test]
+    fn multi_line_data() {
+        assert_decodes(
+            "data: one\ndata:two\ndata\n\n",
+            &[event(None, "one\ntwo\n", None)],
+        );
+    }
+
+    #[test]
+    fn comments_are_skipped() {
+        assert_decodes(
+            ": keep-alive\n\ndata: x\n: in between\n\n:\n\n",
+            &[event(None, "x", None)],
+        );
+    }
+
+    #[test]
+    fn event_and_id_fields() {
+        assert_decodes(
+            "event: update\nid: 7\ndata: x\n\ndata: y\n\nevent: skipped\n\n",
+            &[
+                event(Some("update"), "x", Some("7")),
+                event(None, "y", Some("7")),
+            ],
+        );
+    }
+
+    #[test]
+    fn id_with_nul_is_ignored() {
+        assert_decodes(
+            "id: 1\ndata: a\n\nid: bad\0id\ndata: b\n\n",
+            &[event(None, "a", Some("1")), event(None, "b", Some("1"))],
+        );
+    }
+
+    #[test]
+    fn finish_flushes_last_event() {
+        assert_decodes(
+            "data: a\n\ndata: tail",
+            &[event(None, "a", None), event(None, "tail", None)],
+        );
+        assert_decodes(
+            "event: end\ndata: tail\n",
+            &[event(Some("end"), "tail", None)],
+        );
+        assert_decodes("data: tail\r", &[event(None, "tail", None)]);
+    }
+
+    #[tokio::test]
+    async fn decodes_streams() {
+        let chunks = stream::iter(vec![
+            Ok::<_, ()>("data: a\r".as_bytes()),
+            Ok("\n\r\ndata: b".as_bytes()),
+        ]);
+        let events: Vec<_> = decode(chunks).collect().await;
+        assert_eq!(
+            events,
+            vec![Ok(event(None, "a", None)), Ok(event(None, "b", None))]
+        );
+    }
+}
@@ -0,0 +1,235 @@
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use poise::{CreateReply, ReplyHandle};
+use serenity::all::{
+    ChannelId, CreateAttachment, CreateEmbed, CreateMessage, EditMessage, Http, Message,
+};
+
+use crate::{
+    Context, Error,
+    utils::markdown::{self, CodeFile},
+};
+
+/// Minimum time between two edits of the streamed messages.
+const EDIT_INTERVAL: Duration = Duration::from_millis(1500);
+/// Page size, below Discord's 2000 character limit to leave room for a progress note.
+const PAGE_LIMIT: usize = 1900;
+/// Code blocks longer than this are sent as files once the answer is complete.
+const CODE_ATTACHMENT_LIMIT: usize = 1500;
+const PROGRESS_NOTE: &str = "\n*…*";
+
+/// Where a streamed answer is written to. Index 0 is the initial message.
+#[async_trait]
+pub trait MessageSink: Send {
+    /// Sends a new message after the existing ones.
+    async fn send(&mut self, content: String) -> Result<(), Error>;
+    /// Replaces the content of the message at `index`.
+    async fn edit(&mut self, index: usize, content: String) -> Result<(), Error>;
+    /// Deletes the last message.
+    async fn pop(&mut self) -> Result<(), Error>;
+    /// Sends files in a message of their own.
+    async fn attach(&mut self, files: Vec<CreateAttachment>) -> Result<(), Error>;
+}
+
+/// Writes a growing text into as many messages as it needs, editing them
+/// at most every [`EDIT_INTERVAL`].
+pub struct StreamOutput<S: MessageSink> {
+    sink: S,
+    shown: Vec<String>,
+    last_edit: Option<Instant>,
+}
+
+impl<S: MessageSink> StreamOutput<S> {
+    /// `sink` must already contain the initial message.
+    pub fn new(sink: S) -> Self {
+        Self {
+            sink,
+            shown: vec![String::new()],
+            last_edit: None,
+        }
+    }
+
+    pub fn sink(&self) -> &S {
+        &self.sink
+    }
+
+    /// Shows the text received so far, unless the last edit was too recent.
+    pub async fn update(&mut self, text: &str) -> Result<(), Error> {
+        if self
+            .last_edit
+            .is_some_and(|last| last.elapsed() < EDIT_INTERVAL)
+        {
+            return Ok(());
+        }
+
+        let mut pages = markdown::split_message(text, PAGE_LIMIT);
+        if let Some(last) = pages.last_mut() {
+            last.push_str(PROGRESS_NOTE);
+        }
+        self.render(pages).await
+    }
+
+    /// Shows the complete text, moving big code blocks into attachments.
+    pub async fn finish(&mut self, text: &str) -> Result<(), Error> {
+        let (text, files) = markdown::extract_code_blocks(text, CODE_ATTACHMENT_LIMIT);
+        let mut pages = markdown::split_message(&text, PAGE_LIMIT);
+        if pages.is_empty() {
+            pages.push("*(empty response)*".to_string());
+        }
+        self.render(pages).await?;
+
+        if !files.is_empty() {
+            let files = files
+                .into_iter()
+                .map(|CodeFile { name, content }| CreateAttachment::bytes(content, name))
+                .collect();
+            self.sink.attach(files).await?;
+        }
+        Ok(())
+    }
+
+    async fn render(&mut self, pages: Vec<String>) -> Result<(), Error> {
+        for (i, page) in pages.iter().enumerate() {
+            match self.shown.get(i) {
+                Some(shown) if shown == page => {}
+                Some(_) => self.sink.edit(i, page.clone()).await?,
+                None => self.sink.send(page.clone()).await?,
+            }
+        }
+        while self.shown.len() > pages.len().max(1) {
+            self.sink.pop().await?;
+            self.shown.pop();
+        }
+
+        self.shown = pages;
+        self.last_edit = Some(Instant::now());
+        Ok(())
+    }
+}
+
+/// Streams into the replies of a slash command. The first reply keeps `header`.
+pub struct ReplySink<'a> {
+    ctx: Context<'a>,
+    ephemeral: bool,
+    header: Option<CreateEmbed>,
+    replies: Vec<ReplyHandle<'a>>,
+}
+
+impl<'a> ReplySink<'a> {
+    pub fn new(
+        ctx: Context<'a>,
+        ephemeral: bool,
+        first: ReplyHandle<'a>,
+        header: Option<CreateEmbed>,
+    ) -> Self {
+        Self {
+            ctx,
+            ephemeral,
+            header,
+            replies: vec![first],
+        }
+    }
+
+    /// The initial reply.
+    pub fn first(&self) -> &ReplyHandle<'a> {
+        &self.replies[0]
+    }
+}
+
+#[async_trait]
+impl MessageSink for ReplySink<'_> {
+    async fn send(&mut self, content: String) -> Result<(), Error> {
+        let reply = self
+            .ctx
+            .send(
+                CreateReply::default()
+                    .content(content)
+                    .ephemeral(self.ephemeral),
+            )
+            .await?;
+        self.replies.push(reply);
+        Ok(())
+    }
+
+    async fn edit(&mut self, index: usize, content: String) -> Result<(), Error> {
+        let mut builder = CreateReply::default().content(content);
+        if index == 0
+            && let Some(header) = &self.header
+        {
+            builder = builder.embed(header.clone());
+        }
+        self.replies[index].edit(self.ctx, builder).await?;
+        Ok(())
+    }
+
+    async fn pop(&mut self) -> Result<(), Error> {
+        if self.replies.len() > 1
+            && let Some(reply) = self.replies.pop()
+        {
+            reply.delete(self.ctx).await?;
+        }
+        Ok(())
+    }
+
+    async fn attach(&mut self, files: Vec<CreateAttachment>) -> Result<(), Error> {
+        let mut builder = CreateReply::default().ephemeral(self.ephemeral);
+        for file in files {
+            builder = builder.attachment(file);
+        }
+        self.ctx.send(builder).await?;
+        Ok(())
+    }
+}
+
+/// Streams into plain channel messages, e.g. inside a thread.
+pub struct ChannelSink<'a> {
+    http: &'a Http,
+    channel_id: ChannelId,
+    messages: Vec<Message>,
+}
+
+impl<'a> ChannelSink<'a> {
+    pub fn new(http: &'a Http, first: Message) -> Self {
+        Self {
+            http,
+            channel_id: first.channel_id,
+            messages: vec![first],
+        }
+    }
+}
+
+#[async_trait]
+impl MessageSink for ChannelSink<'_> {
+    async fn send(&mut self, content: String) -> Result<(), Error> {
+        let message = self
+            .channel_id
+            .send_message(self.http, CreateMessage::new().content(content))
+            .await?;
+        self.messages.push(message);
+        Ok(())
+    }
+
+    async fn edit(&mut self, index: usize, content: String) -> Result<(), Error> {
+        self.messages[index]
+            .edit(self.http, EditMessage::new().content(content))
+            .await?;
+        Ok(())
+    }
+
+    async fn pop(&mut self) -> Result<(), Error> {
+        if self.messages.len() > 1
+            && let Some(message) = self.messages.pop()
+        {
+            message.delete(self.http).await?;
+        }
+        Ok(())
+    }
+
+    async fn attach(&mut self, files: Vec<CreateAttachment>) -> Result<(), Error> {
+        self.channel_id
+            .send_message(self.http, CreateMessage::new().add_files(files))
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,216 @@
+use std::fmt::Write;
+
+use chrono::{DateTime, Utc};
+
+use crate::Context;
+
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Values available to placeholders when rendering a template.
+///
+/// Supported placeholders:
+/// - `{user}` mention of the invoking user, `{username}` their display name
+/// - `{channel}` mention of the current channel, `{server}` the guild name
+/// - `{date}` / `{date:<strftime format>}` the current UTC time
+/// - `{arg1}`, `{arg2}`, ... single arguments and `{args}` all of them
+///
+/// `{{` and `}}` produce literal braces and `\n` a line break.
+/// Unknown placeholders are left untouched.
+pub struct TemplateVars {
+    pub user: String,
+    pub username: String,
+    pub channel: String,
+    pub server: String,
+    pub args: Vec<String>,
+    pub now: DateTime<Utc>,
+}
+
+impl TemplateVars {
+    /// Collects the variables of a command invocation, with `args` split like a shell would.
+    pub fn from_ctx(ctx: Context<'_>, args: Option<&str>) -> Self {
+        let server = ctx
+            .guild()
+            .map(|g| g.name.clone())
+            .unwrap_or_else(|| "Direct Messages".to_string());
+
+        Self {
+            user: format!("<@{}>", ctx.author().id),
+            username: ctx.author().display_name().to_string(),
+            channel: format!("<#{}>", ctx.channel_id()),
+            server,
+            args: args.map(split_args).unwrap_or_default(),
+            now: Utc::now(),
+        }
+    }
+
+    fn resolve(&self, name: &str) -> Option<String> {
+        match name {
+            "user" => Some(self.user.clone()),
+            "username" => Some(self.username.clone()),
+            "channel" => Some(self.channel.clone()),
+            "server" => Some(self.server.clone()),
+            "args" => Some(self.args.join(" ")),
+            "date" => format_date(self.now, DEFAULT_DATE_FORMAT),
+            _ => match name.strip_prefix("date:") {
+                Some(format) => format_date(self.now, format),
+                None => name
+                    .strip_prefix("arg")
+                    .and_then(|n| n.parse::<usize>().ok())
+                    .filter(|n| *n > 0)
+                    .map(|n| self.args.get(n - 1).cloned().unwrap_or_default()),
+            },
+        }
+    }
+}
+
+/// Formats `time` with a strftime string, returning None if the format is invalid.
+fn format_date(time: DateTime<Utc>, format: &str) -> Option<String> {
+    let mut out = String::new();
+    write!(out, "{}", time.format(format)).ok()?;
+    Some(out)
+}
+
+/// Renders `template`, replacing placeholders with values from `vars`.
+pub fn render(template: &str, vars: &TemplateVars) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(pos) = rest.find(['{', '}', '\\']) {
+        out.push_str(&rest[..pos]);
+        let tail = &rest[pos..];
+
+        if let Some(after) = tail.strip_prefix("{{") {
+            out.push('{');
+            rest = after;
+        } else if let Some(after) = tail.strip_prefix("}}") {
+            out.push('}');
+            rest = after;
+        } else if let Some(after) = tail.strip_prefix("\\n") {
+            out.push('\n');
+            rest = after;
+        } else if let Some(name) = placeholder_name(tail) {
+            match vars.resolve(name) {
+                Some(value) => out.push_str(&value),
+                None => out.push_str(&tail[..name.len() + 2]),
+            }
+            rest = &tail[name.len() + 2..];
+        } else {
+            out.push_str(&tail[..1]);
+            rest = &tail[1..];
+        }
+    }
+
+    out.push_str(rest);
+    out
+}
+
+/// Returns the name inside a `{name}` placeholder at the start of `text`.
+fn placeholder_name(text: &str) -> Option<&str> {
+    let inner = text.strip_prefix('{')?;
+    let end = inner.find(['{', '}'])?;
+    inner[end..].starts_with('}').then(|| &inner[..end])
+}
+
+/// Splits an argument string on whitespace, keeping "double quoted" parts together.
+pub fn split_args(input: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut has_token = false;
+
+    for c in input.chars() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                has_token = true;
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if has_token {
+                    args.push(std::mem::take(&mut current));
+                    has_token = false;
+                }
+            }
+            c => {
+                current.push(c);
+                has_token = true;
+            }
+        }
+    }
+
+    if has_token {
+        args.push(current);
+    }
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(args: &str) -> TemplateVars {
+        TemplateVars {
+            user: "<@1>".into(),
+            username: "Kybe".into(),
+            channel: "<#2>".into(),
+            server: "Bots".into(),
+            args: split_args(args),
+            now: DateTime::parse_from_rfc3339("2026-10-18T09:05:00Z")
+                .unwrap()
+                .with_timezone(&Utc),
+        }
+    }
+
+    #[test]
+    fn renders_placeholders() {
+        let vars = vars("first \"second one\"");
+        for (template, expected) in [
+            ("Hi {user} ({username})", "Hi <@1> (Kybe)"),
+            ("{channel} on {server}", "<#2> on Bots"),
+            ("{arg1}/{arg2}/{args}", "first/second one/first second one"),
+            ("[{arg3}]", "[]"),
+            ("{date}", "2026-10-18"),
+            ("{date:%H:%M}", "09:05"),
+        ] {
+            assert_eq!(render(template, &vars), expected, "{}", template);
+        }
+    }
+
+    #[test]
+    fn leaves_unknown_placeholders_verbatim() {
+        let vars = vars("");
+        for template in [
+            "{unknown}",
+            "{arg0}",
+            "{argx}",
+            "{date:%Q}",
+            "{ user }",
+            "{}",
+            "{user",
+            "user}",
+            "\\t",
+        ] {
+            assert_eq!(render(template, &vars), template);
+        }
+    }
+
+    #[test]
+    fn escapes_braces_and_line_breaks() {
+        let vars = vars("");
+        assert_eq!(render("{{user}}", &vars), "{user}");
+        assert_eq!(render("{{{user}}}", &vars), "{<@1>}");
+        assert_eq!(render("a\\nb\\\\", &vars), "a\nb\\\\");
+        assert_eq!(render("ünï {user} ✓", &vars), "ünï <@1> ✓");
+    }
+
+    #[test]
+    fn splits_quoted_arguments() {
+        assert_eq!(
+            split_args("one \"two three\"  four"),
+            ["one", "two three", "four"]
+        );
+        assert_eq!(split_args("a\"b c\"d"), ["ab cd"]);
+        assert_eq!(split_args("\"\" x"), ["", "x"]);
+        assert_eq!(split_args("\"unterminated quote"), ["unterminated quote"]);
+        assert!(split_args("   ").is_empty());
+    }
+}
@@ -0,0 +1,84 @@
+//! A tiny HTTP server standing in for external APIs in tests.
+
+use std::time::Duration;
+
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::TcpListener,
+};
+
+/// A canned response for requests whose path starts with `path`.
+pub struct Route {
+    pub path: &'static str,
+    pub status: u16,
+    pub content_type: &'static str,
+    /// Body pieces, written one by one so clients see them as separate chunks.
+    pub chunks: Vec<&'static str>,
+}
+
+impl Route {
+    pub fn ok(path: &'static str, content_type: &'static str, chunks: Vec<&'static str>) -> Self {
+        Self {
+            path,
+            status: 200,
+            content_type,
+            chunks,
+        }
+    }
+}
+
+/// Serves `routes` on a local port until the test ends and returns the base URL.
+/// Unknown paths get a 404. Bodies end when the connection closes.
+pub async fn serve(routes: Vec<Route>) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let routes = std::sync::Arc::new(routes);
+
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let routes = routes.clone();
+            tokio::spawn(async move {
+                let mut reader = BufReader::new(&mut stream);
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).await.unwrap();
+                let mut content_length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).await.unwrap();
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    if let Some((key, value)) = line.split_once(':')
+                        && key.eq_ignore_ascii_case("content-length")
+                    {
+                        content_length = value.trim().parse().unwrap_or(0);
+                    }
+                }
+                let mut body = vec![0; content_length];
+                tokio::io::AsyncReadExt::read_exact(&mut reader, &mut body)
+                    .await
+                    .unwrap();
+
+                let path = request_line.split_whitespace().nth(1).unwrap_or("/");
+                let route = routes.iter().find(|route| path.starts_with(route.path));
+                let (status, content_type, chunks) = match route {
+                    Some(route) => (route.status, route.content_type, route.chunks.clone()),
+                    None => (404, "text/plain", vec!["not found"]),
+                };
+                let head = format!(
+                    "HTTP/1.1 {} X\r\nContent-Type: {}\r\nConnection: close\r\n\r\n",
+                    status, content_type
+                );
+                stream.write_all(head.as_bytes()).await.unwrap();
+                for chunk in chunks {
+                    stream.write_all(chunk.as_bytes()).await.unwrap();
+                    stream.flush().await.unwrap();
+                    tokio::time::sleep(Duration::from_millis(5)).await;
+                }
+                stream.shutdown().await.ok();
+            });
+        }
+    });
+
+    format!("http://{}", addr)
+}
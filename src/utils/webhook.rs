@@ -0,0 +1,234 @@
+use ring::hmac;
+use thiserror::Error;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::TcpStream,
+};
+
+/// Largest accepted request body. GitHub deliveries are usually far smaller.
+const MAX_BODY: usize = 5 * 1024 * 1024;
+/// Largest accepted request line plus headers.
+const MAX_HEAD: usize = 16 * 1024;
+
+#[derive(Error, Debug)]
+pub enum WebhookError {
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Malformed request")]
+    Malformed,
+    #[error("Request body too large")]
+    TooLarge,
+}
+
+/// An HTTP request as delivered by a webhook sender.
+pub struct WebhookRequest {
+    pub method: String,
+    pub path: String,
+    headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl WebhookRequest {
+    /// Looks up a header, ignoring case.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Reads one HTTP/1.1 request with a `Content-Length` body.
+pub async fn read_request(stream: &mut TcpStream) -> Result<WebhookRequest, WebhookError> {
+    let mut reader = BufReader::new(stream).take((MAX_HEAD + MAX_BODY) as u64);
+
+    let mut line = String::new();
+    reader.read_line(&mut line).await?;
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(path)) = (parts.next(), parts.next()) else {
+        return Err(WebhookError::Malformed);
+    };
+    let (method, path) = (method.to_string(), path.to_string());
+
+    let mut headers = Vec::new();
+    let mut head_size = line.len();
+    loop {
+        line.clear();
+        let read = reader.read_line(&mut line).await?;
+        head_size += read;
+        if read == 0 || head_size > MAX_HEAD {
+            return Err(WebhookError::Malformed);
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        let (key, value) = header.split_once(':').ok_or(WebhookError::Malformed)?;
+        headers.push((key.trim().to_string(), value.trim().to_string()));
+    }
+
+    let request = WebhookRequest {
+        method,
+        path,
+        headers,
+        body: Vec::new(),
+    };
+    let length = match request.header("Content-Length") {
+        Some(length) => length.parse().map_err(|_| WebhookError::Malformed)?,
+        None => 0,
+    };
+    if length > MAX_BODY {
+        return Err(WebhookError::TooLarge);
+    }
+
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).await?;
+    Ok(WebhookRequest { body, ..request })
+}
+
+/// Answers with an empty response and closes the connection.
+pub async fn respond(stream: &mut TcpStream, status: u16, reason: &str) -> std::io::Result<()> {
+    let response = format!(
+        "HTTP/1.1 {} {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        status, reason
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    // from_str_radix would also take a sign like `+f`
+    if !hex.len().is_multiple_of(2) || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Checks a `sha256=<hex>` HMAC signature of the body, as sent in `X-Hub-Signature-256`.
+/// The comparison takes constant time.
+pub fn verify_signature(secret: &[u8], body: &[u8], signature: Option<&str>) -> bool {
+    let Some(tag) = signature
+        .and_then(|signature| signature.strip_prefix("sha256="))
+        .and_then(decode_hex)
+    else {
+        return false;
+    };
+    let key = hmac::Key::new(hmac::HMAC_SHA256, secret);
+    hmac::verify(&key, body, &tag).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    fn sign(secret: &[u8], body: &[u8]) -> String {
+        let key = hmac::Key::new(hmac::HMAC_SHA256, secret);
+        let tag = hmac::sign(&key, body);
+        let hex: String = tag.as_ref().iter().map(|b| format!("{:02x}", b)).collect();
+        format!("sha256={}", hex)
+    }
+
+    #[test]
+    fn verifies_signatures() {
+        let body = br#"{"action":"published"}"#;
+        let signature = sign(b"secret", body);
+
+        assert!(verify_signature(b"secret", body, Some(&signature)));
+        assert!(verify_signature(
+            b"secret",
+            body,
+            Some(&signature.to_uppercase().replace("SHA256=", "sha256="))
+        ));
+        assert!(!verify_signature(b"other", body, Some(&signature)));
+        assert!(!verify_signature(b"secret", b"{}", Some(&signature)));
+        assert!(!verify_signature(b"secret", body, None));
+        assert!(!verify_signature(b"secret", body, Some("")));
+        assert!(!verify_signature(
+            b"secret",
+            body,
+            Some(signature.trim_start_matches("sha256="))
+        ));
+        // Odd length, and a valid hex string that is one byte short
+        assert!(!verify_signature(b"secret", body, Some(&signature[..70])));
+        assert!(!verify_signature(b"secret", body, Some(&signature[..69])));
+        let non_hex = format!("{}zz", &signature[..69]);
+        assert!(!verify_signature(b"secret", body, Some(&non_hex)));
+    }
+
+    #[test]
+    fn decodes_only_hex() {
+        assert_eq!(decode_hex("00ff7A"), Some(vec![0x00, 0xff, 0x7a]));
+        assert_eq!(decode_hex(""), Some(Vec::new()));
+        assert_eq!(decode_hex("abc"), None);
+        assert_eq!(decode_hex("zz"), None);
+        assert_eq!(decode_hex("+f"), None);
+        assert_eq!(decode_hex("é1"), None);
+    }
+
+    /// Sends `request` over a local connection and reads it back.
+    async fn read(request: Vec<u8>) -> Result<WebhookRequest, WebhookError> {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut client = TcpStream::connect(addr).await.unwrap();
+            // The server may stop reading early, so a failing write is fine
+            client.write_all(&request).await.ok();
+            client.shutdown().await.ok();
+        });
+        let (mut stream, _) = listener.accept().await.unwrap();
+        read_request(&mut stream).await
+    }
+
+    #[tokio::test]
+    async fn reads_requests() {
+        let request = read(
+            b"POST /github?x=1 HTTP/1.1\r\nX-GitHub-Event: push\r\ncontent-length: 2\r\n\r\n{}"
+                .to_vec(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/github?x=1");
+        assert_eq!(request.header("x-github-event"), Some("push"));
+        assert_eq!(request.body, b"{}");
+    }
+
+    #[tokio::test]
+    async fn rejects_bad_requests() {
+        let oversized = format!(
+            "POST / HTTP/1.1\r\nContent-Length: {}\r\n\r\n",
+            MAX_BODY + 1
+        );
+        assert!(matches!(
+            read(oversized.into_bytes()).await,
+            Err(WebhookError::TooLarge)
+        ));
+
+        let mut overlong = b"POST / HTTP/1.1\r\n".to_vec();
+        for i in 0..MAX_HEAD / 16 {
+            overlong.extend(format!("X-Filler-{:05}: 0\r\n", i).bytes());
+        }
+        overlong.extend(b"\r\n");
+        assert!(matches!(read(overlong).await, Err(WebhookError::Malformed)));
+
+        let truncated = b"POST / HTTP/1.1\r\nContent-Length: 10\r\n\r\n{}".to_vec();
+        assert!(matches!(read(truncated).await, Err(WebhookError::Io(_))));
+
+        for malformed in [
+            &b"\r\n"[..],
+            b"POST / HTTP/1.1\r\nNo colon here\r\n\r\n",
+            b"POST / HTTP/1.1\r\nContent-Length: many\r\n\r\n",
+            b"POST / HTTP/1.1\r\nHost: x\r\n",
+        ] {
+            assert!(matches!(
+                read(malformed.to_vec()).await,
+                Err(WebhookError::Malformed)
+            ));
+        }
+    }
+}
@@ -0,0 +1,519 @@
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use thiserror::Error;
+
+pub mod oembed;
+pub mod url;
+
+pub use url::parse_url;
+
+const API_BASE_URL: &str = "https://www.googleapis.com/youtube/v3";
+/// Public upload feed of a channel, which costs no API quota.
+const FEED_URL: &str = "https://www.youtube.com/feeds/videos.xml";
+/// Most items the API returns per page.
+pub const MAX_PAGE_SIZE: u32 = 50;
+
+/// Represents an error while talking to the YouTube Data API.
+#[derive(Debug, Error)]
+pub enum YouTubeError {
+    #[error("Request failed: {0}")]
+    Request(#[from] reqwest::Error),
+
+    #[error("YouTube API error ({status}): {message}")]
+    Api { status: u16, message: String },
+}
+
+/// One page of a list endpoint.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListResponse<T> {
+    #[serde(default = "Vec::new")]
+    pub items: Vec<T>,
+    pub next_page_token: Option<String>,
+    pub page_info: Option<PageInfo>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PageInfo {
+    pub total_results: u64,
+}
+
+/// A video with its snippet, statistics and content details.
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct YouTubeItem {
+    pub id: String,
+    pub snippet: Snippet,
+    pub statistics: Statistics,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_details: Option<ContentDetails>,
+}
+
+/// The snippet shared by videos, playlists, playlist items, channels and search results.
+/// Fields that only some of them have are optional.
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Snippet {
+    pub title: String,
+    #[serde(default)]
+    pub channel_title: String,
+    #[serde(default)]
+    pub channel_id: Option<String>,
+    pub published_at: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub thumbnails: Thumbnails,
+    /// The `@handle` of a channel.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub custom_url: Option<String>,
+    /// The video a playlist item refers to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resource_id: Option<ResourceId>,
+    /// `live`, `upcoming` or `none` for search results.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub live_broadcast_content: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Default)]
+pub struct Thumbnails {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub high: Option<Thumbnail>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub medium: Option<Thumbnail>,
+    #[serde(default, rename = "default", skip_serializing_if = "Option::is_none")]
+    pub fallback: Option<Thumbnail>,
+}
+
+impl Thumbnails {
+    /// The URL of the largest available thumbnail.
+    pub fn best(&self) -> Option<&str> {
+        self.high
+            .as_ref()
+            .or(self.medium.as_ref())
+            .or(self.fallback.as_ref())
+            .map(|t| t.url.as_str())
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct Thumbnail {
+    pub url: String,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceId {
+    pub video_id: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Statistics {
+    pub view_count: String,
+    pub like_count: Option<String>,
+    pub comment_count: Option<String>,
+}
+
+/// A video with its snippet only.
+#[derive(Deserialize)]
+pub struct VideoSnippet {
+    pub id: String,
+    pub snippet: Snippet,
+}
+
+/// Duration and format of a video.
+#[derive(Deserialize, Serialize)]
+pub struct ContentDetails {
+    /// ISO 8601 duration, see [`parse_duration`].
+    pub duration: String,
+    /// `hd` or `sd`.
+    #[serde(default)]
+    pub definition: String,
+    /// `"true"` if the video has captions.
+    #[serde(default)]
+    pub caption: String,
+}
+
+/// A video's duration, for summing up playlists.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct VideoDuration {
+    content_details: ContentDetails,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Playlist {
+    pub id: String,
+    pub snippet: Snippet,
+    pub content_details: PlaylistDetails,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaylistDetails {
+    pub item_count: u64,
+}
+
+#[derive(Deserialize)]
+pub struct PlaylistItem {
+    pub snippet: Snippet,
+}
+
+impl PlaylistItem {
+    pub fn video_id(&self) -> Option<&str> {
+        self.snippet
+            .resource_id
+            .as_ref()
+            .and_then(|r| r.video_id.as_deref())
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Channel {
+    pub id: String,
+    pub snippet: Snippet,
+    pub statistics: ChannelStatistics,
+    pub content_details: ChannelDetails,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChannelStatistics {
+    pub view_count: String,
+    pub subscriber_count: Option<String>,
+    #[serde(default)]
+    pub hidden_subscriber_count: bool,
+    pub video_count: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChannelDetails {
+    pub related_playlists: RelatedPlaylists,
+}
+
+#[derive(Deserialize)]
+pub struct RelatedPlaylists {
+    /// The playlist containing all uploads of the channel.
+    pub uploads: String,
+}
+
+#[derive(Deserialize)]
+pub struct SearchResult {
+    pub id: SearchId,
+    pub snippet: Snippet,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchId {
+    pub video_id: Option<String>,
+    pub channel_id: Option<String>,
+    pub playlist_id: Option<String>,
+}
+
+impl SearchResult {
+    /// Link to the found video, channel or playlist.
+    pub fn url(&self) -> Option<String> {
+        self.id
+            .video_id
+            .as_deref()
+            .map(video_url)
+            .or_else(|| self.id.channel_id.as_deref().map(channel_url))
+            .or_else(|| self.id.playlist_id.as_deref().map(playlist_url))
+    }
+}
+
+/// How to look up a channel.
+pub enum ChannelRef<'a> {
+    Id(&'a str),
+    /// An `@handle`.
+    Handle(&'a str),
+    /// A legacy username.
+    Username(&'a str),
+}
+
+#[derive(Deserialize)]
+struct ErrorResponse {
+    error: ErrorBody,
+}
+
+#[derive(Deserialize)]
+struct ErrorBody {
+    message: String,
+}
+
+pub fn video_url(id: &str) -> String {
+    format!("https://youtu.be/{}", id)
+}
+
+pub fn playlist_url(id: &str) -> String {
+    format!("https://www.youtube.com/playlist?list={}", id)
+}
+
+pub fn channel_url(id: &str) -> String {
+    format!("https://www.youtube.com/channel/{}", id)
+}
+
+/// Replaces the HTML and XML entities YouTube uses in titles.
+pub fn unescape_html(text: &str) -> String {
+    text.replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&#x27;", "'")
+        .replace("&apos;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+/// A video listed in a channel's feed.
+pub struct FeedEntry {
+    pub video_id: String,
+    pub title: String,
+}
+
+static FEED_TAG_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"<(yt:videoId|title)>([^<]*)</").expect("Invalid regex"));
+
+/// Parses the entries of a channel's Atom feed.
+fn parse_feed(xml: &str) -> Vec<FeedEntry> {
+    let tag = |entry: &str, name: &str| {
+        FEED_TAG_REGEX
+            .captures_iter(entry)
+            .find(|caps| &caps[1] == name)
+            .map(|caps| unescape_html(&caps[2]))
+            .unwrap_or_default()
+    };
+
+    xml.split("<entry>")
+        .skip(1)
+        .map(|entry| FeedEntry {
+            video_id: tag(entry, "yt:videoId"),
+            title: tag(entry, "title"),
+        })
+        .filter(|entry| !entry.video_id.is_empty())
+        .collect()
+}
+
+/// Fetches a channel's latest 15 uploads, newest first. Needs no API key.
+pub async fn fetch_feed(channel_id: &str) -> Result<Vec<FeedEntry>, YouTubeError> {
+    let response = reqwest::Client::new()
+        .get(FEED_URL)
+        .query(&[("channel_id", channel_id)])
+        .send()
+        .await?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(YouTubeError::Api {
+            status: status.as_u16(),
+            message: "channel feed unavailable".into(),
+        });
+    }
+    Ok(parse_feed(&response.text().await?))
+}
+
+/// The playlist containing all uploads of a channel.
+pub fn uploads_playlist_id(channel_id: &str) -> Option<String> {
+    channel_id.strip_prefix("UC").map(|id| format!("UU{}", id))
+}
+
+/// Parses an ISO 8601 duration as used by the API, e.g. `PT1H2M3S` or `P1DT2H`.
+pub fn parse_duration(iso: &str) -> Option<Duration> {
+    let rest = iso.strip_prefix('P')?;
+    let mut seconds = 0;
+    let mut number = String::new();
+    let mut in_time = false;
+
+    for c in rest.chars() {
+        match c {
+            'T' => in_time = true,
+            '0'..='9' => number.push(c),
+            unit => {
+                let value: u64 = number.parse().ok()?;
+                number.clear();
+                seconds += value
+                    * match (unit, in_time) {
+                        ('W', false) => 7 * 24 * 60 * 60,
+                        ('D', false) => 24 * 60 * 60,
+                        ('H', true) => 60 * 60,
+                        ('M', true) => 60,
+                        ('S', true) => 1,
+                        _ => return None,
+                    };
+            }
+        }
+    }
+
+    number.is_empty().then_some(Duration::from_secs(seconds))
+}
+
+/// Formats a duration as `h:mm:ss` or `m:ss`.
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    let (hours, minutes, seconds) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{}:{:02}", minutes, seconds)
+    }
+}
+
+/// Typed client for the YouTube Data API v3.
+pub struct YouTubeClient {
+    http: reqwest::Client,
+    base_url: String,
+    api_key: String,
+}
+
+impl YouTubeClient {
+    pub fn new(api_key: impl Into<String>) -> Self {
+        Self::with_base_url(API_BASE_URL, api_key)
+    }
+
+    /// Uses another API root, e.g. a local stand-in.
+    pub fn with_base_url(base_url: impl Into<String>, api_key: impl Into<String>) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            api_key: api_key.into(),
+        }
+    }
+
+    async fn list<T: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        query: &[(&str, &str)],
+    ) -> Result<ListResponse<T>, YouTubeError> {
+        let response = self
+            .http
+            .get(format!("{}/{}", self.base_url, endpoint))
+            .query(query)
+            .query(&[("key", self.api_key.as_str())])
+            .send()
+            .await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            let message = serde_json::from_str::<ErrorResponse>(&body)
+                .map(|e| e.error.message)
+                .unwrap_or(body);
+            return Err(YouTubeError::Api {
+                status: status.as_u16(),
+                message,
+            });
+        }
+        Ok(response.json().await?)
+    }
+
+    /// Fetches a video's snippet, statistics and content details.
+    /// Returns None if it does not exist.
+    pub async fn video(&self, id: &str) -> Result<Option<YouTubeItem>, YouTubeError> {
+        let response = self
+            .list(
+                "videos",
+                &[("part", "snippet,statistics,contentDetails"), ("id", id)],
+            )
+            .await?;
+        Ok(response.items.into_iter().next())
+    }
+
+    /// Fetches the snippets of up to 50 videos. Missing videos are left out.
+    pub async fn videos(&self, ids: &[&str]) -> Result<Vec<VideoSnippet>, YouTubeError> {
+        let ids = ids.join(",");
+        let response = self
+            .list("videos", &[("part", "snippet"), ("id", &ids)])
+            .await?;
+        Ok(response.items)
+    }
+
+    /// Sums up the durations of up to 50 videos.
+    pub async fn total_duration(&self, ids: &[&str]) -> Result<Duration, YouTubeError> {
+        let ids = ids.join(",");
+        let response: ListResponse<VideoDuration> = self
+            .list("videos", &[("part", "contentDetails"), ("id", &ids)])
+            .await?;
+        Ok(response
+            .items
+            .iter()
+            .filter_map(|v| parse_duration(&v.content_details.duration))
+            .sum())
+    }
+
+    /// Returns None if the playlist does not exist or is private.
+    pub async fn playlist(&self, id: &str) -> Result<Option<Playlist>, YouTubeError> {
+        let response = self
+            .list(
+                "playlists",
+                &[("part", "snippet,contentDetails"), ("id", id)],
+            )
+            .await?;
+        Ok(response.items.into_iter().next())
+    }
+
+    /// One page of a playlist's items, in playlist order.
+    pub async fn playlist_items(
+        &self,
+        playlist_id: &str,
+        page_token: Option<&str>,
+        max_results: u32,
+    ) -> Result<ListResponse<PlaylistItem>, YouTubeError> {
+        let max_results = max_results.min(MAX_PAGE_SIZE).to_string();
+        let mut query = vec![
+            ("part", "snippet"),
+            ("playlistId", playlist_id),
+            ("maxResults", &max_results),
+        ];
+        if let Some(token) = page_token {
+            query.push(("pageToken", token));
+        }
+        self.list("playlistItems", &query).await
+    }
+
+    /// Returns None if no such channel exists.
+    pub async fn channel(&self, channel: ChannelRef<'_>) -> Result<Option<Channel>, YouTubeError> {
+        let filter = match channel {
+            ChannelRef::Id(id) => ("id", id),
+            ChannelRef::Handle(handle) => ("forHandle", handle),
+            ChannelRef::Username(name) => ("forUsername", name),
+        };
+        let response = self
+            .list(
+                "channels",
+                &[("part", "snippet,statistics,contentDetails"), filter],
+            )
+            .await?;
+        Ok(response.items.into_iter().next())
+    }
+
+    /// Searches videos, channels and playlists. `kind` limits the results
+    /// to `video`, `channel` or `playlist`. Costs 100 quota units per page.
+    pub async fn search(
+        &self,
+        query: &str,
+        kind: Option<&str>,
+        page_token: Option<&str>,
+        max_results: u32,
+    ) -> Result<ListResponse<SearchResult>, YouTubeError> {
+        let max_results = max_results.min(MAX_PAGE_SIZE).to_string();
+        let mut params = vec![
+            ("part", "snippet"),
+            ("q", query),
+            ("maxResults", &max_results),
+        ];
+        if let Some(kind) = kind {
+            params.push(("type", kind));
+        }
+        if let Some(token) = page_token {
+            params.push(("pageToken", token));
+        }
+        self.list("search", &params).await
+    }
+}
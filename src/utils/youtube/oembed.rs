@@ -0,0 +1,305 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Deserialize;
+
+use super::{YouTubeError, unescape_html};
+
+/// Site root the public lookups go to unless configured otherwise.
+const PUBLIC_BASE_URL: &str = "https://www.youtube.com";
+
+static META_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"<meta\s[^>]*>").expect("Invalid regex"));
+static LINK_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"<link\s[^>]*>").expect("Invalid regex"));
+static ATTRIBUTE_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"([\w:-]+)="([^"]*)""#).expect("Invalid regex"));
+
+/// What can be learned about a video without an API key.
+pub struct PublicVideo {
+    pub title: String,
+    pub channel_title: Option<String>,
+    pub channel_url: Option<String>,
+    pub thumbnail: Option<String>,
+    pub description: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct OEmbedResponse {
+    title: String,
+    author_name: Option<String>,
+    author_url: Option<String>,
+    thumbnail_url: Option<String>,
+}
+
+/// The attributes of an HTML tag.
+fn attributes(tag: &str) -> Vec<(&str, String)> {
+    ATTRIBUTE_REGEX
+        .captures_iter(tag)
+        .map(|caps| {
+            (
+                caps.get(1).map_or("", |m| m.as_str()),
+                unescape_html(&caps[2]),
+            )
+        })
+        .collect()
+}
+
+/// Reads the OpenGraph metadata and the channel name of a watch page.
+fn parse_page(html: &str) -> Option<PublicVideo> {
+    let mut properties = Vec::new();
+    for tag in META_REGEX.find_iter(html) {
+        let attributes = attributes(tag.as_str());
+        let key = attributes
+            .iter()
+            .find(|(name, _)| *name == "property" || *name == "name")
+            .map(|(_, value)| value.clone());
+        let content = attributes
+            .into_iter()
+            .find(|(name, _)| *name == "content")
+            .map(|(_, value)| value);
+        if let (Some(key), Some(content)) = (key, content) {
+            properties.push((key, content));
+        }
+    }
+    let property = |key: &str| {
+        properties
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.clone())
+    };
+
+    // The channel is linked as `<link itemprop="name" content="...">` inside the author block
+    let channel_title = LINK_REGEX.find_iter(html).find_map(|tag| {
+        let attributes = attributes(tag.as_str());
+        attributes
+            .iter()
+            .any(|(name, value)| *name == "itemprop" && value == "name")
+            .then(|| {
+                attributes
+                    .into_iter()
+                    .find(|(name, _)| *name == "content")
+                    .map(|(_, value)| value)
+            })
+            .flatten()
+    });
+
+    Some(PublicVideo {
+        title: property("og:title")?,
+        channel_title,
+        channel_url: None,
+        thumbnail: property("og:image"),
+        description: property("og:description"),
+    })
+}
+
+/// Completes the oEmbed answer with the watch page's metadata.
+fn merge(oembed: OEmbedResponse, page: Option<PublicVideo>) -> PublicVideo {
+    PublicVideo {
+        title: oembed.title,
+        channel_title: oembed
+            .author_name
+            .or_else(|| page.as_ref().and_then(|p| p.channel_title.clone())),
+        channel_url: oembed.author_url,
+        thumbnail: oembed
+            .thumbnail_url
+            .or_else(|| page.as_ref().and_then(|p| p.thumbnail.clone())),
+        description: page.and_then(|p| p.description),
+    }
+}
+
+/// Looks up videos through the public oEmbed endpoint and watch pages, without an API key.
+pub struct PublicClient {
+    http: reqwest::Client,
+    base_url: String,
+}
+
+impl Default for PublicClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PublicClient {
+    pub fn new() -> Self {
+        Self::with_base_url(PUBLIC_BASE_URL)
+    }
+
+    /// Uses another site root, e.g. a local stand-in.
+    pub fn with_base_url(base_url: impl Into<String>) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+        }
+    }
+
+    fn watch_url(&self, video_id: &str) -> String {
+        format!("{}/watch?v={}", self.base_url, video_id)
+    }
+
+    async fn oembed(&self, video_id: &str) -> Result<OEmbedResponse, YouTubeError> {
+        let response = self
+            .http
+            .get(format!("{}/oembed", self.base_url))
+            .query(&[
+                ("url", self.watch_url(video_id).as_str()),
+                ("format", "json"),
+            ])
+            .send()
+            .await?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(YouTubeError::Api {
+                status: status.as_u16(),
+                message: "oEmbed lookup failed".into(),
+            });
+        }
+        Ok(response.json().await?)
+    }
+
+    /// Returns None if the page has no OpenGraph title, e.g. for removed videos.
+    async fn page(&self, video_id: &str) -> Result<Option<PublicVideo>, YouTubeError> {
+        let response = self
+            .http
+            .get(self.watch_url(video_id))
+            .header("Accept-Language", "en")
+            .send()
+            .await?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(YouTubeError::Api {
+                status: status.as_u16(),
+                message: "watch page unavailable".into(),
+            });
+        }
+        Ok(parse_page(&response.text().await?))
+    }
+
+    /// Fetches title, channel and thumbnail of a video, and its description if
+    /// asked for. oEmbed is preferred, the watch page is only loaded to fill in
+    /// what oEmbed lacks or to stand in when it fails, e.g. for videos that may
+    /// not be embedded. Returns None if neither knows the video.
+    pub async fn video(
+        &self,
+        video_id: &str,
+        description: bool,
+    ) -> Result<Option<PublicVideo>, YouTubeError> {
+        let oembed = match self.oembed(video_id).await {
+            Ok(oembed) => oembed,
+            Err(e) => return self.page(video_id).await.map_err(|_| e),
+        };
+        let complete =
+            oembed.author_name.is_some() && oembed.thumbnail_url.is_some() && !description;
+        let page = if complete {
+            None
+        } else {
+            self.page(video_id).await.ok().flatten()
+        };
+        Ok(Some(merge(oembed, page)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_server::{self, Route};
+
+    const WATCH_PAGE: &str = r#"<!DOCTYPE html><html><head>
+<meta name="title" content="Never Gonna Give You Up">
+<meta property="og:title" content="Rick Astley - Never Gonna Give You Up (Official Video)">
+<meta property="og:image" content="https://i.ytimg.com/vi/dQw4w9WgXcQ/maxresdefault.jpg">
+<meta property="og:description" content="The official video for &quot;Never Gonna Give You Up&quot; &amp; more">
+<link rel="canonical" href="https://www.youtube.com/watch?v=dQw4w9WgXcQ">
+</head><body>
+<span itemprop="author" itemscope itemtype="http://schema.org/Person">
+<link itemprop="url" href="http://www.youtube.com/@RickAstleyYT">
+<link itemprop="name" content="Rick Astley">
+</span>
+</body></html>"#;
+
+    #[test]
+    fn parses_watch_page() {
+        let video = parse_page(WATCH_PAGE).unwrap();
+        assert_eq!(
+            video.title,
+            "Rick Astley - Never Gonna Give You Up (Official Video)"
+        );
+        assert_eq!(video.channel_title.as_deref(), Some("Rick Astley"));
+        assert_eq!(
+            video.thumbnail.as_deref(),
+            Some("https://i.ytimg.com/vi/dQw4w9WgXcQ/maxresdefault.jpg")
+        );
+        assert_eq!(
+            video.description.as_deref(),
+            Some("The official video for \"Never Gonna Give You Up\" & more")
+        );
+
+        assert!(parse_page("<html><head><title>YouTube</title></head></html>").is_none());
+    }
+
+    #[tokio::test]
+    async fn oembed_is_completed_by_the_watch_page() {
+        let base_url = test_server::serve(vec![
+            Route::ok(
+                "/oembed",
+                "application/json",
+                vec![r#"{"title":"From oEmbed","author_url":"https://www.youtube.com/@RickAstleyYT"}"#],
+            ),
+            Route::ok("/watch", "text/html", vec![WATCH_PAGE]),
+        ])
+        .await;
+        let video = PublicClient::with_base_url(base_url)
+            .video("dQw4w9WgXcQ", false)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(video.title, "From oEmbed");
+        assert_eq!(video.channel_title.as_deref(), Some("Rick Astley"));
+        assert_eq!(
+            video.channel_url.as_deref(),
+            Some("https://www.youtube.com/@RickAstleyYT")
+        );
+        assert!(video.thumbnail.unwrap().ends_with("maxresdefault.jpg"));
+        assert!(video.description.is_some());
+    }
+
+    #[tokio::test]
+    async fn complete_oembed_skips_the_watch_page() {
+        let oembed = r#"{"title":"From oEmbed","author_name":"Rick","thumbnail_url":"https://i.ytimg.com/hq.jpg"}"#;
+        let base_url = test_server::serve(vec![
+            Route::ok("/oembed", "application/json", vec![oembed]),
+            Route::ok("/watch", "text/html", vec![WATCH_PAGE]),
+        ])
+        .await;
+        let client = PublicClient::with_base_url(base_url);
+
+        // The page's description only shows up when it is asked for
+        let video = client.video("dQw4w9WgXcQ", false).await.unwrap().unwrap();
+        assert_eq!(video.channel_title.as_deref(), Some("Rick"));
+        assert_eq!(
+            video.thumbnail.as_deref(),
+            Some("https://i.ytimg.com/hq.jpg")
+        );
+        assert!(video.description.is_none());
+
+        let video = client.video("dQw4w9WgXcQ", true).await.unwrap().unwrap();
+        assert_eq!(video.channel_title.as_deref(), Some("Rick"));
+        assert!(video.description.is_some());
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_the_watch_page() {
+        let base_url =
+            test_server::serve(vec![Route::ok("/watch", "text/html", vec![WATCH_PAGE])]).await;
+        let video = PublicClient::with_base_url(base_url)
+            .video("dQw4w9WgXcQ", false)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(video.channel_title.as_deref(), Some("Rick Astley"));
+
+        let base_url = test_server::serve(Vec::new()).await;
+        let result = PublicClient::with_base_url(base_url)
+            .video("dQw4w9WgXcQ", false)
+            .await;
+        assert!(matches!(result, Err(YouTubeError::Api { status: 404, .. })));
+    }
+}
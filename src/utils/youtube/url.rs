@@ -0,0 +1,238 @@
+use std::time::Duration;
+
+/// What a YouTube link points to, with its playback options.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct YouTubeLink {
+    pub video_id: Option<String>,
+    pub playlist_id: Option<String>,
+    /// Requested start time from `t=` or `start=`.
+    pub start: Option<Duration>,
+}
+
+/// Hosts serving YouTube pages, without `www.`, `m.` or `music.`.
+const HOSTS: [&str; 3] = ["youtube.com", "youtu.be", "youtube-nocookie.com"];
+/// Path prefixes followed by a video id, like `/shorts/<id>`.
+const VIDEO_PATHS: [&str; 5] = ["shorts", "live", "embed", "v", "e"];
+/// Prefixes of playlist ids, e.g. `PL` for user playlists and `RD` for mixes.
+const PLAYLIST_PREFIXES: [&str; 9] = ["PL", "UU", "LL", "FL", "RD", "OL", "UL", "PU", "EL"];
+
+fn is_video_id(id: &str) -> bool {
+    id.len() == 11
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+fn is_playlist_id(id: &str) -> bool {
+    id.len() >= 12
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Parses a start time like `90`, `90s`, `1m30s` or `1h2m3s`.
+pub fn parse_timestamp(value: &str) -> Option<Duration> {
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+
+    let mut seconds = 0;
+    let mut number = String::new();
+    for c in value.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let amount: u64 = number.parse().ok()?;
+        number.clear();
+        seconds += amount
+            * match c {
+                'h' => 60 * 60,
+                'm' => 60,
+                's' => 1,
+                _ => return None,
+            };
+    }
+    number.is_empty().then_some(Duration::from_secs(seconds))
+}
+
+/// Splits `a=1&b=2` into its pairs.
+fn query_pairs(query: &str) -> impl Iterator<Item = (&str, &str)> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| pair.split_once('=').unwrap_or((pair, "")))
+}
+
+/// Parses a YouTube video, shorts, live, embed, music or playlist URL,
+/// or a bare video or playlist id. Returns None for anything else.
+pub fn parse_url(input: &str) -> Option<YouTubeLink> {
+    let input = input.trim().trim_start_matches('<').trim_end_matches('>');
+    if is_video_id(input) {
+        return Some(YouTubeLink {
+            video_id: Some(input.to_string()),
+            ..Default::default()
+        });
+    }
+    // Without `list=` only ids with a known prefix are taken as playlists
+    if is_playlist_id(input) && PLAYLIST_PREFIXES.iter().any(|p| input.starts_with(p)) {
+        return Some(YouTubeLink {
+            playlist_id: Some(input.to_string()),
+            ..Default::default()
+        });
+    }
+
+    let rest = input
+        .split_once("://")
+        .map_or(input, |(scheme, rest)| match scheme {
+            "http" | "https" => rest,
+            _ => "",
+        });
+    let host_end = rest.find(['/', '?', '#']).unwrap_or(rest.len());
+    let (host, rest) = rest.split_at(host_end);
+    let host = host.to_lowercase();
+    let host = ["www.", "m.", "music."]
+        .iter()
+        .find_map(|prefix| host.strip_prefix(prefix))
+        .unwrap_or(&host);
+    if !HOSTS.contains(&host) {
+        return None;
+    }
+
+    let (rest, fragment) = rest.split_once('#').unwrap_or((rest, ""));
+    let (path, query) = rest.split_once('?').unwrap_or((rest, ""));
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+
+    let mut link = YouTubeLink::default();
+    for (key, value) in query_pairs(query).chain(query_pairs(fragment)) {
+        match key {
+            "v" if is_video_id(value) => link.video_id = Some(value.to_string()),
+            "list" if is_playlist_id(value) => link.playlist_id = Some(value.to_string()),
+            "t" | "start" => link.start = parse_timestamp(value).or(link.start),
+            _ => {}
+        }
+    }
+
+    match (host, segments.as_slice()) {
+        ("youtu.be", [id, ..]) if is_video_id(id) => link.video_id = Some(id.to_string()),
+        // `/embed/videoseries?list=...` embeds a playlist
+        (_, [kind, id, ..])
+            if VIDEO_PATHS.contains(kind) && is_video_id(id) && *id != "videoseries" =>
+        {
+            link.video_id = Some(id.to_string())
+        }
+        _ => {}
+    }
+
+    (link.video_id.is_some() || link.playlist_id.is_some()).then_some(link)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ID: &str = "dQw4w9WgXcQ";
+    const LIST: &str = "PLx0sYbCqOb8TBPRdmBHs5Iftvv9TPboYG";
+
+    fn link(video: Option<&str>, list: Option<&str>, start: Option<u64>) -> Option<YouTubeLink> {
+        Some(YouTubeLink {
+            video_id: video.map(str::to_string),
+            playlist_id: list.map(str::to_string),
+            start: start.map(Duration::from_secs),
+        })
+    }
+
+    #[test]
+    fn parses_video_urls() {
+        let video = link(Some(ID), None, None);
+        for url in [
+            "dQw4w9WgXcQ",
+            "https://www.youtube.com/watch?v=dQw4w9WgXcQ",
+            "http://youtube.com/watch?feature=share&v=dQw4w9WgXcQ",
+            "youtube.com/watch?v=dQw4w9WgXcQ",
+            "<https://www.youtube.com/watch?v=dQw4w9WgXcQ>",
+            "https://m.youtube.com/watch?v=dQw4w9WgXcQ",
+            "https://music.youtube.com/watch?v=dQw4w9WgXcQ",
+            "https://WWW.YouTube.com/watch?v=dQw4w9WgXcQ",
+            "https://www.youtube.com/shorts/dQw4w9WgXcQ",
+            "https://www.youtube.com/live/dQw4w9WgXcQ?feature=share",
+            "https://www.youtube.com/embed/dQw4w9WgXcQ",
+            "https://www.youtube.com/v/dQw4w9WgXcQ",
+            "https://www.youtube.com/e/dQw4w9WgXcQ",
+            "https://youtu.be/dQw4w9WgXcQ",
+            "https://www.youtube-nocookie.com/embed/dQw4w9WgXcQ",
+        ] {
+            assert_eq!(parse_url(url), video, "{}", url);
+        }
+    }
+
+    #[test]
+    fn parses_start_times() {
+        for (url, start) in [
+            ("https://youtu.be/dQw4w9WgXcQ?t=42", 42),
+            ("https://youtu.be/dQw4w9WgXcQ?t=1m30s", 90),
+            ("https://www.youtube.com/watch?v=dQw4w9WgXcQ&t=1h2m3s", 3723),
+            ("https://www.youtube.com/embed/dQw4w9WgXcQ?start=15", 15),
+            ("https://www.youtube.com/watch?v=dQw4w9WgXcQ#t=90s", 90),
+            ("https://youtu.be/dQw4w9WgXcQ?t=10#t=20", 20),
+        ] {
+            assert_eq!(parse_url(url), link(Some(ID), None, Some(start)), "{}", url);
+        }
+        assert_eq!(
+            parse_url("https://youtu.be/dQw4w9WgXcQ?t=soon"),
+            link(Some(ID), None, None)
+        );
+    }
+
+    #[test]
+    fn parses_playlists() {
+        let list = link(None, Some(LIST), None);
+        for url in [
+            LIST,
+            "https://www.youtube.com/playlist?list=PLx0sYbCqOb8TBPRdmBHs5Iftvv9TPboYG",
+            "https://music.youtube.com/playlist?list=PLx0sYbCqOb8TBPRdmBHs5Iftvv9TPboYG",
+            "https://www.youtube.com/embed/videoseries?list=PLx0sYbCqOb8TBPRdmBHs5Iftvv9TPboYG",
+        ] {
+            assert_eq!(parse_url(url), list, "{}", url);
+        }
+        assert_eq!(
+            parse_url(
+                "https://www.youtube.com/watch?v=dQw4w9WgXcQ&list=PLx0sYbCqOb8TBPRdmBHs5Iftvv9TPboYG"
+            ),
+            link(Some(ID), Some(LIST), None)
+        );
+        // Ids from `list=` are taken as they are, bare ones need a known prefix
+        assert_eq!(
+            parse_url("https://www.youtube.com/playlist?list=OLAK5uy_abcdefghijkl"),
+            link(None, Some("OLAK5uy_abcdefghijkl"), None)
+        );
+        assert_eq!(
+            parse_url("https://www.youtube.com/playlist?list=WL12345678901"),
+            link(None, Some("WL12345678901"), None)
+        );
+        assert_eq!(
+            parse_url("RDdQw4w9WgXcQ"),
+            link(None, Some("RDdQw4w9WgXcQ"), None)
+        );
+    }
+
+    #[test]
+    fn rejects_other_input() {
+        for input in [
+            "",
+            "hello",
+            "definitelynotaplaylist",
+            "https://example.com/watch?v=dQw4w9WgXcQ",
+            "https://youtube.com.evil.com/watch?v=dQw4w9WgXcQ",
+            "https://notyoutube.com/watch?v=dQw4w9WgXcQ",
+            "https://gaming.youtube.com/watch?v=dQw4w9WgXcQ",
+            "ftp://youtube.com/watch?v=dQw4w9WgXcQ",
+            "https://www.youtube.com/watch?v=short",
+            "https://www.youtube.com/channel/UCuAXFkgsw1L7xaCfnd5JJOw",
+            "https://www.youtube.com/",
+            "https://youtu.be/",
+        ] {
+            assert_eq!(parse_url(input), None, "{}", input);
+        }
+    }
+}